@@ -0,0 +1,292 @@
+use ash::vk;
+use std::sync::Arc;
+use winit::dpi::LogicalSize;
+use winit::event_loop::EventLoop;
+use winit::raw_window_handle::HasDisplayHandle;
+use winit::window::{Window, WindowAttributes};
+
+use crate::application::window::WindowApp;
+use crate::error::{Error, Result};
+use crate::renderer::debug::DebugConfig;
+use crate::renderer::instance::{AshInstance, ExtensionRequest};
+use crate::renderer::selection::DeviceConfig;
+use crate::renderer::window_target::TargetConfig;
+use crate::widget::base::Widget;
+use crate::widget::flex::Flex;
+
+const DEFAULT_TITLE: &str = "Ash Application";
+const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+/// Which renderer an [`App`] draws with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Vulkan, or software rendering if Vulkan cannot be loaded or no GPU
+    /// is suitable.
+    #[default]
+    Auto,
+    /// Vulkan only; failing to set it up is an error.
+    Vulkan,
+    /// Draws on the CPU and presents through the platform's window
+    /// surface, for machines without a GPU.
+    Software,
+}
+
+impl Backend {
+    /// Whether `err`, from setting up Vulkan, should make this backend
+    /// render in software instead.
+    pub(crate) fn falls_back_from(self, err: &Error) -> bool {
+        self == Backend::Auto
+            && matches!(
+                err,
+                Error::Loading(_)
+                    | Error::NoSuitableDevice
+                    | Error::NoMemoryType { .. }
+                    | Error::MissingExtension(_)
+                    | Error::Vulkan(
+                        vk::Result::ERROR_INCOMPATIBLE_DRIVER
+                            | vk::Result::ERROR_INITIALIZATION_FAILED
+                    )
+            )
+    }
+}
+
+/// A configured application, ready to [`run`](Self::run).
+pub struct App {
+    title: String,
+    attributes: WindowAttributes,
+    backend: Backend,
+    config: TargetConfig,
+    debug: DebugConfig,
+    device: DeviceConfig,
+    max_frames_in_flight: usize,
+    root: Box<dyn Widget>,
+}
+
+impl App {
+    pub fn builder() -> AppBuilder {
+        AppBuilder::new()
+    }
+
+    /// Opens the window and runs the event loop until the window is closed
+    /// or rendering fails.
+    pub fn run(self) -> Result<()> {
+        let event_loop = EventLoop::new().map_err(|err| Error::Window(err.to_string()))?;
+
+        let instance = match self.backend {
+            Backend::Software => None,
+            backend => match create_instance(&event_loop, &self.title, self.debug) {
+                Ok(instance) => Some(Arc::new(instance)),
+                Err(err) if backend.falls_back_from(&err) => {
+                    log::warn!("{}; rendering in software", err);
+                    None
+                }
+                Err(err) => return Err(err),
+            },
+        };
+
+        let mut app = WindowApp::new(
+            instance,
+            self.backend,
+            self.attributes,
+            self.config,
+            self.device,
+            self.max_frames_in_flight,
+            self.root,
+        );
+        event_loop
+            .run_app(&mut app)
+            .map_err(|err| Error::Window(err.to_string()))?;
+        app.take_error().map_or(Ok(()), Err)
+    }
+}
+
+/// Creates an instance that can present to windows on `event_loop`'s
+/// display.
+fn create_instance(
+    event_loop: &EventLoop<()>,
+    title: &str,
+    debug: DebugConfig,
+) -> Result<AshInstance> {
+    let display_handle = event_loop
+        .display_handle()
+        .map_err(|err| Error::Window(err.to_string()))?;
+    let extensions = ExtensionRequest::for_display(display_handle.as_raw())?;
+    AshInstance::with_config(title, &extensions, debug)
+}
+
+/// Builds an [`App`]. Sizes are in logical pixels.
+pub struct AppBuilder {
+    title: String,
+    attributes: WindowAttributes,
+    backend: Backend,
+    config: TargetConfig,
+    debug: DebugConfig,
+    device: DeviceConfig,
+    max_frames_in_flight: usize,
+    root: Option<Box<dyn Widget>>,
+}
+
+impl AppBuilder {
+    pub fn new() -> Self {
+        AppBuilder {
+            title: DEFAULT_TITLE.to_string(),
+            attributes: Window::default_attributes(),
+            backend: Backend::default(),
+            config: TargetConfig::default(),
+            debug: DebugConfig::default(),
+            device: DeviceConfig::default(),
+            max_frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            root: None,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// The initial size of the window's contents.
+    pub fn size(mut self, width: f32, height: f32) -> Self {
+        self.attributes = self
+            .attributes
+            .with_inner_size(LogicalSize::new(width, height));
+        self
+    }
+
+    pub fn min_size(mut self, width: f32, height: f32) -> Self {
+        self.attributes = self
+            .attributes
+            .with_min_inner_size(LogicalSize::new(width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: f32, height: f32) -> Self {
+        self.attributes = self
+            .attributes
+            .with_max_inner_size(LogicalSize::new(width, height));
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.attributes = self.attributes.with_resizable(resizable);
+        self
+    }
+
+    /// Whether the window has a title bar and borders.
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.attributes = self.attributes.with_decorations(decorations);
+        self
+    }
+
+    /// Lets what is behind the window show through where nothing is drawn.
+    /// Not every platform supports this.
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.attributes = self.attributes.with_transparent(transparent);
+        self.config.transparent = transparent;
+        self
+    }
+
+    /// Which renderer to draw with. By default Vulkan, falling back to
+    /// software rendering where it cannot be used.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// The preferred present mode, such as `FIFO` for vsync. Defaults to
+    /// `MAILBOX`; FIFO is used if the preference is unsupported.
+    pub fn present_mode(mut self, present_mode: vk::PresentModeKHR) -> Self {
+        self.config.present_mode = present_mode;
+        self
+    }
+
+    /// Samples per pixel for anti-aliasing; 1 turns it off. Rounded down to
+    /// a power of two the device supports.
+    pub fn msaa(mut self, samples: u32) -> Self {
+        let samples = samples.clamp(1, 64);
+        self.config.samples = vk::SampleCountFlags::from_raw(1 << samples.ilog2());
+        self
+    }
+
+    /// Whether the window has a depth buffer, in a format the device
+    /// supports. On by default; pure 2D UIs can turn it off.
+    pub fn depth(mut self, depth: bool) -> Self {
+        self.config.depth = depth;
+        self
+    }
+
+    /// Validation and other debugging aids. By default validation is on in
+    /// debug builds.
+    pub fn debug(mut self, debug: DebugConfig) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Which GPU to render with. By default the best suitable one, unless
+    /// the `UPLIFT_DEVICE` environment variable names another.
+    pub fn device(mut self, device: DeviceConfig) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// How many frames the CPU may record ahead of the GPU, at least 1.
+    pub fn frames_in_flight(mut self, frames: usize) -> Self {
+        self.max_frames_in_flight = frames.max(1);
+        self
+    }
+
+    /// The widget tree to show, laid out to fill the window. An empty
+    /// column by default.
+    pub fn root(mut self, root: impl Widget + 'static) -> Self {
+        self.root = Some(Box::new(root));
+        self
+    }
+
+    pub fn build(self) -> App {
+        App {
+            attributes: self.attributes.with_title(self.title.clone()),
+            title: self.title,
+            backend: self.backend,
+            config: self.config,
+            debug: self.debug,
+            device: self.device,
+            max_frames_in_flight: self.max_frames_in_flight,
+            root: self.root.unwrap_or_else(|| Box::new(Flex::column())),
+        }
+    }
+
+    /// Builds the app and runs it.
+    pub fn run(self) -> Result<()> {
+        self.build().run()
+    }
+}
+
+impl Default for AppBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_auto_falls_back_when_vulkan_is_unusable() {
+        let unusable = [
+            Error::NoSuitableDevice,
+            Error::NoMemoryType {
+                properties: vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            },
+            Error::MissingExtension("VK_KHR_surface".to_string()),
+            Error::Vulkan(vk::Result::ERROR_INCOMPATIBLE_DRIVER),
+        ];
+        for err in &unusable {
+            assert!(Backend::Auto.falls_back_from(err), "{}", err);
+            assert!(!Backend::Vulkan.falls_back_from(err), "{}", err);
+        }
+
+        assert!(!Backend::Auto.falls_back_from(&Error::DeviceLost));
+        assert!(!Backend::Auto.falls_back_from(&Error::Window("no display".to_string())));
+    }
+}
@@ -0,0 +1,188 @@
+use ash::vk;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::renderer::buffer::Buffer;
+use crate::renderer::canvas::Canvas;
+use crate::renderer::command::{CommandBuffers, CommandPool};
+use crate::renderer::depth::find_depth_format;
+use crate::renderer::device::AshDevice;
+use crate::renderer::framebuffer::Framebuffers;
+use crate::renderer::image_io::save_png;
+use crate::renderer::instance::AshInstance;
+use crate::renderer::memory::OwnedBuffer;
+use crate::renderer::offscreen::{OffscreenTarget, OFFSCREEN_COLOR_FORMAT};
+use crate::renderer::pipeline::Pipeline;
+use crate::renderer::primitive::Image;
+use crate::renderer::render_pass::RenderPass;
+use crate::renderer::text_pipeline::TextPipeline;
+use crate::renderer::texture::AtlasTexture;
+use crate::renderer::upload::{UploadManager, STAGING_RING_SIZE};
+use crate::renderer::vulkan_canvas::{TextResources, VulkanCanvas};
+use crate::text::atlas::GlyphAtlas;
+
+/// Side length of the glyph atlas texture.
+const ATLAS_SIZE: u32 = 1024;
+
+/// Renders a single frame without a window or swapchain and reads it back.
+///
+/// Only a graphics queue is required, so this runs on CPU implementations
+/// such as lavapipe on machines without a display server. Everything created
+/// is destroyed on return, in reverse order of creation.
+///
+/// `depth` gives the frame a depth attachment, like
+/// [`TargetConfig::depth`](crate::renderer::window_target::TargetConfig::depth)
+/// does for windows. `paint` runs a second time if the glyph atlas fills
+/// up, after it has been cleared.
+pub fn render_to_image(
+    width: u32,
+    height: u32,
+    depth: bool,
+    mut paint: impl FnMut(&mut dyn Canvas),
+) -> Result<Image> {
+    let instance = Arc::new(AshInstance::new("Ash Headless")?);
+    let device = AshDevice::new_headless(&instance)?;
+    let extent = vk::Extent2D { width, height };
+    let depth_format = if depth {
+        Some(find_depth_format(&device)?)
+    } else {
+        None
+    };
+
+    let render_pass =
+        RenderPass::new_offscreen(&device.device, OFFSCREEN_COLOR_FORMAT, depth_format)?;
+    let target = OffscreenTarget::new(&device, extent, depth_format)?;
+    let framebuffers = Framebuffers::new(
+        &device.device,
+        render_pass.render_pass,
+        &[target.color.view],
+        target.depth.as_ref().map(|depth| depth.view),
+        extent,
+    )?;
+    let pipeline = Pipeline::new(&device.device, render_pass.render_pass, render_pass.samples)?;
+    let text_pipeline =
+        TextPipeline::new(&device.device, render_pass.render_pass, render_pass.samples)?;
+
+    let mut atlas = GlyphAtlas::new(ATLAS_SIZE, ATLAS_SIZE);
+    let atlas_texture = AtlasTexture::new(
+        &device,
+        vk::Extent2D {
+            width: ATLAS_SIZE,
+            height: ATLAS_SIZE,
+        },
+        text_pipeline.descriptor_set_layout,
+    )?;
+
+    let mut uploads = UploadManager::new(&device, STAGING_RING_SIZE)?;
+    let command_pool = CommandPool::new(
+        &device.device,
+        device.queue_family_indices.graphics_family.unwrap(),
+    )?;
+    let command_buffers = CommandBuffers::new(&device.device, &command_pool, 1)?;
+    let command_buffer = command_buffers.buffers[0];
+
+    let mut canvas = VulkanCanvas::new(&device.device, command_buffer, &pipeline, extent)
+        .with_text(TextResources {
+            pipeline: &text_pipeline,
+            texture: &atlas_texture,
+            atlas: &mut atlas,
+        });
+    paint(&mut canvas);
+    if canvas.atlas_overflowed() {
+        canvas.reset_and_clear_atlas();
+        paint(&mut canvas);
+    }
+
+    let mut vertex_buffer = Buffer::vertex(&device, canvas.vertices())?;
+    vertex_buffer.set_name("vertex buffer");
+    let mut text_vertex_buffer = Buffer::vertex(&device, canvas.text_vertices())?;
+    text_vertex_buffer.set_name("text vertex buffer");
+
+    let readback_buffer = OwnedBuffer::new(
+        &device,
+        target.readback_size(),
+        vk::BufferUsageFlags::TRANSFER_DST,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+    )?;
+    readback_buffer.set_name("readback buffer");
+
+    let clear_values = [
+        vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        },
+        vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        },
+    ];
+
+    let render_pass_info = vk::RenderPassBeginInfo::default()
+        .render_pass(render_pass.render_pass)
+        .framebuffer(framebuffers.framebuffers[0])
+        .render_area(vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent,
+        })
+        // One clear value per attachment.
+        .clear_values(&clear_values[..1 + depth_format.is_some() as usize]);
+
+    let begin_info =
+        vk::CommandBufferBeginInfo::default().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    unsafe {
+        device
+            .device
+            .begin_command_buffer(command_buffer, &begin_info)?;
+        device.device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_info,
+            vk::SubpassContents::INLINE,
+        );
+        canvas.finish(vertex_buffer.handle(), text_vertex_buffer.handle());
+        device.device.cmd_end_render_pass(command_buffer);
+        target.record_readback(&device.device, command_buffer, readback_buffer.buffer);
+        device.device.end_command_buffer(command_buffer)?;
+
+        if atlas.take_dirty() {
+            atlas_texture.upload(&device, &mut uploads, &atlas.data)?;
+            uploads.flush()?;
+        }
+
+        let command_buffers = [command_buffer];
+        let submit_info = vk::SubmitInfo::default().command_buffers(&command_buffers);
+        let fence = device
+            .device
+            .create_fence(&vk::FenceCreateInfo::default(), None)?;
+        let result = device
+            .device
+            .queue_submit(device.graphics_queue, &[submit_info], fence)
+            .and_then(|_| device.device.wait_for_fences(&[fence], true, u64::MAX));
+        device.device.destroy_fence(fence, None);
+        result?;
+    }
+
+    let mut pixels = vec![0u8; target.readback_size() as usize];
+    readback_buffer.read(&mut pixels)?;
+    instance.check_validation();
+
+    Ok(Image::new(width, height, pixels))
+}
+
+/// Renders a single frame headlessly and writes it to `path` as a PNG.
+pub fn render_to_png(
+    width: u32,
+    height: u32,
+    depth: bool,
+    path: impl AsRef<Path>,
+    paint: impl FnMut(&mut dyn Canvas),
+) -> std::result::Result<(), Box<dyn Error>> {
+    let image = render_to_image(width, height, depth, paint)?;
+    save_png(&image, path)?;
+    Ok(())
+}
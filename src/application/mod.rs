@@ -1,2 +1,4 @@
+pub mod app;
 pub mod run;
-pub mod window;
\ No newline at end of file
+pub mod window;
+pub mod headless;
\ No newline at end of file
@@ -1,6 +1,316 @@
-
+use ash::vk;
+use std::sync::Arc;
 use winit::application::ApplicationHandler;
-use winit::event::WindowEvent;
-use winit::event_loop::{ActiveEventLoop, EventLoop};
-use winit::window::{Window, WindowId};
+use winit::dpi::PhysicalSize;
+use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event_loop::ActiveEventLoop;
+use winit::window::{Window, WindowAttributes, WindowId};
+
+use crate::application::app::Backend;
+use crate::error::{Error, Result};
+use crate::renderer::canvas::ScaledCanvas;
+use crate::renderer::device::AshDevice;
+use crate::renderer::instance::AshInstance;
+use crate::renderer::primitive::{Color, Size};
+use crate::renderer::selection::DeviceConfig;
+use crate::renderer::software::rasterizer::SoftwareRenderer;
+use crate::renderer::software::surface::SoftwareSurface;
+use crate::renderer::surface::Surface;
+use crate::renderer::window_renderer::WindowRenderer;
+use crate::renderer::window_target::{TargetConfig, WindowTarget};
+use crate::widget::base::{
+    dispatch_event, layout_root, mount_tree, paint_tree, unmount_tree, Widget,
+};
+use crate::widget::event::{Event, PointerButton};
+
+/// A winit application showing a widget tree in a single window.
+///
+/// The window is created on the first `resumed`, along with the device.
+/// The surface and everything drawing to it are torn down on `suspended`
+/// and rebuilt on the next `resumed`, as platforms such as Android require.
+///
+/// Without a Vulkan instance, or if [`Backend::Auto`] finds no usable GPU,
+/// frames are drawn in software instead.
+///
+/// If the renderer fails, the event loop exits and the error is kept for
+/// [`Self::take_error`].
+pub struct WindowApp {
+    /// `None` when rendering in software.
+    instance: Option<Arc<AshInstance>>,
+    backend: Backend,
+    attributes: WindowAttributes,
+    config: TargetConfig,
+    device_config: DeviceConfig,
+    max_frames_in_flight: usize,
+    root: Box<dyn Widget>,
+    // Dropped in this order: the surface before the window it belongs to.
+    renderer: Option<Renderer>,
+    device: Option<AshDevice>,
+    window: Option<Arc<Window>>,
+    /// Last pointer position in logical pixels.
+    pointer: (f32, f32),
+    error: Option<Error>,
+}
+
+impl WindowApp {
+    /// `instance` must have the surface extensions for the event loop's
+    /// display enabled. Without one, frames are drawn in software.
+    pub fn new(
+        instance: Option<Arc<AshInstance>>,
+        backend: Backend,
+        attributes: WindowAttributes,
+        config: TargetConfig,
+        device_config: DeviceConfig,
+        max_frames_in_flight: usize,
+        mut root: Box<dyn Widget>,
+    ) -> Self {
+        mount_tree(root.as_mut());
+        WindowApp {
+            instance,
+            backend,
+            attributes,
+            config,
+            device_config,
+            max_frames_in_flight,
+            root,
+            renderer: None,
+            device: None,
+            window: None,
+            pointer: (0.0, 0.0),
+            error: None,
+        }
+    }
+
+    /// The error that stopped the event loop, if any.
+    pub fn take_error(&mut self) -> Option<Error> {
+        self.error.take()
+    }
+
+    /// Stops the event loop because of `error`.
+    fn fail(&mut self, event_loop: &ActiveEventLoop, error: Error) {
+        self.error.get_or_insert(error);
+        event_loop.exit();
+    }
+
+    /// Creates the window if there is none yet, then the device if there is
+    /// none yet, and a renderer drawing to the window.
+    fn create_renderer(&mut self, event_loop: &ActiveEventLoop) -> Result<()> {
+        if self.window.is_none() {
+            let window = event_loop
+                .create_window(self.attributes.clone())
+                .map_err(|err| Error::Window(err.to_string()))?;
+            self.window = Some(Arc::new(window));
+        }
+        let window = self.window.clone().unwrap();
+
+        let renderer = match self.instance.clone() {
+            Some(instance) => match self.create_vulkan_renderer(&instance, &window) {
+                Ok(renderer) => renderer,
+                Err(err) if self.backend.falls_back_from(&err) => {
+                    log::warn!("{}; rendering in software", err);
+                    self.instance = None;
+                    create_software_renderer(window)?
+                }
+                Err(err) => return Err(err),
+            },
+            None => create_software_renderer(window)?,
+        };
+        self.renderer = Some(renderer);
+        Ok(())
+    }
+
+    fn create_vulkan_renderer(
+        &mut self,
+        instance: &Arc<AshInstance>,
+        window: &Window,
+    ) -> Result<Renderer> {
+        // The window is dropped after the renderer, and with it the surface.
+        let surface = unsafe { Surface::from_window(instance, window)? };
+        let device = match &mut self.device {
+            Some(device) => device,
+            device => device.insert(AshDevice::with_config(
+                instance,
+                surface.handle(),
+                &self.device_config,
+            )?),
+        };
+        let target =
+            WindowTarget::new(device, surface, to_extent(window.inner_size()), self.config)?;
+        let mut renderer = WindowRenderer::new(device, target, self.max_frames_in_flight)?;
+
+        if self.config.transparent {
+            renderer.clear_color = [0.0; 4];
+        }
+        Ok(Renderer::Vulkan(Box::new(renderer)))
+    }
+
+    /// Lays the widget tree out to fill the window.
+    fn relayout(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = window.inner_size().to_logical::<f32>(window.scale_factor());
+        layout_root(self.root.as_mut(), Size::new(size.width, size.height));
+        window.request_redraw();
+    }
+
+    fn redraw(&mut self, event_loop: &ActiveEventLoop) {
+        let (Some(window), Some(renderer)) = (&self.window, &mut self.renderer) else {
+            return;
+        };
+
+        let scale = window.scale_factor() as f32;
+        let root = self.root.as_ref();
+        let result = match renderer {
+            Renderer::Vulkan(renderer) => {
+                let Some(device) = &self.device else {
+                    return;
+                };
+                renderer
+                    .draw_frame(device, |canvas| {
+                        paint_tree(root, &mut ScaledCanvas::new(canvas, scale));
+                    })
+                    .map(|_| ())
+            }
+            Renderer::Software { renderer, surface } => {
+                let size = window.inner_size();
+                if (renderer.pixmap.width, renderer.pixmap.height) != (size.width, size.height) {
+                    renderer.resize(size.width, size.height);
+                }
+                renderer.clear(if self.config.transparent {
+                    Color::TRANSPARENT
+                } else {
+                    Color::BLACK
+                });
+                paint_tree(root, &mut ScaledCanvas::new(renderer, scale));
+                surface
+                    .present(&renderer.pixmap)
+                    .map_err(|err| Error::Window(err.to_string()))
+            }
+        };
+
+        match result {
+            Ok(()) => {}
+            // The device is still usable; only the surface needs replacing.
+            Err(Error::SurfaceLost) => {
+                self.destroy_renderer();
+                match self.create_renderer(event_loop) {
+                    Ok(()) => self.relayout(),
+                    Err(err) => self.fail(event_loop, err),
+                }
+            }
+            Err(err) => self.fail(event_loop, err),
+        }
+    }
+
+    /// Converts a winit event into a widget event, if widgets handle it.
+    fn translate_event(&mut self, event: &WindowEvent) -> Option<Event> {
+        let scale = self.window.as_ref()?.scale_factor();
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = position.to_logical::<f32>(scale);
+                self.pointer = (position.x, position.y);
+                Some(Event::PointerMove {
+                    x: position.x,
+                    y: position.y,
+                })
+            }
+            WindowEvent::CursorLeft { .. } => Some(Event::PointerLeave),
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => PointerButton::Primary,
+                    MouseButton::Right => PointerButton::Secondary,
+                    MouseButton::Middle => PointerButton::Middle,
+                    _ => return None,
+                };
+                let (x, y) = self.pointer;
+                Some(match state {
+                    ElementState::Pressed => Event::PointerDown { x, y, button },
+                    ElementState::Released => Event::PointerUp { x, y, button },
+                })
+            }
+            WindowEvent::KeyboardInput { event, .. } if event.state.is_pressed() => event
+                .text
+                .as_ref()
+                .map(|text| Event::Text(text.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Destroys the renderer and its surface, keeping the device.
+    fn destroy_renderer(&mut self) {
+        self.renderer = None;
+    }
+}
+
+impl ApplicationHandler for WindowApp {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        match self.create_renderer(event_loop) {
+            Ok(()) => self.relayout(),
+            Err(err) => self.fail(event_loop, err),
+        }
+    }
+
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        self.destroy_renderer();
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => {
+                // The software renderer follows the window size when drawing.
+                if let Some(Renderer::Vulkan(renderer)) = &mut self.renderer {
+                    renderer.target.resize(to_extent(size));
+                }
+                self.relayout();
+            }
+            WindowEvent::ScaleFactorChanged { .. } => self.relayout(),
+            WindowEvent::RedrawRequested => self.redraw(event_loop),
+            event => {
+                if let Some(event) = self.translate_event(&event) {
+                    dispatch_event(self.root.as_mut(), &event);
+                    // Widgets may have changed in response.
+                    self.relayout();
+                }
+            }
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        unmount_tree(self.root.as_mut());
+        self.destroy_renderer();
+        self.device = None;
+    }
+}
+
+/// What draws a [`WindowApp`]'s frames.
+enum Renderer {
+    Vulkan(Box<WindowRenderer>),
+    /// Draws on the CPU and copies each frame to the window.
+    Software {
+        renderer: SoftwareRenderer,
+        surface: SoftwareSurface,
+    },
+}
+
+fn create_software_renderer(window: Arc<Window>) -> Result<Renderer> {
+    let size = window.inner_size();
+    let surface = SoftwareSurface::new(window).map_err(|err| Error::Window(err.to_string()))?;
+    Ok(Renderer::Software {
+        renderer: SoftwareRenderer::new(size.width, size.height),
+        surface,
+    })
+}
 
+fn to_extent(size: PhysicalSize<u32>) -> vk::Extent2D {
+    vk::Extent2D {
+        width: size.width,
+        height: size.height,
+    }
+}
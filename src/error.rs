@@ -0,0 +1,82 @@
+use std::fmt;
+
+use ash::vk;
+
+/// Errors from creating or using the renderer and its window.
+#[derive(Debug)]
+pub enum Error {
+    /// The Vulkan library could not be loaded.
+    Loading(ash::LoadingError),
+    /// No GPU provides what the renderer needs, such as presenting to the
+    /// window's surface.
+    NoSuitableDevice,
+    /// The GPU has no memory type with all of these properties that a
+    /// resource can be placed in.
+    NoMemoryType { properties: vk::MemoryPropertyFlags },
+    /// A required instance or device extension is not available.
+    MissingExtension(String),
+    /// The platform destroyed the window's surface; it must be recreated.
+    SurfaceLost,
+    /// The GPU stopped responding or was reset. The device and everything
+    /// created from it must be recreated.
+    DeviceLost,
+    /// Host or device memory ran out.
+    OutOfMemory,
+    /// A shader could not be loaded.
+    Shader(String),
+    /// The windowing system failed, e.g. there is no display to connect to.
+    Window(String),
+    /// Any other failed Vulkan call.
+    Vulkan(vk::Result),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Loading(err) => write!(f, "failed to load Vulkan: {}", err),
+            Error::NoSuitableDevice => write!(f, "no suitable GPU found"),
+            Error::NoMemoryType { properties } => {
+                write!(f, "no GPU memory type with properties {:?}", properties)
+            }
+            Error::MissingExtension(name) => write!(f, "missing Vulkan extension {}", name),
+            Error::SurfaceLost => write!(f, "the window surface was lost"),
+            Error::DeviceLost => write!(f, "the GPU device was lost"),
+            Error::OutOfMemory => write!(f, "out of memory"),
+            Error::Shader(message) => write!(f, "invalid shader: {}", message),
+            Error::Window(message) => write!(f, "windowing error: {}", message),
+            Error::Vulkan(result) => write!(f, "Vulkan call failed: {}", result),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Loading(err) => Some(err),
+            Error::Vulkan(result) => Some(result),
+            _ => None,
+        }
+    }
+}
+
+impl From<vk::Result> for Error {
+    fn from(result: vk::Result) -> Self {
+        match result {
+            vk::Result::ERROR_OUT_OF_HOST_MEMORY | vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => {
+                Error::OutOfMemory
+            }
+            vk::Result::ERROR_DEVICE_LOST => Error::DeviceLost,
+            vk::Result::ERROR_SURFACE_LOST_KHR => Error::SurfaceLost,
+            vk::Result::ERROR_INVALID_SHADER_NV => Error::Shader(result.to_string()),
+            result => Error::Vulkan(result),
+        }
+    }
+}
+
+impl From<ash::LoadingError> for Error {
+    fn from(err: ash::LoadingError) -> Self {
+        Error::Loading(err)
+    }
+}
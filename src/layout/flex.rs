@@ -0,0 +1,991 @@
+use crate::renderer::primitive::{Rect, Size};
+
+use super::style::{Dimension, Edges, SizeConstraints};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlexDirection {
+    #[default]
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlexWrap {
+    #[default]
+    NoWrap,
+    Wrap,
+    WrapReverse,
+}
+
+/// Distribution of free space along the main axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JustifyContent {
+    #[default]
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+/// Placement of an item on the cross axis of its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlignItems {
+    Start,
+    End,
+    Center,
+    #[default]
+    Stretch,
+}
+
+/// Distribution of lines along the cross axis when wrapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlignContent {
+    Start,
+    End,
+    Center,
+    #[default]
+    Stretch,
+    SpaceBetween,
+    SpaceAround,
+}
+
+/// Properties of a flex container.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FlexStyle {
+    pub direction: FlexDirection,
+    pub wrap: FlexWrap,
+    pub justify_content: JustifyContent,
+    pub align_items: AlignItems,
+    pub align_content: AlignContent,
+    /// Space between rows (lines in a row container, items in a column).
+    pub row_gap: f32,
+    /// Space between columns (items in a row container, lines in a column).
+    pub column_gap: f32,
+    pub padding: Edges,
+}
+
+/// Properties of a child of a flex container.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexItem {
+    pub grow: f32,
+    pub shrink: f32,
+    /// The initial main size; `Auto` uses the measured content size.
+    pub basis: Dimension,
+    pub margin: Edges,
+    pub constraints: SizeConstraints,
+    /// Overrides the container's `align_items` for this item.
+    pub align_self: Option<AlignItems>,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        FlexItem {
+            grow: 0.0,
+            shrink: 1.0,
+            basis: Dimension::Auto,
+            margin: Edges::ZERO,
+            constraints: SizeConstraints::UNBOUNDED,
+            align_self: None,
+        }
+    }
+}
+
+/// Computes the rectangle of every item of a flex container occupying
+/// `bounds`.
+///
+/// `content` holds each item's measured size and is used wherever a size
+/// is `Auto`. The returned rectangles exclude margins.
+pub fn compute_flex(
+    style: &FlexStyle,
+    items: &[FlexItem],
+    content: &[Size],
+    bounds: Rect,
+) -> Vec<Rect> {
+    assert_eq!(
+        items.len(),
+        content.len(),
+        "Every flex item needs a content size"
+    );
+
+    let axes = Axes::new(style);
+    let inner = style.padding.inset(bounds);
+    let main_avail = axes.main(inner.size());
+    let cross_avail = axes.cross(inner.size());
+
+    let resolved: Vec<ResolvedItem> = items
+        .iter()
+        .zip(content)
+        .map(|(item, &content)| ResolvedItem::new(item, content, &axes, main_avail))
+        .collect();
+
+    let lines = break_lines(style, &axes, &resolved, main_avail);
+
+    let mut main_sizes = vec![0.0; items.len()];
+    for line in &lines {
+        resolve_flexible_lengths(line, &resolved, axes.main_gap, main_avail, &mut main_sizes);
+    }
+
+    // Cross size of each line: a single-line container uses all the space.
+    let mut line_cross: Vec<f32> = if style.wrap == FlexWrap::NoWrap {
+        vec![cross_avail]
+    } else {
+        lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|&i| resolved[i].hypothetical_cross + resolved[i].margin_cross())
+                    .fold(0.0, f32::max)
+            })
+            .collect()
+    };
+
+    let used_cross: f32 =
+        line_cross.iter().sum::<f32>() + axes.cross_gap * lines.len().saturating_sub(1) as f32;
+    let free_cross = cross_avail - used_cross;
+    if style.wrap != FlexWrap::NoWrap
+        && style.align_content == AlignContent::Stretch
+        && free_cross > 0.0
+    {
+        let extra = free_cross / lines.len() as f32;
+        for cross in &mut line_cross {
+            *cross += extra;
+        }
+    }
+
+    let (mut cross_pos, cross_between) = if style.wrap == FlexWrap::NoWrap {
+        (0.0, 0.0)
+    } else {
+        distribute_lines(style.align_content, free_cross, lines.len())
+    };
+
+    let mut rects = vec![Rect::default(); items.len()];
+    for (line, &line_cross) in lines.iter().zip(&line_cross) {
+        let used_main: f32 = line
+            .iter()
+            .map(|&i| main_sizes[i] + resolved[i].margin_main())
+            .sum::<f32>()
+            + axes.main_gap * line.len().saturating_sub(1) as f32;
+        let (mut main_pos, main_between) =
+            justify(style.justify_content, main_avail - used_main, line.len());
+
+        for &i in line {
+            let item = &resolved[i];
+            let align = item.align_self.unwrap_or(style.align_items);
+
+            let cross_size = if align == AlignItems::Stretch {
+                clamp(
+                    line_cross - item.margin_cross(),
+                    item.min_cross,
+                    item.max_cross,
+                )
+            } else {
+                item.hypothetical_cross
+            };
+            let cross_offset = match align {
+                AlignItems::Start | AlignItems::Stretch => 0.0,
+                AlignItems::End => line_cross - cross_size - item.margin_cross(),
+                AlignItems::Center => (line_cross - cross_size - item.margin_cross()) / 2.0,
+            };
+
+            let mut main = main_pos + item.margin_main_start;
+            let mut cross = cross_pos + item.margin_cross_start + cross_offset;
+            if axes.main_reversed {
+                main = main_avail - main - main_sizes[i];
+            }
+            if axes.cross_reversed {
+                cross = cross_avail - cross - cross_size;
+            }
+
+            rects[i] = axes.to_rect(inner, main, cross, main_sizes[i], cross_size);
+            main_pos += main_sizes[i] + item.margin_main() + axes.main_gap + main_between;
+        }
+
+        cross_pos += line_cross + axes.cross_gap + cross_between;
+    }
+
+    rects
+}
+
+/// Returns the size a flex container needs to fit its items without
+/// growing or shrinking them, given `available` space.
+pub fn measure_flex(
+    style: &FlexStyle,
+    items: &[FlexItem],
+    content: &[Size],
+    available: Size,
+) -> Size {
+    let axes = Axes::new(style);
+    let main_avail = axes.main(available) - axes.main_padding(&style.padding);
+
+    let resolved: Vec<ResolvedItem> = items
+        .iter()
+        .zip(content)
+        .map(|(item, &content)| ResolvedItem::new(item, content, &axes, main_avail))
+        .collect();
+
+    let lines = break_lines(style, &axes, &resolved, main_avail);
+
+    let mut main: f32 = 0.0;
+    let mut cross: f32 = 0.0;
+    for line in &lines {
+        let line_main = line
+            .iter()
+            .map(|&i| resolved[i].hypothetical_main + resolved[i].margin_main())
+            .sum::<f32>()
+            + axes.main_gap * line.len().saturating_sub(1) as f32;
+        let line_cross = line
+            .iter()
+            .map(|&i| resolved[i].hypothetical_cross + resolved[i].margin_cross())
+            .fold(0.0, f32::max);
+
+        main = main.max(line_main);
+        cross += line_cross;
+    }
+    cross += axes.cross_gap * lines.len().saturating_sub(1) as f32;
+
+    let size = axes.to_size(main, cross);
+    Size::new(
+        size.width + style.padding.horizontal(),
+        size.height + style.padding.vertical(),
+    )
+}
+
+/// Maps between the flex main/cross axes and x/y.
+struct Axes {
+    row: bool,
+    main_reversed: bool,
+    cross_reversed: bool,
+    main_gap: f32,
+    cross_gap: f32,
+}
+
+impl Axes {
+    fn new(style: &FlexStyle) -> Self {
+        let row = matches!(
+            style.direction,
+            FlexDirection::Row | FlexDirection::RowReverse
+        );
+        Axes {
+            row,
+            main_reversed: matches!(
+                style.direction,
+                FlexDirection::RowReverse | FlexDirection::ColumnReverse
+            ),
+            cross_reversed: style.wrap == FlexWrap::WrapReverse,
+            main_gap: if row { style.column_gap } else { style.row_gap },
+            cross_gap: if row { style.row_gap } else { style.column_gap },
+        }
+    }
+
+    fn main(&self, size: Size) -> f32 {
+        if self.row {
+            size.width
+        } else {
+            size.height
+        }
+    }
+
+    fn cross(&self, size: Size) -> f32 {
+        if self.row {
+            size.height
+        } else {
+            size.width
+        }
+    }
+
+    fn main_padding(&self, padding: &Edges) -> f32 {
+        if self.row {
+            padding.horizontal()
+        } else {
+            padding.vertical()
+        }
+    }
+
+    fn to_size(&self, main: f32, cross: f32) -> Size {
+        if self.row {
+            Size::new(main, cross)
+        } else {
+            Size::new(cross, main)
+        }
+    }
+
+    fn to_rect(&self, inner: Rect, main: f32, cross: f32, main_size: f32, cross_size: f32) -> Rect {
+        if self.row {
+            Rect::new(inner.x + main, inner.y + cross, main_size, cross_size)
+        } else {
+            Rect::new(inner.x + cross, inner.y + main, cross_size, main_size)
+        }
+    }
+}
+
+/// A flex item with its sizes resolved onto the container's axes.
+struct ResolvedItem {
+    grow: f32,
+    shrink: f32,
+    /// The flex base size, before min/max clamping.
+    basis: f32,
+    hypothetical_main: f32,
+    hypothetical_cross: f32,
+    min_main: f32,
+    max_main: f32,
+    min_cross: f32,
+    max_cross: f32,
+    margin_main_start: f32,
+    margin_main_end: f32,
+    margin_cross_start: f32,
+    margin_cross_end: f32,
+    align_self: Option<AlignItems>,
+}
+
+impl ResolvedItem {
+    fn new(item: &FlexItem, content: Size, axes: &Axes, main_avail: f32) -> Self {
+        let constraints = &item.constraints;
+        let m = &item.margin;
+        let (min_main, max_main, min_cross, max_cross) = if axes.row {
+            (
+                constraints.min.width,
+                constraints.max.width,
+                constraints.min.height,
+                constraints.max.height,
+            )
+        } else {
+            (
+                constraints.min.height,
+                constraints.max.height,
+                constraints.min.width,
+                constraints.max.width,
+            )
+        };
+        let (main_start, main_end, cross_start, cross_end) = if axes.row {
+            (m.left, m.right, m.top, m.bottom)
+        } else {
+            (m.top, m.bottom, m.left, m.right)
+        };
+
+        let basis = item
+            .basis
+            .resolve(main_avail)
+            .unwrap_or_else(|| axes.main(content));
+
+        ResolvedItem {
+            grow: item.grow,
+            shrink: item.shrink,
+            basis,
+            hypothetical_main: clamp(basis, min_main, max_main),
+            hypothetical_cross: clamp(axes.cross(content), min_cross, max_cross),
+            min_main,
+            max_main,
+            min_cross,
+            max_cross,
+            // The start margin is on the side the items flow from.
+            margin_main_start: if axes.main_reversed {
+                main_end
+            } else {
+                main_start
+            },
+            margin_main_end: if axes.main_reversed {
+                main_start
+            } else {
+                main_end
+            },
+            margin_cross_start: if axes.cross_reversed {
+                cross_end
+            } else {
+                cross_start
+            },
+            margin_cross_end: if axes.cross_reversed {
+                cross_start
+            } else {
+                cross_end
+            },
+            align_self: item.align_self,
+        }
+    }
+
+    fn margin_main(&self) -> f32 {
+        self.margin_main_start + self.margin_main_end
+    }
+
+    fn margin_cross(&self) -> f32 {
+        self.margin_cross_start + self.margin_cross_end
+    }
+}
+
+/// Splits items into lines, wrapping before an item that would overflow.
+fn break_lines(
+    style: &FlexStyle,
+    axes: &Axes,
+    items: &[ResolvedItem],
+    main_avail: f32,
+) -> Vec<Vec<usize>> {
+    if style.wrap == FlexWrap::NoWrap {
+        return vec![(0..items.len()).collect()];
+    }
+
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+    let mut line_main = 0.0;
+
+    for (i, item) in items.iter().enumerate() {
+        let outer = item.hypothetical_main + item.margin_main();
+        if !line.is_empty() && line_main + axes.main_gap + outer > main_avail {
+            lines.push(std::mem::take(&mut line));
+            line_main = 0.0;
+        }
+        if !line.is_empty() {
+            line_main += axes.main_gap;
+        }
+        line_main += outer;
+        line.push(i);
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Grows or shrinks the items of one line to fill `main_avail`, following
+/// the CSS "resolve flexible lengths" algorithm.
+fn resolve_flexible_lengths(
+    line: &[usize],
+    items: &[ResolvedItem],
+    gap: f32,
+    main_avail: f32,
+    sizes: &mut [f32],
+) {
+    let gaps = gap * line.len().saturating_sub(1) as f32;
+    let margins: f32 = line.iter().map(|&i| items[i].margin_main()).sum();
+    let hypothetical: f32 = line.iter().map(|&i| items[i].hypothetical_main).sum();
+    let growing = hypothetical + margins + gaps < main_avail;
+
+    // Items that cannot flex in the needed direction keep their
+    // hypothetical size.
+    let mut frozen = vec![false; line.len()];
+    for (k, &i) in line.iter().enumerate() {
+        let item = &items[i];
+        sizes[i] = item.basis;
+        let inflexible = if growing {
+            item.grow == 0.0 || item.basis > item.hypothetical_main
+        } else {
+            item.shrink == 0.0 || item.basis < item.hypothetical_main
+        };
+        if inflexible {
+            frozen[k] = true;
+            sizes[i] = item.hypothetical_main;
+        }
+    }
+
+    while frozen.iter().any(|&f| !f) {
+        let used: f32 = line
+            .iter()
+            .zip(&frozen)
+            .map(|(&i, &f)| if f { sizes[i] } else { items[i].basis })
+            .sum();
+        let free = main_avail - gaps - margins - used;
+
+        let unfrozen = || {
+            line.iter()
+                .zip(&frozen)
+                .filter(|(_, &f)| !f)
+                .map(|(&i, _)| i)
+        };
+        if growing {
+            let total_grow: f32 = unfrozen().map(|i| items[i].grow).sum();
+            for i in unfrozen() {
+                sizes[i] = items[i].basis + free * items[i].grow / total_grow;
+            }
+        } else {
+            let total_scaled: f32 = unfrozen().map(|i| items[i].shrink * items[i].basis).sum();
+            for i in unfrozen() {
+                let scaled = items[i].shrink * items[i].basis;
+                let ratio = if total_scaled > 0.0 {
+                    scaled / total_scaled
+                } else {
+                    0.0
+                };
+                sizes[i] = items[i].basis + free * ratio;
+            }
+        }
+
+        // Clamp, then freeze the items whose violations point the same way
+        // as the total; the rest are redistributed in the next round.
+        let mut violations = vec![0.0; line.len()];
+        for (k, &i) in line.iter().enumerate() {
+            if !frozen[k] {
+                let clamped = clamp(sizes[i], items[i].min_main, items[i].max_main);
+                violations[k] = clamped - sizes[i];
+                sizes[i] = clamped;
+            }
+        }
+
+        let total: f32 = violations.iter().sum();
+        for (k, frozen) in frozen.iter_mut().enumerate() {
+            let freeze = total == 0.0
+                || (total > 0.0 && violations[k] > 0.0)
+                || (total < 0.0 && violations[k] < 0.0);
+            if freeze {
+                *frozen = true;
+            }
+        }
+    }
+}
+
+/// Returns the leading offset and extra space between items for
+/// `justify`, given the line's `free` space.
+fn justify(justify: JustifyContent, free: f32, count: usize) -> (f32, f32) {
+    let n = count as f32;
+    match justify {
+        JustifyContent::Start => (0.0, 0.0),
+        JustifyContent::End => (free, 0.0),
+        JustifyContent::Center => (free / 2.0, 0.0),
+        // Negative free space falls back to start alignment.
+        _ if free <= 0.0 => (0.0, 0.0),
+        JustifyContent::SpaceBetween if count > 1 => (0.0, free / (n - 1.0)),
+        JustifyContent::SpaceBetween => (0.0, 0.0),
+        JustifyContent::SpaceAround => (free / n / 2.0, free / n),
+        JustifyContent::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+    }
+}
+
+/// Returns the leading offset and extra space between lines.
+fn distribute_lines(align: AlignContent, free: f32, count: usize) -> (f32, f32) {
+    let n = count as f32;
+    match align {
+        AlignContent::Start | AlignContent::Stretch => (0.0, 0.0),
+        AlignContent::End => (free, 0.0),
+        AlignContent::Center => (free / 2.0, 0.0),
+        _ if free <= 0.0 => (0.0, 0.0),
+        AlignContent::SpaceBetween if count > 1 => (0.0, free / (n - 1.0)),
+        AlignContent::SpaceBetween => (0.0, 0.0),
+        AlignContent::SpaceAround => (free / n / 2.0, free / n),
+    }
+}
+
+/// Clamps `value` into `min..=max`, letting `min` win when it exceeds
+/// `max` instead of panicking like `f32::clamp`.
+fn clamp(value: f32, min: f32, max: f32) -> f32 {
+    value.min(max).max(min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::test_helpers::{assert_rects, rect, sizes};
+
+    struct Case {
+        name: &'static str,
+        style: FlexStyle,
+        items: Vec<FlexItem>,
+        content: Vec<Size>,
+        expected: Vec<Rect>,
+    }
+
+    const BOUNDS: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 50.0,
+    };
+
+    fn grow(grow: f32) -> FlexItem {
+        FlexItem {
+            grow,
+            ..FlexItem::default()
+        }
+    }
+
+    fn basis(points: f32) -> FlexItem {
+        FlexItem {
+            basis: Dimension::Points(points),
+            ..FlexItem::default()
+        }
+    }
+
+    fn max_width(item: FlexItem, width: f32) -> FlexItem {
+        FlexItem {
+            constraints: SizeConstraints {
+                max: Size::new(width, f32::INFINITY),
+                ..item.constraints
+            },
+            ..item
+        }
+    }
+
+    fn min_width(item: FlexItem, width: f32) -> FlexItem {
+        FlexItem {
+            constraints: SizeConstraints {
+                min: Size::new(width, 0.0),
+                ..item.constraints
+            },
+            ..item
+        }
+    }
+
+    fn run(cases: Vec<Case>) {
+        for case in cases {
+            let rects = compute_flex(&case.style, &case.items, &case.content, BOUNDS);
+            assert_rects(case.name, &rects, &case.expected);
+        }
+    }
+
+    #[test]
+    fn grow_and_shrink() {
+        run(vec![
+            Case {
+                name: "grow splits free space by factor",
+                style: FlexStyle::default(),
+                items: vec![grow(1.0), grow(3.0)],
+                content: sizes(2, 10.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 30.0, 50.0), rect(30.0, 0.0, 70.0, 50.0)],
+            },
+            Case {
+                name: "grow freezes an item at its max",
+                style: FlexStyle::default(),
+                items: vec![max_width(grow(1.0), 20.0), grow(1.0)],
+                content: sizes(2, 10.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 20.0, 50.0), rect(20.0, 0.0, 80.0, 50.0)],
+            },
+            Case {
+                name: "no grow leaves free space",
+                style: FlexStyle::default(),
+                items: vec![FlexItem::default(); 2],
+                content: sizes(2, 10.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 10.0, 50.0), rect(10.0, 0.0, 10.0, 50.0)],
+            },
+            Case {
+                name: "shrink is weighted by basis",
+                style: FlexStyle::default(),
+                items: vec![basis(120.0), basis(40.0)],
+                content: sizes(2, 0.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 75.0, 50.0), rect(75.0, 0.0, 25.0, 50.0)],
+            },
+            Case {
+                name: "shrink freezes an item at its min",
+                style: FlexStyle::default(),
+                items: vec![basis(120.0), min_width(basis(40.0), 35.0)],
+                content: sizes(2, 0.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 65.0, 50.0), rect(65.0, 0.0, 35.0, 50.0)],
+            },
+            Case {
+                name: "zero shrink keeps its basis",
+                style: FlexStyle::default(),
+                items: vec![
+                    FlexItem {
+                        shrink: 0.0,
+                        ..basis(80.0)
+                    },
+                    basis(40.0),
+                ],
+                content: sizes(2, 0.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 80.0, 50.0), rect(80.0, 0.0, 20.0, 50.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn basis_resolution() {
+        run(vec![
+            Case {
+                name: "auto basis uses the content size",
+                style: FlexStyle::default(),
+                items: vec![FlexItem::default()],
+                content: sizes(1, 25.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 25.0, 50.0)],
+            },
+            Case {
+                name: "points and percent ignore the content",
+                style: FlexStyle::default(),
+                items: vec![
+                    basis(30.0),
+                    FlexItem {
+                        basis: Dimension::Percent(0.5),
+                        ..FlexItem::default()
+                    },
+                ],
+                content: sizes(2, 5.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 30.0, 50.0), rect(30.0, 0.0, 50.0, 50.0)],
+            },
+            Case {
+                name: "max clamps the basis",
+                style: FlexStyle::default(),
+                items: vec![max_width(basis(60.0), 40.0)],
+                content: sizes(1, 0.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 40.0, 50.0)],
+            },
+            Case {
+                name: "min clamps the basis",
+                style: FlexStyle::default(),
+                items: vec![min_width(basis(10.0), 40.0)],
+                content: sizes(1, 0.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 40.0, 50.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn gaps_margins_and_padding() {
+        run(vec![
+            Case {
+                name: "column gap between items",
+                style: FlexStyle {
+                    column_gap: 10.0,
+                    ..FlexStyle::default()
+                },
+                items: vec![FlexItem::default(); 3],
+                content: sizes(3, 20.0, 10.0),
+                expected: vec![
+                    rect(0.0, 0.0, 20.0, 50.0),
+                    rect(30.0, 0.0, 20.0, 50.0),
+                    rect(60.0, 0.0, 20.0, 50.0),
+                ],
+            },
+            Case {
+                name: "gaps are taken from the space to grow into",
+                style: FlexStyle {
+                    column_gap: 10.0,
+                    ..FlexStyle::default()
+                },
+                items: vec![grow(1.0); 2],
+                content: sizes(2, 0.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 45.0, 50.0), rect(55.0, 0.0, 45.0, 50.0)],
+            },
+            Case {
+                name: "margins offset items and reduce stretch",
+                style: FlexStyle::default(),
+                items: vec![
+                    FlexItem {
+                        margin: Edges {
+                            top: 4.0,
+                            right: 10.0,
+                            bottom: 6.0,
+                            left: 5.0,
+                        },
+                        ..FlexItem::default()
+                    },
+                    FlexItem::default(),
+                ],
+                content: sizes(2, 20.0, 10.0),
+                expected: vec![rect(5.0, 4.0, 20.0, 40.0), rect(35.0, 0.0, 20.0, 50.0)],
+            },
+            Case {
+                name: "max height caps stretch",
+                style: FlexStyle::default(),
+                items: vec![FlexItem {
+                    constraints: SizeConstraints {
+                        max: Size::new(f32::INFINITY, 30.0),
+                        ..SizeConstraints::UNBOUNDED
+                    },
+                    ..FlexItem::default()
+                }],
+                content: sizes(1, 20.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 20.0, 30.0)],
+            },
+            Case {
+                name: "padding insets the items",
+                style: FlexStyle {
+                    padding: Edges::all(10.0),
+                    ..FlexStyle::default()
+                },
+                items: vec![grow(1.0)],
+                content: sizes(1, 0.0, 10.0),
+                expected: vec![rect(10.0, 10.0, 80.0, 30.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn directions_and_alignment() {
+        let start = FlexStyle {
+            align_items: AlignItems::Start,
+            ..FlexStyle::default()
+        };
+        run(vec![
+            Case {
+                name: "column",
+                style: FlexStyle {
+                    direction: FlexDirection::Column,
+                    ..start
+                },
+                items: vec![FlexItem::default(); 2],
+                content: sizes(2, 20.0, 10.0),
+                expected: vec![rect(0.0, 0.0, 20.0, 10.0), rect(0.0, 10.0, 20.0, 10.0)],
+            },
+            Case {
+                name: "row reverse",
+                style: FlexStyle {
+                    direction: FlexDirection::RowReverse,
+                    ..start
+                },
+                items: vec![FlexItem::default(); 2],
+                content: sizes(2, 20.0, 10.0),
+                expected: vec![rect(80.0, 0.0, 20.0, 10.0), rect(60.0, 0.0, 20.0, 10.0)],
+            },
+            Case {
+                name: "space between",
+                style: FlexStyle {
+                    justify_content: JustifyContent::SpaceBetween,
+                    ..start
+                },
+                items: vec![FlexItem::default(); 3],
+                content: sizes(3, 20.0, 10.0),
+                expected: vec![
+                    rect(0.0, 0.0, 20.0, 10.0),
+                    rect(40.0, 0.0, 20.0, 10.0),
+                    rect(80.0, 0.0, 20.0, 10.0),
+                ],
+            },
+            Case {
+                name: "center on both axes",
+                style: FlexStyle {
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..FlexStyle::default()
+                },
+                items: vec![FlexItem::default()],
+                content: sizes(1, 20.0, 10.0),
+                expected: vec![rect(40.0, 20.0, 20.0, 10.0)],
+            },
+            Case {
+                name: "align self overrides align items",
+                style: start,
+                items: vec![FlexItem {
+                    align_self: Some(AlignItems::End),
+                    ..FlexItem::default()
+                }],
+                content: sizes(1, 20.0, 10.0),
+                expected: vec![rect(0.0, 40.0, 20.0, 10.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn wrap() {
+        let wrap = FlexStyle {
+            wrap: FlexWrap::Wrap,
+            ..FlexStyle::default()
+        };
+        run(vec![
+            Case {
+                name: "no wrap overflows onto one line",
+                style: FlexStyle::default(),
+                items: vec![
+                    FlexItem {
+                        shrink: 0.0,
+                        ..FlexItem::default()
+                    };
+                    3
+                ],
+                content: sizes(3, 40.0, 10.0),
+                expected: vec![
+                    rect(0.0, 0.0, 40.0, 50.0),
+                    rect(40.0, 0.0, 40.0, 50.0),
+                    rect(80.0, 0.0, 40.0, 50.0),
+                ],
+            },
+            Case {
+                name: "wrapped lines stretch to fill the cross axis",
+                style: wrap,
+                items: vec![FlexItem::default(); 3],
+                content: sizes(3, 40.0, 10.0),
+                expected: vec![
+                    rect(0.0, 0.0, 40.0, 25.0),
+                    rect(40.0, 0.0, 40.0, 25.0),
+                    rect(0.0, 25.0, 40.0, 25.0),
+                ],
+            },
+            Case {
+                name: "gaps count towards wrapping",
+                style: FlexStyle {
+                    row_gap: 5.0,
+                    column_gap: 10.0,
+                    align_items: AlignItems::Start,
+                    align_content: AlignContent::Start,
+                    ..wrap
+                },
+                items: vec![FlexItem::default(); 3],
+                content: sizes(3, 40.0, 10.0),
+                expected: vec![
+                    rect(0.0, 0.0, 40.0, 10.0),
+                    rect(50.0, 0.0, 40.0, 10.0),
+                    rect(0.0, 15.0, 40.0, 10.0),
+                ],
+            },
+            Case {
+                name: "wrap reverse stacks lines from the bottom",
+                style: FlexStyle {
+                    wrap: FlexWrap::WrapReverse,
+                    align_items: AlignItems::Start,
+                    align_content: AlignContent::Start,
+                    ..FlexStyle::default()
+                },
+                items: vec![FlexItem::default(); 3],
+                content: sizes(3, 40.0, 10.0),
+                expected: vec![
+                    rect(0.0, 40.0, 40.0, 10.0),
+                    rect(40.0, 40.0, 40.0, 10.0),
+                    rect(0.0, 30.0, 40.0, 10.0),
+                ],
+            },
+            Case {
+                name: "each line grows on its own",
+                style: wrap,
+                items: vec![grow(1.0); 3],
+                content: sizes(3, 40.0, 10.0),
+                expected: vec![
+                    rect(0.0, 0.0, 50.0, 25.0),
+                    rect(50.0, 0.0, 50.0, 25.0),
+                    rect(0.0, 25.0, 100.0, 25.0),
+                ],
+            },
+        ]);
+    }
+
+    #[test]
+    fn measure_includes_gaps_margins_and_padding() {
+        let style = FlexStyle {
+            column_gap: 5.0,
+            padding: Edges::all(2.0),
+            ..FlexStyle::default()
+        };
+        let items = [
+            FlexItem::default(),
+            FlexItem {
+                margin: Edges {
+                    left: 3.0,
+                    ..Edges::ZERO
+                },
+                ..FlexItem::default()
+            },
+        ];
+        let size = measure_flex(
+            &style,
+            &items,
+            &sizes(2, 20.0, 10.0),
+            Size::new(500.0, 500.0),
+        );
+        assert_eq!(size, Size::new(52.0, 14.0));
+    }
+
+    #[test]
+    fn measure_wraps_to_the_available_width() {
+        let style = FlexStyle {
+            wrap: FlexWrap::Wrap,
+            row_gap: 5.0,
+            ..FlexStyle::default()
+        };
+        let items = [FlexItem::default(); 3];
+        let size = measure_flex(
+            &style,
+            &items,
+            &sizes(3, 40.0, 10.0),
+            Size::new(100.0, 500.0),
+        );
+        assert_eq!(size, Size::new(80.0, 25.0));
+    }
+}
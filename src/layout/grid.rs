@@ -0,0 +1,885 @@
+use std::collections::HashMap;
+
+use crate::renderer::primitive::{Rect, Size};
+
+use super::flex::AlignItems;
+use super::style::Edges;
+
+/// The sizing function of a single row or column.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TrackSize {
+    Points(f32),
+    /// A fraction of the grid's size, from `0.0` to `1.0`.
+    Percent(f32),
+    /// A share of the space left after all other tracks are sized.
+    Fraction(f32),
+    /// Sized to fit the largest item in the track.
+    #[default]
+    Auto,
+}
+
+/// Which axis auto-placed items fill first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridAutoFlow {
+    #[default]
+    Row,
+    Column,
+}
+
+/// Properties of a grid container.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GridStyle {
+    pub columns: Vec<TrackSize>,
+    pub rows: Vec<TrackSize>,
+    /// Size of rows created implicitly by auto-placement.
+    pub auto_rows: TrackSize,
+    /// Size of columns created implicitly by auto-placement.
+    pub auto_columns: TrackSize,
+    pub auto_flow: GridAutoFlow,
+    /// Named areas, one string per row with one name per column, as in
+    /// CSS `grid-template-areas`. A `.` marks an unnamed cell. Each area
+    /// must fill a rectangle; other shapes are ignored with a warning.
+    pub areas: Vec<String>,
+    pub row_gap: f32,
+    pub column_gap: f32,
+    pub padding: Edges,
+}
+
+/// Properties of a child of a grid container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridItem {
+    /// Zero-based starting row, or `None` to auto-place.
+    pub row: Option<usize>,
+    /// Zero-based starting column, or `None` to auto-place.
+    pub column: Option<usize>,
+    pub row_span: usize,
+    pub column_span: usize,
+    /// A named area from [`GridStyle::areas`]; overrides the fields above.
+    /// Unknown names are warned about and fall back to the fields above.
+    pub area: Option<String>,
+    pub margin: Edges,
+    /// Horizontal alignment inside the cell.
+    pub justify_self: AlignItems,
+    /// Vertical alignment inside the cell.
+    pub align_self: AlignItems,
+}
+
+impl Default for GridItem {
+    fn default() -> Self {
+        GridItem {
+            row: None,
+            column: None,
+            row_span: 1,
+            column_span: 1,
+            area: None,
+            margin: Edges::ZERO,
+            justify_self: AlignItems::Stretch,
+            align_self: AlignItems::Stretch,
+        }
+    }
+}
+
+impl GridItem {
+    pub fn at(row: usize, column: usize) -> Self {
+        GridItem {
+            row: Some(row),
+            column: Some(column),
+            ..GridItem::default()
+        }
+    }
+
+    pub fn area(name: &str) -> Self {
+        GridItem {
+            area: Some(name.to_string()),
+            ..GridItem::default()
+        }
+    }
+
+    pub fn span(mut self, rows: usize, columns: usize) -> Self {
+        self.row_span = rows.max(1);
+        self.column_span = columns.max(1);
+        self
+    }
+}
+
+/// The cells an item occupies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Placement {
+    row: usize,
+    column: usize,
+    row_span: usize,
+    column_span: usize,
+}
+
+/// Computes the rectangle of every item of a grid container occupying
+/// `bounds`.
+///
+/// `content` holds each item's measured size, used for `Auto` tracks and
+/// non-stretched items. The returned rectangles exclude margins.
+pub fn compute_grid(
+    style: &GridStyle,
+    items: &[GridItem],
+    content: &[Size],
+    bounds: Rect,
+) -> Vec<Rect> {
+    assert_eq!(
+        items.len(),
+        content.len(),
+        "Every grid item needs a content size"
+    );
+
+    let inner = style.padding.inset(bounds);
+    let areas = parse_areas(&style.areas);
+    let placements = place_items(style, &areas, items);
+    let (column_count, row_count) = grid_dimensions(style, &areas, &placements);
+
+    let outer: Vec<Size> = items
+        .iter()
+        .zip(content)
+        .map(|(item, size)| {
+            Size::new(
+                size.width + item.margin.horizontal(),
+                size.height + item.margin.vertical(),
+            )
+        })
+        .collect();
+
+    let columns = size_tracks(
+        &tracks(&style.columns, style.auto_columns, column_count),
+        placements.iter().map(|p| (p.column, p.column_span)),
+        outer.iter().map(|s| s.width),
+        style.column_gap,
+        Some(inner.width),
+    );
+    let rows = size_tracks(
+        &tracks(&style.rows, style.auto_rows, row_count),
+        placements.iter().map(|p| (p.row, p.row_span)),
+        outer.iter().map(|s| s.height),
+        style.row_gap,
+        Some(inner.height),
+    );
+
+    let column_starts = track_starts(&columns, style.column_gap);
+    let row_starts = track_starts(&rows, style.row_gap);
+
+    placements
+        .iter()
+        .zip(items)
+        .zip(content)
+        .map(|((placement, item), content)| {
+            let cell_x = column_starts[placement.column];
+            let cell_y = row_starts[placement.row];
+            let cell_width = span_size(&columns, placement.column, placement.column_span)
+                + style.column_gap * (placement.column_span - 1) as f32;
+            let cell_height = span_size(&rows, placement.row, placement.row_span)
+                + style.row_gap * (placement.row_span - 1) as f32;
+
+            let (x, width) = align(
+                item.justify_self,
+                cell_width - item.margin.horizontal(),
+                content.width,
+            );
+            let (y, height) = align(
+                item.align_self,
+                cell_height - item.margin.vertical(),
+                content.height,
+            );
+
+            Rect::new(
+                inner.x + cell_x + item.margin.left + x,
+                inner.y + cell_y + item.margin.top + y,
+                width,
+                height,
+            )
+        })
+        .collect()
+}
+
+/// Returns the size a grid container needs for its content, with
+/// fractional tracks sized like `Auto` ones.
+pub fn measure_grid(style: &GridStyle, items: &[GridItem], content: &[Size]) -> Size {
+    let areas = parse_areas(&style.areas);
+    let placements = place_items(style, &areas, items);
+    let (column_count, row_count) = grid_dimensions(style, &areas, &placements);
+
+    let columns = size_tracks(
+        &tracks(&style.columns, style.auto_columns, column_count),
+        placements.iter().map(|p| (p.column, p.column_span)),
+        items
+            .iter()
+            .zip(content)
+            .map(|(item, size)| size.width + item.margin.horizontal()),
+        style.column_gap,
+        None,
+    );
+    let rows = size_tracks(
+        &tracks(&style.rows, style.auto_rows, row_count),
+        placements.iter().map(|p| (p.row, p.row_span)),
+        items
+            .iter()
+            .zip(content)
+            .map(|(item, size)| size.height + item.margin.vertical()),
+        style.row_gap,
+        None,
+    );
+
+    let total = |sizes: &[f32], gap: f32| {
+        sizes.iter().sum::<f32>() + gap * sizes.len().saturating_sub(1) as f32
+    };
+
+    Size::new(
+        total(&columns, style.column_gap) + style.padding.horizontal(),
+        total(&rows, style.row_gap) + style.padding.vertical(),
+    )
+}
+
+/// Parses `grid-template-areas`-style rows into the cells each name covers.
+///
+/// Names that do not fill the rectangle around them, such as L-shapes or a
+/// name used in two separate places, are left out with a warning.
+fn parse_areas(areas: &[String]) -> HashMap<&str, Placement> {
+    let mut parsed: HashMap<&str, Placement> = HashMap::new();
+    let mut cell_counts: HashMap<&str, usize> = HashMap::new();
+
+    for (row, line) in areas.iter().enumerate() {
+        for (column, name) in line.split_whitespace().enumerate() {
+            if name == "." {
+                continue;
+            }
+
+            *cell_counts.entry(name).or_default() += 1;
+            parsed
+                .entry(name)
+                .and_modify(|area| {
+                    let row_end = (area.row + area.row_span).max(row + 1);
+                    let column_end = (area.column + area.column_span).max(column + 1);
+                    area.row = area.row.min(row);
+                    area.column = area.column.min(column);
+                    area.row_span = row_end - area.row;
+                    area.column_span = column_end - area.column;
+                })
+                .or_insert(Placement {
+                    row,
+                    column,
+                    row_span: 1,
+                    column_span: 1,
+                });
+        }
+    }
+
+    // Every cell holds one name, so a name fills its bounding rectangle
+    // exactly when it has as many cells.
+    parsed.retain(|name, area| {
+        let rectangular = cell_counts[name] == area.row_span * area.column_span;
+        if !rectangular {
+            log::warn!("grid area '{}' is not a rectangle and is ignored", name);
+        }
+        rectangular
+    });
+
+    parsed
+}
+
+/// Assigns every item to cells: named areas and fully specified items
+/// first, then the rest in auto-flow order, skipping occupied cells.
+fn place_items(
+    style: &GridStyle,
+    areas: &HashMap<&str, Placement>,
+    items: &[GridItem],
+) -> Vec<Placement> {
+    let explicit_columns = style.columns.len().max(
+        areas
+            .values()
+            .map(|a| a.column + a.column_span)
+            .max()
+            .unwrap_or(0),
+    );
+    let explicit_rows = style.rows.len().max(
+        areas
+            .values()
+            .map(|a| a.row + a.row_span)
+            .max()
+            .unwrap_or(0),
+    );
+
+    let mut placements: Vec<Option<Placement>> = items
+        .iter()
+        .map(|item| {
+            if let Some(name) = item.area.as_deref() {
+                match areas.get(name) {
+                    Some(area) => return Some(*area),
+                    None => log::warn!(
+                        "grid item refers to unknown area '{}' and is placed by row and column",
+                        name
+                    ),
+                }
+            }
+            match (item.row, item.column) {
+                (Some(row), Some(column)) => Some(Placement {
+                    row,
+                    column,
+                    row_span: item.row_span.max(1),
+                    column_span: item.column_span.max(1),
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+
+    // Work in flow-relative terms: `major` is the axis that grows to fit
+    // auto-placed items, `minor` has a fixed number of tracks.
+    let by_row = style.auto_flow == GridAutoFlow::Row;
+    let to_flow =
+        |row: Option<usize>, column: Option<usize>, row_span: usize, column_span: usize| {
+            if by_row {
+                (row, column, row_span, column_span)
+            } else {
+                (column, row, column_span, row_span)
+            }
+        };
+
+    let minor_count = placements
+        .iter()
+        .flatten()
+        .map(|p| {
+            if by_row {
+                p.column + p.column_span
+            } else {
+                p.row + p.row_span
+            }
+        })
+        .chain(items.iter().map(|item| {
+            let (_, minor, _, minor_span) =
+                to_flow(item.row, item.column, item.row_span, item.column_span);
+            minor.unwrap_or(0) + minor_span.max(1)
+        }))
+        .chain(std::iter::once(if by_row {
+            explicit_columns
+        } else {
+            explicit_rows
+        }))
+        .max()
+        .unwrap_or(1)
+        .max(1);
+
+    let mut occupied = Occupancy::new(minor_count);
+    for placement in placements.iter().flatten() {
+        let (major, minor, major_span, minor_span) = to_flow(
+            Some(placement.row),
+            Some(placement.column),
+            placement.row_span,
+            placement.column_span,
+        );
+        occupied.mark(major.unwrap(), minor.unwrap(), major_span, minor_span);
+    }
+
+    let mut cursor = (0, 0);
+    for (item, placement) in items.iter().zip(placements.iter_mut()) {
+        if placement.is_some() {
+            continue;
+        }
+
+        let (major, minor, major_span, minor_span) = to_flow(
+            item.row,
+            item.column,
+            item.row_span.max(1),
+            item.column_span.max(1),
+        );
+        let minor_span = minor_span.min(minor_count);
+
+        let (major, minor) = match (major, minor) {
+            // Fixed in the growing axis: find the first free slot in it.
+            (Some(major), None) => {
+                let minor = (0..=minor_count - minor_span)
+                    .find(|&minor| occupied.is_free(major, minor, major_span, minor_span));
+                // With no free slot the item overlaps whatever is at the start.
+                (major, minor.unwrap_or(0))
+            }
+            // Fixed in the fixed axis: walk down until the slot is free.
+            (None, Some(minor)) => {
+                let minor = minor.min(minor_count - minor_span);
+                let major = (cursor.0..)
+                    .find(|&major| occupied.is_free(major, minor, major_span, minor_span))
+                    .unwrap();
+                (major, minor)
+            }
+            _ => {
+                let (mut major, mut minor) = cursor;
+                loop {
+                    if minor + minor_span > minor_count {
+                        major += 1;
+                        minor = 0;
+                    }
+                    if occupied.is_free(major, minor, major_span, minor_span) {
+                        break;
+                    }
+                    minor += 1;
+                }
+                cursor = (major, minor + minor_span);
+                (major, minor)
+            }
+        };
+
+        occupied.mark(major, minor, major_span, minor_span);
+        *placement = Some(if by_row {
+            Placement {
+                row: major,
+                column: minor,
+                row_span: major_span,
+                column_span: minor_span,
+            }
+        } else {
+            Placement {
+                row: minor,
+                column: major,
+                row_span: minor_span,
+                column_span: major_span,
+            }
+        });
+    }
+
+    placements.into_iter().map(Option::unwrap).collect()
+}
+
+/// Tracks which cells are taken, in flow-relative coordinates.
+struct Occupancy {
+    minor_count: usize,
+    cells: Vec<bool>,
+}
+
+impl Occupancy {
+    fn new(minor_count: usize) -> Self {
+        Occupancy {
+            minor_count,
+            cells: Vec::new(),
+        }
+    }
+
+    fn is_free(&self, major: usize, minor: usize, major_span: usize, minor_span: usize) -> bool {
+        (major..major + major_span).all(|major| {
+            (minor..minor + minor_span).all(|minor| {
+                minor >= self.minor_count
+                    || !self
+                        .cells
+                        .get(major * self.minor_count + minor)
+                        .copied()
+                        .unwrap_or(false)
+            })
+        })
+    }
+
+    fn mark(&mut self, major: usize, minor: usize, major_span: usize, minor_span: usize) {
+        let end = (major + major_span) * self.minor_count;
+        if self.cells.len() < end {
+            self.cells.resize(end, false);
+        }
+        for major in major..major + major_span {
+            for minor in (minor..minor + minor_span).filter(|&m| m < self.minor_count) {
+                self.cells[major * self.minor_count + minor] = true;
+            }
+        }
+    }
+}
+
+/// Returns the number of columns and rows, including implicit tracks.
+fn grid_dimensions(
+    style: &GridStyle,
+    areas: &HashMap<&str, Placement>,
+    placements: &[Placement],
+) -> (usize, usize) {
+    let columns = placements
+        .iter()
+        .map(|p| p.column + p.column_span)
+        .chain(areas.values().map(|a| a.column + a.column_span))
+        .chain(std::iter::once(style.columns.len()))
+        .max()
+        .unwrap_or(0);
+    let rows = placements
+        .iter()
+        .map(|p| p.row + p.row_span)
+        .chain(areas.values().map(|a| a.row + a.row_span))
+        .chain(std::iter::once(style.rows.len()))
+        .max()
+        .unwrap_or(0);
+
+    (columns, rows)
+}
+
+/// The explicit tracks, padded with implicit ones up to `count`.
+fn tracks(explicit: &[TrackSize], implicit: TrackSize, count: usize) -> Vec<TrackSize> {
+    let mut tracks = explicit.to_vec();
+    tracks.resize(count.max(explicit.len()), implicit);
+    tracks
+}
+
+/// Resolves track sizes along one axis. With no `available` size,
+/// fractional tracks size to their content like `Auto`.
+fn size_tracks(
+    tracks: &[TrackSize],
+    spans: impl Iterator<Item = (usize, usize)>,
+    item_sizes: impl Iterator<Item = f32>,
+    gap: f32,
+    available: Option<f32>,
+) -> Vec<f32> {
+    let spans: Vec<(usize, usize, f32)> = spans
+        .zip(item_sizes)
+        .map(|((start, span), size)| (start, span, size))
+        .collect();
+
+    let is_content_sized = |track: &TrackSize| {
+        matches!(track, TrackSize::Auto)
+            || (available.is_none() && matches!(track, TrackSize::Fraction(_)))
+    };
+
+    let mut sizes: Vec<f32> = tracks
+        .iter()
+        .map(|track| match *track {
+            TrackSize::Points(points) => points,
+            TrackSize::Percent(fraction) => available.map_or(0.0, |a| a * fraction),
+            _ => 0.0,
+        })
+        .collect();
+
+    // Content-sized tracks first fit items spanning only them...
+    for &(start, span, size) in &spans {
+        if span == 1 && is_content_sized(&tracks[start]) {
+            sizes[start] = sizes[start].max(size);
+        }
+    }
+
+    // ...then spanning items share any shortfall among their
+    // content-sized tracks.
+    for &(start, span, size) in spans.iter().filter(|(_, span, _)| *span > 1) {
+        let range = start..start + span;
+        let current = sizes[range.clone()].iter().sum::<f32>() + gap * (span - 1) as f32;
+        let flexible: Vec<usize> = range.filter(|&i| is_content_sized(&tracks[i])).collect();
+        if size > current && !flexible.is_empty() {
+            let extra = (size - current) / flexible.len() as f32;
+            for i in flexible {
+                sizes[i] += extra;
+            }
+        }
+    }
+
+    if let Some(available) = available {
+        let total_fraction: f32 = tracks
+            .iter()
+            .map(|track| match track {
+                TrackSize::Fraction(fraction) => *fraction,
+                _ => 0.0,
+            })
+            .sum();
+
+        if total_fraction > 0.0 {
+            let used = sizes.iter().sum::<f32>() + gap * tracks.len().saturating_sub(1) as f32;
+            let free = (available - used).max(0.0);
+            for (size, track) in sizes.iter_mut().zip(tracks) {
+                if let TrackSize::Fraction(fraction) = track {
+                    *size = free * fraction / total_fraction;
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+fn track_starts(sizes: &[f32], gap: f32) -> Vec<f32> {
+    let mut position = 0.0;
+    sizes
+        .iter()
+        .map(|size| {
+            let start = position;
+            position += size + gap;
+            start
+        })
+        .collect()
+}
+
+fn span_size(sizes: &[f32], start: usize, span: usize) -> f32 {
+    sizes[start..start + span].iter().sum()
+}
+
+/// Returns the offset and size of an item of `content` size aligned in a
+/// cell of `cell` size.
+fn align(align: AlignItems, cell: f32, content: f32) -> (f32, f32) {
+    let size = content.min(cell);
+    match align {
+        AlignItems::Stretch => (0.0, cell.max(0.0)),
+        AlignItems::Start => (0.0, size),
+        AlignItems::End => (cell - size, size),
+        AlignItems::Center => ((cell - size) / 2.0, size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::test_helpers::{assert_rects, rect, sizes};
+    use TrackSize::{Auto, Fraction, Percent, Points};
+
+    struct Case {
+        name: &'static str,
+        style: GridStyle,
+        items: Vec<GridItem>,
+        content: Vec<Size>,
+        expected: Vec<Rect>,
+    }
+
+    const BOUNDS: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 100.0,
+    };
+
+    fn grid(columns: &[TrackSize], rows: &[TrackSize]) -> GridStyle {
+        GridStyle {
+            columns: columns.to_vec(),
+            rows: rows.to_vec(),
+            ..GridStyle::default()
+        }
+    }
+
+    fn areas(style: GridStyle, areas: &[&str]) -> GridStyle {
+        GridStyle {
+            areas: areas.iter().map(|row| row.to_string()).collect(),
+            ..style
+        }
+    }
+
+    fn run(cases: Vec<Case>) {
+        for case in cases {
+            let rects = compute_grid(&case.style, &case.items, &case.content, BOUNDS);
+            assert_rects(case.name, &rects, &case.expected);
+        }
+    }
+
+    #[test]
+    fn track_sizing() {
+        run(vec![
+            Case {
+                name: "fractions share the space left by fixed tracks",
+                style: grid(
+                    &[Points(20.0), Fraction(1.0), Fraction(3.0)],
+                    &[Points(10.0)],
+                ),
+                items: vec![GridItem::at(0, 0), GridItem::at(0, 1), GridItem::at(0, 2)],
+                content: sizes(3, 5.0, 5.0),
+                expected: vec![
+                    rect(0.0, 0.0, 20.0, 10.0),
+                    rect(20.0, 0.0, 20.0, 10.0),
+                    rect(40.0, 0.0, 60.0, 10.0),
+                ],
+            },
+            Case {
+                name: "auto tracks fit their largest item",
+                style: grid(&[Auto, Fraction(1.0)], &[Auto]),
+                items: vec![GridItem::at(0, 0), GridItem::at(0, 1)],
+                content: vec![Size::new(30.0, 15.0), Size::new(5.0, 5.0)],
+                expected: vec![rect(0.0, 0.0, 30.0, 15.0), rect(30.0, 0.0, 70.0, 15.0)],
+            },
+            Case {
+                name: "percent tracks and gaps come out of the free space",
+                style: GridStyle {
+                    column_gap: 10.0,
+                    ..grid(&[Percent(0.25), Fraction(1.0)], &[Points(10.0)])
+                },
+                items: vec![GridItem::at(0, 0), GridItem::at(0, 1)],
+                content: sizes(2, 5.0, 5.0),
+                expected: vec![rect(0.0, 0.0, 25.0, 10.0), rect(35.0, 0.0, 65.0, 10.0)],
+            },
+            Case {
+                name: "padding insets the tracks",
+                style: GridStyle {
+                    padding: Edges::all(10.0),
+                    ..grid(&[Fraction(1.0)], &[Fraction(1.0)])
+                },
+                items: vec![GridItem::at(0, 0)],
+                content: sizes(1, 5.0, 5.0),
+                expected: vec![rect(10.0, 10.0, 80.0, 80.0)],
+            },
+            Case {
+                name: "non-stretched items align inside their cell",
+                style: grid(&[Points(50.0)], &[Points(50.0)]),
+                items: vec![GridItem {
+                    justify_self: AlignItems::Center,
+                    align_self: AlignItems::End,
+                    ..GridItem::at(0, 0)
+                }],
+                content: sizes(1, 10.0, 10.0),
+                expected: vec![rect(20.0, 40.0, 10.0, 10.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn spans() {
+        run(vec![
+            Case {
+                name: "a span covers its tracks and the gaps between them",
+                style: GridStyle {
+                    row_gap: 5.0,
+                    column_gap: 5.0,
+                    ..grid(
+                        &[Points(20.0), Points(30.0), Points(40.0)],
+                        &[Points(10.0), Points(10.0)],
+                    )
+                },
+                items: vec![GridItem::at(0, 0).span(2, 2), GridItem::at(1, 2)],
+                content: sizes(2, 5.0, 5.0),
+                expected: vec![rect(0.0, 0.0, 55.0, 25.0), rect(60.0, 15.0, 40.0, 10.0)],
+            },
+            Case {
+                name: "a spanning item shares its shortfall among auto tracks",
+                style: grid(&[Auto, Auto], &[Points(10.0), Points(10.0)]),
+                items: vec![GridItem::at(0, 0).span(1, 2), GridItem::at(1, 0)],
+                content: vec![Size::new(50.0, 10.0), Size::new(10.0, 10.0)],
+                expected: vec![rect(0.0, 0.0, 50.0, 10.0), rect(0.0, 10.0, 30.0, 10.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn named_areas() {
+        let layout = areas(
+            grid(
+                &[Points(20.0), Fraction(1.0)],
+                &[Points(10.0), Fraction(1.0)],
+            ),
+            &["header header", "side main"],
+        );
+        run(vec![
+            Case {
+                name: "items fill their areas",
+                style: layout.clone(),
+                items: vec![
+                    GridItem::area("header"),
+                    GridItem::area("side"),
+                    GridItem::area("main"),
+                ],
+                content: sizes(3, 5.0, 5.0),
+                expected: vec![
+                    rect(0.0, 0.0, 100.0, 10.0),
+                    rect(0.0, 10.0, 20.0, 90.0),
+                    rect(20.0, 10.0, 80.0, 90.0),
+                ],
+            },
+            Case {
+                name: "an unknown area falls back to the row and column",
+                style: layout,
+                items: vec![GridItem {
+                    area: Some("footer".to_string()),
+                    ..GridItem::at(1, 1)
+                }],
+                content: sizes(1, 5.0, 5.0),
+                expected: vec![rect(20.0, 10.0, 80.0, 90.0)],
+            },
+            Case {
+                name: "an L-shaped area is ignored",
+                style: areas(
+                    grid(&[Points(50.0); 2], &[Points(50.0); 2]),
+                    &["a a", "a b"],
+                ),
+                items: vec![GridItem::area("a"), GridItem::area("b")],
+                content: sizes(2, 5.0, 5.0),
+                expected: vec![rect(0.0, 0.0, 50.0, 50.0), rect(50.0, 50.0, 50.0, 50.0)],
+            },
+            Case {
+                name: "a disjoint area is ignored",
+                style: areas(grid(&[Points(20.0); 3], &[Points(20.0)]), &["a b a"]),
+                items: vec![GridItem::area("b"), GridItem::area("a")],
+                content: sizes(2, 5.0, 5.0),
+                expected: vec![rect(20.0, 0.0, 20.0, 20.0), rect(0.0, 0.0, 20.0, 20.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn auto_placement() {
+        let two_columns = GridStyle {
+            auto_rows: Points(20.0),
+            ..grid(&[Points(50.0); 2], &[])
+        };
+        run(vec![
+            Case {
+                name: "row flow fills rows first",
+                style: two_columns.clone(),
+                items: vec![GridItem::default(); 3],
+                content: sizes(3, 5.0, 5.0),
+                expected: vec![
+                    rect(0.0, 0.0, 50.0, 20.0),
+                    rect(50.0, 0.0, 50.0, 20.0),
+                    rect(0.0, 20.0, 50.0, 20.0),
+                ],
+            },
+            Case {
+                name: "column flow fills columns first",
+                style: GridStyle {
+                    auto_flow: GridAutoFlow::Column,
+                    auto_columns: Points(20.0),
+                    ..grid(&[], &[Points(50.0); 2])
+                },
+                items: vec![GridItem::default(); 3],
+                content: sizes(3, 5.0, 5.0),
+                expected: vec![
+                    rect(0.0, 0.0, 20.0, 50.0),
+                    rect(0.0, 50.0, 20.0, 50.0),
+                    rect(20.0, 0.0, 20.0, 50.0),
+                ],
+            },
+            Case {
+                name: "auto-placed items skip occupied cells",
+                style: two_columns.clone(),
+                items: vec![GridItem::at(0, 1), GridItem::default(), GridItem::default()],
+                content: sizes(3, 5.0, 5.0),
+                expected: vec![
+                    rect(50.0, 0.0, 50.0, 20.0),
+                    rect(0.0, 0.0, 50.0, 20.0),
+                    rect(0.0, 20.0, 50.0, 20.0),
+                ],
+            },
+            Case {
+                name: "a span that does not fit moves to the next row",
+                style: GridStyle {
+                    auto_rows: Points(10.0),
+                    ..grid(&[Points(30.0); 3], &[])
+                },
+                items: vec![
+                    GridItem::default().span(1, 2),
+                    GridItem::default().span(1, 2),
+                ],
+                content: sizes(2, 5.0, 5.0),
+                expected: vec![rect(0.0, 0.0, 60.0, 10.0), rect(0.0, 10.0, 60.0, 10.0)],
+            },
+            Case {
+                name: "an item with only a row takes the first free column in it",
+                style: two_columns,
+                items: vec![
+                    GridItem::at(1, 0),
+                    GridItem {
+                        row: Some(1),
+                        ..GridItem::default()
+                    },
+                ],
+                content: sizes(2, 5.0, 5.0),
+                expected: vec![rect(0.0, 20.0, 50.0, 20.0), rect(50.0, 20.0, 50.0, 20.0)],
+            },
+        ]);
+    }
+
+    #[test]
+    fn measure_sizes_fractions_to_content() {
+        let style = GridStyle {
+            column_gap: 5.0,
+            padding: Edges::all(2.0),
+            ..grid(&[Points(20.0), Fraction(1.0)], &[Auto])
+        };
+        let items = [GridItem::at(0, 0), GridItem::at(0, 1)];
+        let content = [Size::new(10.0, 10.0), Size::new(30.0, 5.0)];
+        assert_eq!(
+            measure_grid(&style, &items, &content),
+            Size::new(59.0, 14.0)
+        );
+    }
+}
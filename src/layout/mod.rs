@@ -0,0 +1,6 @@
+pub mod flex;
+pub mod grid;
+pub mod style;
+
+#[cfg(test)]
+mod test_helpers;
\ No newline at end of file
@@ -0,0 +1,101 @@
+use crate::renderer::primitive::{Rect, Size};
+
+/// A length that is either fixed, relative to the containing block, or
+/// derived from the content.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Dimension {
+    #[default]
+    Auto,
+    Points(f32),
+    /// A fraction of the containing size, from `0.0` to `1.0`.
+    Percent(f32),
+}
+
+impl Dimension {
+    /// Resolves against `container`, or returns `None` for `Auto`.
+    pub fn resolve(self, container: f32) -> Option<f32> {
+        match self {
+            Dimension::Auto => None,
+            Dimension::Points(points) => Some(points),
+            Dimension::Percent(fraction) => Some(container * fraction),
+        }
+    }
+}
+
+/// Space around the four sides of a box, used for padding and margins.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Edges {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+}
+
+impl Edges {
+    pub const ZERO: Edges = Edges::all(0.0);
+
+    pub const fn all(value: f32) -> Self {
+        Edges {
+            top: value,
+            right: value,
+            bottom: value,
+            left: value,
+        }
+    }
+
+    pub const fn symmetric(horizontal: f32, vertical: f32) -> Self {
+        Edges {
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+            left: horizontal,
+        }
+    }
+
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+
+    /// Shrinks `rect` by these edges.
+    pub fn inset(&self, rect: Rect) -> Rect {
+        Rect::new(
+            rect.x + self.left,
+            rect.y + self.top,
+            (rect.width - self.horizontal()).max(0.0),
+            (rect.height - self.vertical()).max(0.0),
+        )
+    }
+}
+
+/// Lower and upper bounds on a box's size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeConstraints {
+    pub min: Size,
+    pub max: Size,
+}
+
+impl SizeConstraints {
+    pub const UNBOUNDED: SizeConstraints = SizeConstraints {
+        min: Size::ZERO,
+        max: Size::new(f32::INFINITY, f32::INFINITY),
+    };
+
+    /// Clamps `size` into range. As in CSS, the minimum wins if it exceeds
+    /// the maximum.
+    pub fn clamp(&self, size: Size) -> Size {
+        Size::new(
+            size.width.min(self.max.width).max(self.min.width),
+            size.height.min(self.max.height).max(self.min.height),
+        )
+    }
+}
+
+impl Default for SizeConstraints {
+    fn default() -> Self {
+        SizeConstraints::UNBOUNDED
+    }
+}
@@ -0,0 +1,30 @@
+//! Fixtures shared by the layout tests.
+
+use crate::renderer::primitive::{Rect, Size};
+
+/// `count` items with content of the same size.
+pub fn sizes(count: usize, width: f32, height: f32) -> Vec<Size> {
+    vec![Size::new(width, height); count]
+}
+
+pub fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+    Rect::new(x, y, width, height)
+}
+
+/// Asserts that `actual` matches `expected` up to rounding, naming the
+/// case that failed.
+pub fn assert_rects(name: &str, actual: &[Rect], expected: &[Rect]) {
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+    let matches = actual.len() == expected.len()
+        && actual.iter().zip(expected).all(|(a, e)| {
+            close(a.x, e.x)
+                && close(a.y, e.y)
+                && close(a.width, e.width)
+                && close(a.height, e.height)
+        });
+    assert!(
+        matches,
+        "{}:\n  actual   {:?}\n  expected {:?}",
+        name, actual, expected
+    );
+}
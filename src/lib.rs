@@ -1,3 +1,12 @@
 pub mod application;
+pub mod error;
+pub mod layout;
 pub mod renderer;
+pub mod text;
+pub mod testing;
 pub mod widget;
+
+pub use error::{Error, Result};
+
+// Lets derived code name this crate as `::uplift` from inside it too.
+extern crate self as uplift;
@@ -1,9 +1,8 @@
-use application::run;
-use instance::AshInstance;
+use uplift::application::app::App;
 
 fn main() {
-    let app_name = "Ash GUI";
-    let ash_instance = AshInstance::new(app_name).expect("Failed to create Ash instance");
-
-    run();
+    if let Err(err) = App::builder().title("Ash GUI").run() {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
 }
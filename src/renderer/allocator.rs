@@ -0,0 +1,624 @@
+use ash::vk;
+use std::ops::Range;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::device::LogicalDevice;
+use super::instance::AshInstance;
+use crate::error::{Error, Result};
+
+/// Largest size of the blocks allocations are carved from. Smaller heaps
+/// get blocks of an eighth of their size.
+pub const MAX_BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+/// Sub-allocates buffer and image memory from large `vk::DeviceMemory`
+/// blocks, one pool of blocks per memory type. Where the device's
+/// `bufferImageGranularity` would require padding between linear and
+/// optimal resources, each memory type has a pool per [`ResourceKind`]
+/// instead, so neighbours never need it.
+///
+/// Blocks of host-visible types stay mapped for as long as they live, so
+/// allocations from them can be written without mapping. Allocations too
+/// large to share a block get one of their own. Blocks are freed when
+/// their last allocation is, except for one spare per memory type.
+///
+/// With `VK_EXT_memory_budget` enabled on the device, [`Self::heap_usage`]
+/// includes the budget the driver gives the process, and a warning is
+/// logged when a new block would exceed it.
+pub struct Allocator {
+    physical_device: vk::PhysicalDevice,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    /// Alignment that keeps linear buffers and optimal images from sharing
+    /// a page.
+    granularity: vk::DeviceSize,
+    /// Indexed by [`pool_index`].
+    pools: Mutex<Vec<Pool>>,
+    next_block_id: AtomicU64,
+    device: Arc<LogicalDevice>,
+    instance: Arc<AshInstance>,
+}
+
+/// Whether memory is for a linear resource, such as a buffer, or an
+/// optimally tiled image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    Linear,
+    Optimal,
+}
+
+/// The blocks of one memory type, for one kind of resource if the two are
+/// kept apart.
+#[derive(Default)]
+struct Pool {
+    blocks: Vec<Block>,
+}
+
+struct Block {
+    id: u64,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    /// Start of the mapping of the whole block, if host visible.
+    mapped: Option<NonNull<u8>>,
+    /// Free ranges, sorted by offset and never adjacent.
+    free: Vec<Range<vk::DeviceSize>>,
+    /// Bytes in use by allocations, not counting alignment padding.
+    used: vk::DeviceSize,
+    /// Holds a single large allocation and is freed with it.
+    dedicated: bool,
+}
+
+// The mapping is only accessed through allocations, which own disjoint
+// ranges of it.
+unsafe impl Send for Block {}
+
+/// Memory use of one heap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapUsage {
+    pub heap_index: u32,
+    pub flags: vk::MemoryHeapFlags,
+    pub size: vk::DeviceSize,
+    /// Bytes in blocks allocated by this allocator.
+    pub allocated: vk::DeviceSize,
+    /// Bytes of those blocks in use.
+    pub used: vk::DeviceSize,
+    /// How much of the heap the process should stay within, if the device
+    /// has `VK_EXT_memory_budget` enabled.
+    pub budget: Option<vk::DeviceSize>,
+    /// How much of the heap the whole process uses, if the device has
+    /// `VK_EXT_memory_budget` enabled.
+    pub usage: Option<vk::DeviceSize>,
+}
+
+impl Allocator {
+    pub fn new(
+        instance: &Arc<AshInstance>,
+        physical_device: vk::PhysicalDevice,
+        device: &Arc<LogicalDevice>,
+    ) -> Self {
+        let (memory_properties, properties) = unsafe {
+            (
+                instance
+                    .instance
+                    .get_physical_device_memory_properties(physical_device),
+                instance
+                    .instance
+                    .get_physical_device_properties(physical_device),
+            )
+        };
+        let pools = (0..memory_properties.memory_type_count * 2)
+            .map(|_| Pool::default())
+            .collect();
+
+        Allocator {
+            physical_device,
+            memory_properties,
+            granularity: properties.limits.buffer_image_granularity.max(1),
+            pools: Mutex::new(pools),
+            next_block_id: AtomicU64::new(0),
+            device: device.clone(),
+            instance: instance.clone(),
+        }
+    }
+
+    /// Allocates memory meeting `requirements` from a memory type with all
+    /// of `properties`, for a resource of the given kind.
+    pub fn allocate(
+        self: &Arc<Self>,
+        requirements: vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+        kind: ResourceKind,
+    ) -> Result<Allocation> {
+        let memory_type = self
+            .find_memory_type(requirements.memory_type_bits, properties)
+            .ok_or(Error::NoMemoryType { properties })?;
+        let alignment = requirements.alignment;
+        let size = requirements.size;
+        let block_size = self.block_size(memory_type);
+
+        let pool_index = pool_index(self.granularity, memory_type, kind);
+        let mut pools = self.lock_pools();
+        let pool = &mut pools[pool_index];
+
+        let shared = size <= block_size / 2;
+        if shared {
+            if let Some(allocation) = pool
+                .blocks
+                .iter_mut()
+                .filter(|block| !block.dedicated)
+                .find_map(|block| block.take(size, alignment))
+            {
+                return Ok(self.allocation(pool_index, allocation));
+            }
+        }
+
+        let block = match shared.then(|| self.create_block(memory_type, block_size, false)) {
+            Some(Ok(block)) => block,
+            // Try again with no more than is needed.
+            _ => self.create_block(memory_type, size, true)?,
+        };
+        pool.blocks.push(block);
+        let block = pool.blocks.last_mut().unwrap();
+        let allocation = block
+            .take(size, alignment)
+            .expect("a new block fits its first allocation");
+        Ok(self.allocation(pool_index, allocation))
+    }
+
+    /// Frees every block that no allocation uses.
+    pub fn trim(&self) {
+        let mut pools = self.lock_pools();
+        for pool in pools.iter_mut() {
+            pool.blocks.retain(|block| {
+                let empty = block.used == 0;
+                if empty {
+                    self.free_block(block);
+                }
+                !empty
+            });
+        }
+    }
+
+    /// Memory use of each heap.
+    pub fn heap_usage(&self) -> Vec<HeapUsage> {
+        let heap_count = self.memory_properties.memory_heap_count as usize;
+        let mut usage: Vec<HeapUsage> = self.memory_properties.memory_heaps[..heap_count]
+            .iter()
+            .enumerate()
+            .map(|(i, heap)| HeapUsage {
+                heap_index: i as u32,
+                flags: heap.flags,
+                size: heap.size,
+                allocated: 0,
+                used: 0,
+                budget: None,
+                usage: None,
+            })
+            .collect();
+
+        for (index, pool) in self.lock_pools().iter().enumerate() {
+            let memory_type = index / 2;
+            let heap = self.memory_properties.memory_types[memory_type].heap_index as usize;
+            for block in &pool.blocks {
+                usage[heap].allocated += block.size;
+                usage[heap].used += block.used;
+            }
+        }
+
+        if let Some(budget) = self.query_budget() {
+            for heap in &mut usage {
+                heap.budget = Some(budget.budget[heap.heap_index as usize]);
+                heap.usage = Some(budget.usage[heap.heap_index as usize]);
+            }
+        }
+        usage
+    }
+
+    fn lock_pools(&self) -> MutexGuard<'_, Vec<Pool>> {
+        self.pools.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn find_memory_type(
+        &self,
+        type_filter: u32,
+        properties: vk::MemoryPropertyFlags,
+    ) -> Option<u32> {
+        (0..self.memory_properties.memory_type_count).find(|&i| {
+            type_filter & (1 << i) != 0
+                && self.memory_properties.memory_types[i as usize]
+                    .property_flags
+                    .contains(properties)
+        })
+    }
+
+    fn block_size(&self, memory_type: u32) -> vk::DeviceSize {
+        let heap_index = self.memory_properties.memory_types[memory_type as usize].heap_index;
+        let heap_size = self.memory_properties.memory_heaps[heap_index as usize].size;
+        (heap_size / 8).min(MAX_BLOCK_SIZE)
+    }
+
+    fn create_block(
+        &self,
+        memory_type: u32,
+        size: vk::DeviceSize,
+        dedicated: bool,
+    ) -> Result<Block> {
+        let memory_type_info = self.memory_properties.memory_types[memory_type as usize];
+        if let Some(budget) = self.query_budget() {
+            let heap = memory_type_info.heap_index as usize;
+            if budget.usage[heap] + size > budget.budget[heap] {
+                log::warn!(
+                    "allocating {} bytes exceeds the budget of memory heap {}: {} of {} bytes in use",
+                    size,
+                    heap,
+                    budget.usage[heap],
+                    budget.budget[heap]
+                );
+            }
+        }
+
+        let alloc_info = vk::MemoryAllocateInfo::default()
+            .allocation_size(size)
+            .memory_type_index(memory_type);
+        let memory = unsafe { self.device.allocate_memory(&alloc_info, None)? };
+
+        let mapped = if memory_type_info
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE)
+        {
+            let mapped = unsafe {
+                self.device
+                    .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+                    .inspect_err(|_| self.device.free_memory(memory, None))?
+            };
+            NonNull::new(mapped as *mut u8)
+        } else {
+            None
+        };
+
+        let id = self.next_block_id.fetch_add(1, Ordering::Relaxed);
+        self.device.set_object_name(
+            memory,
+            &format!("memory block {} of type {}", id, memory_type),
+        );
+
+        Ok(Block {
+            id,
+            memory,
+            size,
+            mapped,
+            free: std::iter::once(0..size).collect(),
+            used: 0,
+            dedicated,
+        })
+    }
+
+    fn free_block(&self, block: &Block) {
+        unsafe {
+            // Freeing memory unmaps it.
+            self.device.free_memory(block.memory, None);
+        }
+    }
+
+    /// Returns the range of `allocation` to its block, freeing the block if
+    /// it is no longer needed.
+    fn free(&self, allocation: &Allocation) {
+        let mut pools = self.lock_pools();
+        let pool = &mut pools[allocation.pool];
+        let Some(index) = pool
+            .blocks
+            .iter()
+            .position(|block| block.id == allocation.block_id)
+        else {
+            return;
+        };
+
+        let block = &mut pool.blocks[index];
+        block.give_back(allocation.range.clone());
+        if block.used > 0 {
+            return;
+        }
+        // Keep one empty shared block around, so a pool that is emptied and
+        // refilled every frame does not allocate every frame.
+        let spare = !block.dedicated
+            && !pool.blocks.iter().any(|other| {
+                other.id != allocation.block_id && !other.dedicated && other.used == 0
+            });
+        if !spare {
+            let block = pool.blocks.swap_remove(index);
+            self.free_block(&block);
+        }
+    }
+
+    fn query_budget(&self) -> Option<Budget> {
+        if !self
+            .device
+            .is_extension_enabled(ash::ext::memory_budget::NAME)
+        {
+            return None;
+        }
+        let mut budget = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+        let mut properties = vk::PhysicalDeviceMemoryProperties2::default().push_next(&mut budget);
+        unsafe {
+            self.instance
+                .instance
+                .get_physical_device_memory_properties2(self.physical_device, &mut properties);
+        }
+        Some(Budget {
+            budget: budget.heap_budget,
+            usage: budget.heap_usage,
+        })
+    }
+
+    fn allocation(self: &Arc<Self>, pool: usize, taken: Taken) -> Allocation {
+        Allocation {
+            memory: taken.memory,
+            range: taken.range,
+            pool,
+            block_id: taken.block_id,
+            mapped: taken.mapped,
+            allocator: self.clone(),
+        }
+    }
+}
+
+/// All allocations hold the allocator, so their blocks are all empty by now.
+impl Drop for Allocator {
+    fn drop(&mut self) {
+        let pools = std::mem::take(&mut *self.lock_pools());
+        for block in pools.iter().flat_map(|pool| &pool.blocks) {
+            self.free_block(block);
+        }
+    }
+}
+
+/// The pool of `memory_type` that resources of `kind` are allocated from.
+/// Both kinds share one unless `granularity` would require padding between
+/// them.
+fn pool_index(granularity: vk::DeviceSize, memory_type: u32, kind: ResourceKind) -> usize {
+    let kind = match kind {
+        ResourceKind::Optimal if granularity > 1 => 1,
+        _ => 0,
+    };
+    memory_type as usize * 2 + kind
+}
+
+/// What `VK_EXT_memory_budget` reports for each heap.
+struct Budget {
+    budget: [vk::DeviceSize; vk::MAX_MEMORY_HEAPS],
+    usage: [vk::DeviceSize; vk::MAX_MEMORY_HEAPS],
+}
+
+/// A range taken from a block.
+struct Taken {
+    memory: vk::DeviceMemory,
+    range: Range<vk::DeviceSize>,
+    block_id: u64,
+    mapped: Option<NonNull<u8>>,
+}
+
+impl Block {
+    /// Takes the first free range that fits `size` bytes at `alignment`.
+    fn take(&mut self, size: vk::DeviceSize, alignment: vk::DeviceSize) -> Option<Taken> {
+        let (index, start) = self.free.iter().enumerate().find_map(|(i, range)| {
+            let start = range.start.next_multiple_of(alignment);
+            (start + size <= range.end).then_some((i, start))
+        })?;
+
+        let range = self.free.remove(index);
+        let end = start + size;
+        if end < range.end {
+            self.free.insert(index, end..range.end);
+        }
+        if range.start < start {
+            self.free.insert(index, range.start..start);
+        }
+        self.used += size;
+
+        Some(Taken {
+            memory: self.memory,
+            range: start..end,
+            block_id: self.id,
+            mapped: self
+                .mapped
+                .map(|mapped| unsafe { mapped.add(start as usize) }),
+        })
+    }
+
+    /// Frees `range`, merging it with the free ranges around it.
+    fn give_back(&mut self, range: Range<vk::DeviceSize>) {
+        self.used -= range.end - range.start;
+
+        let index = self.free.partition_point(|free| free.start < range.start);
+        let mut merged = range;
+        if let Some(next) = self.free.get(index).filter(|next| next.start == merged.end) {
+            merged.end = next.end;
+            self.free.remove(index);
+        }
+        if let Some(previous) = index
+            .checked_sub(1)
+            .and_then(|i| self.free.get_mut(i))
+            .filter(|previous| previous.end == merged.start)
+        {
+            previous.end = merged.end;
+        } else {
+            self.free.insert(index, merged);
+        }
+    }
+}
+
+/// A range of device memory from an [`Allocator`], returned to it on drop.
+/// Whatever is bound to it must be destroyed first.
+pub struct Allocation {
+    memory: vk::DeviceMemory,
+    range: Range<vk::DeviceSize>,
+    /// Index of the pool of the block in [`Allocator::pools`].
+    pool: usize,
+    block_id: u64,
+    /// Start of the range in the block's mapping, if host visible.
+    mapped: Option<NonNull<u8>>,
+    allocator: Arc<Allocator>,
+}
+
+// The allocation owns its range of the mapping.
+unsafe impl Send for Allocation {}
+
+impl Allocation {
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    /// Offset of the allocation in [`Self::memory`], to bind at.
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.range.start
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.range.end - self.range.start
+    }
+
+    /// The mapped allocation, if its memory is host visible.
+    pub fn mapped(&self) -> Option<NonNull<u8>> {
+        self.mapped
+    }
+
+    /// Copies `bytes` to the start of the allocation, which must be host
+    /// visible and coherent.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is larger than the allocation.
+    pub fn write(&self, bytes: &[u8]) -> Result<()> {
+        self.write_at(0, bytes)
+    }
+
+    /// Copies `bytes` to `offset` in the allocation, which must be host
+    /// visible and coherent.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` does not fit at `offset`.
+    pub fn write_at(&self, offset: vk::DeviceSize, bytes: &[u8]) -> Result<()> {
+        let mapped = self.mapped_for(offset + bytes.len() as vk::DeviceSize)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                mapped.as_ptr().add(offset as usize),
+                bytes.len(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Copies the start of the allocation into `bytes`. It must be host
+    /// visible and coherent.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is larger than the allocation.
+    pub fn read(&self, bytes: &mut [u8]) -> Result<()> {
+        let mapped = self.mapped_for(bytes.len() as vk::DeviceSize)?;
+        unsafe {
+            std::ptr::copy_nonoverlapping(mapped.as_ptr(), bytes.as_mut_ptr(), bytes.len());
+        }
+        Ok(())
+    }
+
+    fn mapped_for(&self, len: vk::DeviceSize) -> Result<NonNull<u8>> {
+        assert!(
+            len <= self.size(),
+            "{} bytes do not fit an allocation of {}",
+            len,
+            self.size()
+        );
+        self.mapped
+            .ok_or(Error::Vulkan(vk::Result::ERROR_MEMORY_MAP_FAILED))
+    }
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        self.allocator.free(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(size: vk::DeviceSize) -> Block {
+        Block {
+            id: 0,
+            memory: vk::DeviceMemory::null(),
+            size,
+            mapped: None,
+            free: std::iter::once(0..size).collect(),
+            used: 0,
+            dedicated: false,
+        }
+    }
+
+    /// The free ranges of `block` as pairs, to compare against.
+    fn free(block: &Block) -> Vec<(u64, u64)> {
+        block
+            .free
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    fn take(block: &mut Block, size: vk::DeviceSize, alignment: vk::DeviceSize) -> Range<u64> {
+        block.take(size, alignment).unwrap().range
+    }
+
+    #[test]
+    fn take_aligns_and_keeps_the_padding_free() {
+        let mut block = block(64);
+        assert_eq!(take(&mut block, 10, 1), 0..10);
+        assert_eq!(take(&mut block, 4, 16), 16..20);
+        assert_eq!(free(&block), [(10, 16), (20, 64)]);
+        assert_eq!(block.used, 14);
+
+        // The padding is used by allocations that fit in it.
+        assert_eq!(take(&mut block, 6, 2), 10..16);
+        assert_eq!(free(&block), [(20, 64)]);
+        assert!(block.take(48, 1).is_none());
+    }
+
+    #[test]
+    fn give_back_merges_with_free_neighbours() {
+        let mut block = block(30);
+        let ranges: Vec<_> = (0..3).map(|_| take(&mut block, 10, 1)).collect();
+        assert!(block.free.is_empty());
+
+        block.give_back(ranges[1].clone());
+        assert_eq!(free(&block), [(10, 20)]);
+        block.give_back(ranges[0].clone());
+        assert_eq!(free(&block), [(0, 20)]);
+        block.give_back(ranges[2].clone());
+        assert_eq!(free(&block), [(0, 30)]);
+        assert_eq!(block.used, 0);
+    }
+
+    #[test]
+    fn give_back_between_allocations_stays_separate() {
+        let mut block = block(40);
+        let ranges: Vec<_> = (0..4).map(|_| take(&mut block, 10, 1)).collect();
+
+        block.give_back(ranges[0].clone());
+        block.give_back(ranges[2].clone());
+        assert_eq!(free(&block), [(0, 10), (20, 30)]);
+        block.give_back(ranges[3].clone());
+        assert_eq!(free(&block), [(0, 10), (20, 40)]);
+        block.give_back(ranges[1].clone());
+        assert_eq!(free(&block), [(0, 40)]);
+    }
+
+    #[test]
+    fn kinds_share_a_pool_unless_granularity_requires_padding() {
+        assert_eq!(pool_index(1, 3, ResourceKind::Linear), 6);
+        assert_eq!(pool_index(1, 3, ResourceKind::Optimal), 6);
+        assert_eq!(pool_index(1024, 3, ResourceKind::Linear), 6);
+        assert_eq!(pool_index(1024, 3, ResourceKind::Optimal), 7);
+    }
+}
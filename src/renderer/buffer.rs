@@ -0,0 +1,172 @@
+use ash::vk;
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+use super::device::AshDevice;
+use super::memory::OwnedBuffer;
+use crate::error::Result;
+
+/// What a [`Buffer`] is bound as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferKind {
+    Vertex,
+    Index,
+    Uniform,
+}
+
+impl BufferKind {
+    fn usage(self) -> vk::BufferUsageFlags {
+        match self {
+            BufferKind::Vertex => vk::BufferUsageFlags::VERTEX_BUFFER,
+            BufferKind::Index => vk::BufferUsageFlags::INDEX_BUFFER,
+            BufferKind::Uniform => vk::BufferUsageFlags::UNIFORM_BUFFER,
+        }
+    }
+}
+
+/// An element type of index buffers.
+pub trait IndexType: Pod {
+    const INDEX_TYPE: vk::IndexType;
+}
+
+impl IndexType for u16 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT16;
+}
+
+impl IndexType for u32 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT32;
+}
+
+/// A host-visible buffer of `T`s that grows to fit what is written to it.
+///
+/// No memory is allocated until something is written. Growing replaces
+/// the buffer, so it must not be in use by pending work when written.
+pub struct Buffer<T> {
+    kind: BufferKind,
+    /// Given to each buffer created, for graphics debuggers.
+    name: Option<String>,
+    len: usize,
+    buffer: Option<OwnedBuffer>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> Buffer<T> {
+    pub fn new(kind: BufferKind) -> Self {
+        Buffer {
+            kind,
+            name: None,
+            len: 0,
+            buffer: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// A vertex buffer holding `vertices`.
+    pub fn vertex(device: &AshDevice, vertices: &[T]) -> Result<Self> {
+        let mut buffer = Self::new(BufferKind::Vertex);
+        buffer.write(device, vertices)?;
+        Ok(buffer)
+    }
+
+    /// A uniform buffer holding `value`.
+    pub fn uniform(device: &AshDevice, value: &T) -> Result<Self> {
+        let mut buffer = Self::new(BufferKind::Uniform);
+        buffer.write(device, std::slice::from_ref(value))?;
+        Ok(buffer)
+    }
+
+    pub fn kind(&self) -> BufferKind {
+        self.kind
+    }
+
+    /// Names the buffer, and those replacing it as it grows, for graphics
+    /// debuggers.
+    pub fn set_name(&mut self, name: &str) {
+        if let Some(buffer) = &self.buffer {
+            buffer.set_name(name);
+        }
+        self.name = Some(name.to_string());
+    }
+
+    /// Number of elements last written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of elements that fit without growing.
+    pub fn capacity(&self) -> usize {
+        self.buffer.as_ref().map_or(0, |buffer| {
+            (buffer.size / std::mem::size_of::<T>().max(1) as vk::DeviceSize) as usize
+        })
+    }
+
+    /// The buffer, or null if nothing has been written yet.
+    pub fn handle(&self) -> vk::Buffer {
+        self.buffer
+            .as_ref()
+            .map_or(vk::Buffer::null(), |buffer| buffer.buffer)
+    }
+
+    /// Makes room for at least `capacity` elements, discarding the contents
+    /// if the buffer has to grow.
+    pub fn reserve(&mut self, device: &AshDevice, capacity: usize) -> Result<()> {
+        if capacity <= self.capacity() {
+            return Ok(());
+        }
+        // Left empty if the new buffer cannot be created.
+        self.buffer = None;
+        self.len = 0;
+
+        let size = (capacity.next_power_of_two() * std::mem::size_of::<T>()).max(1);
+        let buffer = OwnedBuffer::new(
+            device,
+            size as vk::DeviceSize,
+            self.kind.usage(),
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        if let Some(name) = &self.name {
+            buffer.set_name(name);
+        }
+        self.buffer = Some(buffer);
+        Ok(())
+    }
+
+    /// Replaces the contents with `data`, growing the buffer if needed.
+    pub fn write(&mut self, device: &AshDevice, data: &[T]) -> Result<()> {
+        self.reserve(device, data.len())?;
+        if let Some(buffer) = &self.buffer {
+            buffer.write(bytemuck::cast_slice(data))?;
+        }
+        self.len = data.len();
+        Ok(())
+    }
+
+    /// Describes the written elements for a uniform buffer descriptor.
+    pub fn descriptor_info(&self) -> vk::DescriptorBufferInfo {
+        vk::DescriptorBufferInfo {
+            buffer: self.handle(),
+            offset: 0,
+            range: (self.len * std::mem::size_of::<T>()) as vk::DeviceSize,
+        }
+    }
+}
+
+impl<T: IndexType> Buffer<T> {
+    /// An index buffer holding `indices`.
+    pub fn index(device: &AshDevice, indices: &[T]) -> Result<Self> {
+        let mut buffer = Self::new(BufferKind::Index);
+        buffer.write(device, indices)?;
+        Ok(buffer)
+    }
+
+    /// Binds the buffer as the index buffer of `command_buffer`.
+    pub fn cmd_bind_index(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            device.cmd_bind_index_buffer(command_buffer, self.handle(), 0, T::INDEX_TYPE);
+        }
+    }
+}
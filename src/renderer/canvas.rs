@@ -0,0 +1,122 @@
+use crate::text::shaping::ShapedText;
+
+use super::primitive::{Color, Glyph, Image, Rect};
+
+/// A backend-neutral drawing surface that widgets paint against.
+///
+/// Coordinates are in pixels with the origin at the top-left of the target.
+/// Clips nest: each `push_clip` is intersected with the one below it and
+/// must be balanced by a `pop_clip`.
+pub trait Canvas {
+    /// Fills `rect` with a solid color.
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.fill_quad(rect, [color; 4]);
+    }
+
+    /// Fills `rect` with per-corner colors, ordered top-left, top-right,
+    /// bottom-right, bottom-left.
+    fn fill_quad(&mut self, rect: Rect, colors: [Color; 4]);
+
+    /// Draws a run of glyph coverage masks tinted with `color`. GPU canvases
+    /// cache masks by address, so reuse the same ones from frame to frame.
+    fn draw_glyph_run(&mut self, glyphs: &[Glyph], color: Color);
+
+    /// Draws a shaped line of text with the top-left corner of its line box
+    /// at `(x, y)`. The canvas rasterizes and caches the glyphs itself.
+    fn draw_text(&mut self, text: &ShapedText, x: f32, y: f32, color: Color);
+
+    /// Draws `image` scaled to fill `dest`. GPU canvases cache images by
+    /// address, so reuse the same one from frame to frame.
+    fn draw_image(&mut self, dest: Rect, image: &Image);
+
+    fn push_clip(&mut self, rect: Rect);
+
+    fn pop_clip(&mut self);
+
+    /// Opens a named group of draws, shown by graphics debuggers. Must be
+    /// balanced by a `pop_label`. Ignored by default.
+    fn push_label(&mut self, _name: &str) {}
+
+    fn pop_label(&mut self) {}
+}
+
+/// Wraps another canvas and scales every coordinate by `scale`, so widgets
+/// can paint in logical pixels on a high-DPI target.
+///
+/// Glyph masks and images are positioned in logical pixels but are not
+/// resampled; rasterize them at the device scale instead. Shaped text is
+/// scaled before rasterization, so it stays sharp.
+pub struct ScaledCanvas<'a> {
+    inner: &'a mut dyn Canvas,
+    scale: f32,
+}
+
+impl<'a> ScaledCanvas<'a> {
+    pub fn new(inner: &'a mut dyn Canvas, scale: f32) -> Self {
+        ScaledCanvas { inner, scale }
+    }
+
+    fn scale_rect(&self, rect: Rect) -> Rect {
+        Rect::new(
+            rect.x * self.scale,
+            rect.y * self.scale,
+            rect.width * self.scale,
+            rect.height * self.scale,
+        )
+    }
+}
+
+impl Canvas for ScaledCanvas<'_> {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let rect = self.scale_rect(rect);
+        self.inner.fill_rect(rect, color);
+    }
+
+    fn fill_quad(&mut self, rect: Rect, colors: [Color; 4]) {
+        let rect = self.scale_rect(rect);
+        self.inner.fill_quad(rect, colors);
+    }
+
+    fn draw_glyph_run(&mut self, glyphs: &[Glyph], color: Color) {
+        let glyphs: Vec<Glyph> = glyphs
+            .iter()
+            .map(|glyph| Glyph {
+                x: glyph.x * self.scale,
+                y: glyph.y * self.scale,
+                mask: glyph.mask,
+            })
+            .collect();
+        self.inner.draw_glyph_run(&glyphs, color);
+    }
+
+    fn draw_text(&mut self, text: &ShapedText, x: f32, y: f32, color: Color) {
+        self.inner.draw_text(
+            &text.scaled(self.scale),
+            x * self.scale,
+            y * self.scale,
+            color,
+        );
+    }
+
+    fn draw_image(&mut self, dest: Rect, image: &Image) {
+        let dest = self.scale_rect(dest);
+        self.inner.draw_image(dest, image);
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        let rect = self.scale_rect(rect);
+        self.inner.push_clip(rect);
+    }
+
+    fn pop_clip(&mut self) {
+        self.inner.pop_clip();
+    }
+
+    fn push_label(&mut self, name: &str) {
+        self.inner.push_label(name);
+    }
+
+    fn pop_label(&mut self) {
+        self.inner.pop_label();
+    }
+}
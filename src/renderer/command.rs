@@ -1,76 +1,87 @@
 use ash::vk;
-use ash::Device;
 use std::sync::Arc;
 
+use super::device::LogicalDevice;
+use crate::error::Result;
+
+/// A command pool. Its command buffers are freed when it is dropped.
 pub struct CommandPool {
     pub pool: vk::CommandPool,
+    device: Arc<LogicalDevice>,
 }
 
 impl CommandPool {
-    pub fn new(device: &Arc<Device>, queue_family_index: u32) -> Self {
+    pub fn new(device: &Arc<LogicalDevice>, queue_family_index: u32) -> Result<Self> {
         let pool_info = vk::CommandPoolCreateInfo::default()
             .queue_family_index(queue_family_index)
             .flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
 
-        let pool = unsafe {
-            device
-                .create_command_pool(&pool_info, None)
-                .expect("Failed to create command pool!")
-        };
+        let pool = unsafe { device.create_command_pool(&pool_info, None)? };
+
+        Ok(CommandPool {
+            pool,
+            device: device.clone(),
+        })
+    }
 
-        CommandPool { pool }
+    pub fn device(&self) -> &Arc<LogicalDevice> {
+        &self.device
     }
+}
 
-    pub fn cleanup(&self, device: &Arc<Device>) {
+impl Drop for CommandPool {
+    fn drop(&mut self) {
         unsafe {
-            device.destroy_command_pool(self.pool, None);
+            self.device.destroy_command_pool(self.pool, None);
         }
     }
 }
 
 pub struct CommandBuffers {
     pub buffers: Vec<vk::CommandBuffer>,
+    device: Arc<LogicalDevice>,
 }
 
 impl CommandBuffers {
-    pub fn new(device: &Arc<Device>, command_pool: &CommandPool, count: u32) -> Self {
+    pub fn new(
+        device: &Arc<LogicalDevice>,
+        command_pool: &CommandPool,
+        count: u32,
+    ) -> Result<Self> {
         let alloc_info = vk::CommandBufferAllocateInfo::default()
             .command_pool(command_pool.pool)
             .level(vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(count);
 
-        let buffers = unsafe {
-            device
-                .allocate_command_buffers(&alloc_info)
-                .expect("Failed to allocate command buffers!")
-        };
+        let buffers = unsafe { device.allocate_command_buffers(&alloc_info)? };
 
-        CommandBuffers { buffers }
+        Ok(CommandBuffers {
+            buffers,
+            device: device.clone(),
+        })
     }
 
-    pub fn begin_command_buffer(&self, buffer_index: usize) {
+    pub fn begin_command_buffer(&self, buffer_index: usize) -> Result<()> {
         let begin_info = vk::CommandBufferBeginInfo::default()
             .flags(vk::CommandBufferUsageFlags::SIMULTANEOUS_USE);
 
         unsafe {
             self.device()
-                .begin_command_buffer(self.buffers[buffer_index], &begin_info)
-                .expect("Failed to begin recording command buffer!");
+                .begin_command_buffer(self.buffers[buffer_index], &begin_info)?;
         }
+        Ok(())
     }
 
-    pub fn end_command_buffer(&self, buffer_index: usize) {
+    pub fn end_command_buffer(&self, buffer_index: usize) -> Result<()> {
         unsafe {
             self.device()
-                .end_command_buffer(self.buffers[buffer_index])
-                .expect("Failed to end recording command buffer!");
+                .end_command_buffer(self.buffers[buffer_index])?;
         }
+        Ok(())
     }
 
-    pub fn device(&self) -> &Arc<Device> {
-        // Assuming CommandBuffers is part of a larger structure that includes a reference to the device.
-        // You may need to modify this function to get the actual device reference.
-        unimplemented!()
+    pub fn device(&self) -> &Arc<LogicalDevice> {
+        &self.device
     }
 
     pub fn record_commands(
@@ -79,15 +90,14 @@ impl CommandBuffers {
         render_pass: vk::RenderPass,
         framebuffer: vk::Framebuffer,
         extent: vk::Extent2D,
-    ) {
+    ) -> Result<()> {
         let command_buffer = self.buffers[buffer_index];
 
         let begin_info = vk::CommandBufferBeginInfo::default();
 
         unsafe {
             self.device()
-                .begin_command_buffer(command_buffer, &begin_info)
-                .expect("Failed to begin recording command buffer!");
+                .begin_command_buffer(command_buffer, &begin_info)?;
         }
 
         let clear_values = [vk::ClearValue {
@@ -116,17 +126,16 @@ impl CommandBuffers {
 
             self.device().cmd_end_render_pass(command_buffer);
 
-            self.device()
-                .end_command_buffer(command_buffer)
-                .expect("Failed to record command buffer!");
+            self.device().end_command_buffer(command_buffer)?;
         }
+        Ok(())
     }
 }
 
 pub fn create_sync_objects(
-    device: &Arc<Device>,
+    device: &Arc<LogicalDevice>,
     max_frames_in_flight: usize,
-) -> (Vec<vk::Semaphore>, Vec<vk::Semaphore>, Vec<vk::Fence>) {
+) -> Result<(Vec<vk::Semaphore>, Vec<vk::Semaphore>, Vec<vk::Fence>)> {
     let semaphore_info = vk::SemaphoreCreateInfo::default();
 
     let mut image_available_semaphores = Vec::with_capacity(max_frames_in_flight);
@@ -137,27 +146,15 @@ pub fn create_sync_objects(
 
     for _ in 0..max_frames_in_flight {
         unsafe {
-            image_available_semaphores.push(
-                device
-                    .create_semaphore(&semaphore_info, None)
-                    .expect("Failed to create semaphore!"),
-            );
-            render_finished_semaphores.push(
-                device
-                    .create_semaphore(&semaphore_info, None)
-                    .expect("Failed to create semaphore!"),
-            );
-            in_flight_fences.push(
-                device
-                    .create_fence(&fence_info, None)
-                    .expect("Failed to create fence!"),
-            );
+            image_available_semaphores.push(device.create_semaphore(&semaphore_info, None)?);
+            render_finished_semaphores.push(device.create_semaphore(&semaphore_info, None)?);
+            in_flight_fences.push(device.create_fence(&fence_info, None)?);
         }
     }
 
-    (
+    Ok((
         image_available_semaphores,
         render_finished_semaphores,
         in_flight_fences,
-    )
+    ))
 }
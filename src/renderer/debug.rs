@@ -0,0 +1,163 @@
+use ash::ext::debug_utils;
+use ash::{vk, Entry, Instance};
+use std::ffi::{c_void, CStr};
+use std::sync::Mutex;
+
+use crate::error::Result;
+
+/// The Khronos validation layer, enabled by [`DebugConfig::validation`].
+pub const VALIDATION_LAYER: &CStr = c"VK_LAYER_KHRONOS_validation";
+
+/// Set to `1` to panic on validation errors, e.g. when running tests.
+pub const PANIC_ON_VALIDATION_ENV_VAR: &str = "UPLIFT_PANIC_ON_VALIDATION";
+
+/// Target of the log records the validation layer's messages are forwarded
+/// as.
+pub const LOG_TARGET: &str = "uplift::vulkan";
+
+/// Debugging aids enabled on an [`AshInstance`](super::instance::AshInstance).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugConfig {
+    /// Enables the validation layer if it is installed and forwards its
+    /// messages to the `log` crate. On by default in debug builds.
+    pub validation: bool,
+    /// Enables `VK_EXT_debug_utils` if available, so objects are named and
+    /// command buffers labelled in graphics debuggers such as RenderDoc.
+    /// Always on with validation. On by default.
+    pub debug_utils: bool,
+    /// Makes [`AshInstance::check_validation`] panic once the validation
+    /// layer has reported an error. Off unless
+    /// [`PANIC_ON_VALIDATION_ENV_VAR`] is `1`.
+    ///
+    /// [`AshInstance::check_validation`]: super::instance::AshInstance::check_validation
+    pub panic_on_error: bool,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        DebugConfig {
+            validation: cfg!(debug_assertions),
+            debug_utils: true,
+            panic_on_error: std::env::var(PANIC_ON_VALIDATION_ENV_VAR).is_ok_and(|v| v == "1"),
+        }
+    }
+}
+
+/// What the messenger callback records, shared with it through its user
+/// data pointer.
+pub(crate) struct MessengerState {
+    /// Whether validation errors are kept for [`Self::take_errors`].
+    record_errors: bool,
+    errors: Mutex<Vec<String>>,
+}
+
+impl MessengerState {
+    pub fn new(record_errors: bool) -> Self {
+        MessengerState {
+            record_errors,
+            errors: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Validation errors reported since the last call.
+    pub fn take_errors(&self) -> Vec<String> {
+        std::mem::take(&mut *self.errors.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    /// Settings for a messenger reporting to this state. Also chained into
+    /// the instance create info, so instance creation itself is covered.
+    pub fn create_info(&self) -> vk::DebugUtilsMessengerCreateInfoEXT<'_> {
+        vk::DebugUtilsMessengerCreateInfoEXT::default()
+            .message_severity(
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::INFO
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            )
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                    | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+            )
+            .pfn_user_callback(Some(debug_callback))
+            .user_data(self as *const MessengerState as *mut c_void)
+    }
+}
+
+/// A debug utils messenger, destroyed on drop. Must be dropped before the
+/// instance.
+pub(crate) struct DebugMessenger {
+    pub loader: debug_utils::Instance,
+    messenger: vk::DebugUtilsMessengerEXT,
+    /// Boxed so its address, given to the callback, stays put.
+    pub state: Box<MessengerState>,
+}
+
+impl DebugMessenger {
+    pub fn new(entry: &Entry, instance: &Instance, state: Box<MessengerState>) -> Result<Self> {
+        let loader = debug_utils::Instance::new(entry, instance);
+        let messenger = unsafe { loader.create_debug_utils_messenger(&state.create_info(), None)? };
+
+        Ok(DebugMessenger {
+            loader,
+            messenger,
+            state,
+        })
+    }
+}
+
+impl Drop for DebugMessenger {
+    fn drop(&mut self) {
+        unsafe {
+            self.loader
+                .destroy_debug_utils_messenger(self.messenger, None);
+        }
+    }
+}
+
+/// Forwards a message to `log`. Panicking here would unwind into the
+/// driver, so errors are recorded and checked from Rust instead.
+unsafe extern "system" fn debug_callback(
+    severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    types: vk::DebugUtilsMessageTypeFlagsEXT,
+    data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    user_data: *mut c_void,
+) -> vk::Bool32 {
+    // The loader's INFO messages are chatty, so they are only shown at the
+    // debug level.
+    let level = if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+        log::Level::Error
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+        log::Level::Warn
+    } else if severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+        log::Level::Debug
+    } else {
+        log::Level::Trace
+    };
+
+    let Some(data) = data.as_ref() else {
+        return vk::FALSE;
+    };
+    let message = data
+        .message_as_c_str()
+        .map(CStr::to_string_lossy)
+        .unwrap_or_default();
+    let id = data
+        .message_id_name_as_c_str()
+        .map(CStr::to_string_lossy)
+        .unwrap_or_default();
+    log::log!(target: LOG_TARGET, level, "{:?} {}: {}", types, id, message);
+
+    if level == log::Level::Error && types.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+        if let Some(state) = (user_data as *const MessengerState)
+            .as_ref()
+            .filter(|state| state.record_errors)
+        {
+            let mut errors = state.errors.lock().unwrap_or_else(|err| err.into_inner());
+            errors.push(format!("{}: {}", id, message));
+        }
+    }
+
+    // The call that triggered the message must not be aborted.
+    vk::FALSE
+}
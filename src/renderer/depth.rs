@@ -0,0 +1,187 @@
+use ash::vk;
+
+use super::device::AshDevice;
+use super::memory::OwnedImage;
+use crate::error::{Error, Result};
+
+/// Depth formats to use, most preferred first. Every device supports at
+/// least one of `D32_SFLOAT` and `D24_UNORM_S8_UINT` as a depth attachment.
+pub const DEPTH_FORMATS: &[vk::Format] = &[
+    vk::Format::D32_SFLOAT,
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D16_UNORM,
+];
+
+/// The first of [`DEPTH_FORMATS`] the device can use as a depth attachment.
+pub fn find_depth_format(device: &AshDevice) -> Result<vk::Format> {
+    let format = find_supported_format(
+        device,
+        DEPTH_FORMATS,
+        vk::ImageTiling::OPTIMAL,
+        vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+    )?;
+    log::debug!("using depth format {:?}", format);
+    Ok(format)
+}
+
+/// The first of `candidates` that supports `features` with `tiling`.
+pub fn find_supported_format(
+    device: &AshDevice,
+    candidates: &[vk::Format],
+    tiling: vk::ImageTiling,
+    features: vk::FormatFeatureFlags,
+) -> Result<vk::Format> {
+    first_supported(candidates, tiling, features, |format| unsafe {
+        device
+            .instance
+            .instance
+            .get_physical_device_format_properties(device.physical_device, format)
+    })
+    .ok_or(Error::Vulkan(vk::Result::ERROR_FORMAT_NOT_SUPPORTED))
+}
+
+/// The first of `candidates` whose properties, as given by `properties_of`,
+/// support `features` with `tiling`.
+fn first_supported(
+    candidates: &[vk::Format],
+    tiling: vk::ImageTiling,
+    features: vk::FormatFeatureFlags,
+    properties_of: impl Fn(vk::Format) -> vk::FormatProperties,
+) -> Option<vk::Format> {
+    candidates
+        .iter()
+        .copied()
+        .find(|&format| supports(&properties_of(format), tiling, features))
+}
+
+/// Whether a format with `properties` supports `features` with `tiling`.
+fn supports(
+    properties: &vk::FormatProperties,
+    tiling: vk::ImageTiling,
+    features: vk::FormatFeatureFlags,
+) -> bool {
+    let supported = match tiling {
+        vk::ImageTiling::LINEAR => properties.linear_tiling_features,
+        _ => properties.optimal_tiling_features,
+    };
+    supported.contains(features)
+}
+
+/// Whether `format` has a stencil component as well as depth.
+pub fn has_stencil(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT
+    )
+}
+
+/// Creates a depth attachment, named `name` for graphics debuggers.
+pub fn create_depth_image(
+    device: &AshDevice,
+    extent: vk::Extent2D,
+    format: vk::Format,
+    samples: vk::SampleCountFlags,
+    name: &str,
+) -> Result<OwnedImage> {
+    // Views of combined formats used as attachments cover both aspects.
+    let aspect = if has_stencil(format) {
+        vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+    } else {
+        vk::ImageAspectFlags::DEPTH
+    };
+    let image = OwnedImage::new(
+        device,
+        extent,
+        format,
+        vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        samples,
+        aspect,
+    )?;
+    image.set_name(name);
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ATTACHMENT: vk::FormatFeatureFlags = vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT;
+
+    /// Properties of a device that can use `optimal` formats as depth
+    /// attachments with optimal tiling and `linear` ones with linear tiling.
+    fn properties(
+        optimal: &'static [vk::Format],
+        linear: &'static [vk::Format],
+    ) -> impl Fn(vk::Format) -> vk::FormatProperties {
+        move |format| vk::FormatProperties {
+            optimal_tiling_features: if optimal.contains(&format) {
+                ATTACHMENT | vk::FormatFeatureFlags::SAMPLED_IMAGE
+            } else {
+                vk::FormatFeatureFlags::SAMPLED_IMAGE
+            },
+            linear_tiling_features: if linear.contains(&format) {
+                ATTACHMENT
+            } else {
+                vk::FormatFeatureFlags::empty()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn first_depth_format(
+        tiling: vk::ImageTiling,
+        properties_of: impl Fn(vk::Format) -> vk::FormatProperties,
+    ) -> Option<vk::Format> {
+        first_supported(DEPTH_FORMATS, tiling, ATTACHMENT, properties_of)
+    }
+
+    #[test]
+    fn candidates_are_tried_in_order() {
+        let optimal = vk::ImageTiling::OPTIMAL;
+
+        assert_eq!(
+            first_depth_format(optimal, properties(DEPTH_FORMATS, &[])),
+            Some(vk::Format::D32_SFLOAT)
+        );
+        assert_eq!(
+            first_depth_format(
+                optimal,
+                properties(&[vk::Format::D16_UNORM, vk::Format::D24_UNORM_S8_UINT], &[])
+            ),
+            Some(vk::Format::D24_UNORM_S8_UINT)
+        );
+        assert_eq!(first_depth_format(optimal, properties(&[], &[])), None);
+    }
+
+    #[test]
+    fn features_are_checked_for_the_tiling() {
+        let formats = properties(&[vk::Format::D24_UNORM_S8_UINT], &[vk::Format::D16_UNORM]);
+
+        assert_eq!(
+            first_depth_format(vk::ImageTiling::OPTIMAL, &formats),
+            Some(vk::Format::D24_UNORM_S8_UINT)
+        );
+        assert_eq!(
+            first_depth_format(vk::ImageTiling::LINEAR, &formats),
+            Some(vk::Format::D16_UNORM)
+        );
+        // Every candidate can be sampled, but only some are attachments.
+        assert_eq!(
+            first_supported(
+                DEPTH_FORMATS,
+                vk::ImageTiling::OPTIMAL,
+                ATTACHMENT | vk::FormatFeatureFlags::SAMPLED_IMAGE,
+                &formats
+            ),
+            Some(vk::Format::D24_UNORM_S8_UINT)
+        );
+        assert!(!supports(
+            &formats(vk::Format::D32_SFLOAT),
+            vk::ImageTiling::OPTIMAL,
+            ATTACHMENT
+        ));
+    }
+}
@@ -1,119 +1,241 @@
+use ash::ext::debug_utils;
 use ash::vk;
+use std::ffi::{CStr, CString};
+use std::ops::Deref;
 use std::sync::Arc;
-use ash::khr::surface::Instance as SurfaceInstance;
 
+use super::allocator::Allocator;
 use super::instance::AshInstance;
+use super::selection::{
+    optional_extensions, required_extensions, select_physical_device, DeviceConfig,
+};
+use crate::error::{Error, Result};
 
-pub struct AshDevice<'a> {
-    pub instance: &'a AshInstance,
-    pub physical_device: vk::PhysicalDevice,
-    pub device: Arc<ash::Device>,
-    pub graphics_queue: vk::Queue,
-    pub present_queue: vk::Queue,
-    pub queue_family_indices: QueueFamilyIndices,
+/// A logical device, destroyed when the last object created from it is
+/// dropped.
+///
+/// Everything that owns Vulkan objects holds an `Arc` of this, so the device
+/// outlives them, and the device holds the instance, so the instance
+/// outlives the device.
+pub struct LogicalDevice {
+    device: ash::Device,
+    /// Present when the instance has debug utils enabled.
+    debug_utils: Option<debug_utils::Device>,
+    enabled_extensions: Vec<&'static CStr>,
+    _instance: Arc<AshInstance>,
 }
 
-impl<'a> AshDevice<'a> {
-    pub fn new(instance: &'a AshInstance) -> Self {
-        let physical_device = AshDevice::pick_physical_device(instance);
-        let queue_family_indices = AshDevice::find_queue_families(instance, physical_device);
-        let (device, graphics_queue, present_queue) = AshDevice::create_logical_device(
-            instance,
-            physical_device,
-            &queue_family_indices,
-        );
+impl LogicalDevice {
+    pub fn is_extension_enabled(&self, name: &CStr) -> bool {
+        self.enabled_extensions.contains(&name)
+    }
 
-        AshDevice {
-            instance,
-            physical_device,
-            device: Arc::new(device),
-            graphics_queue,
-            present_queue,
-            queue_family_indices,
+    /// Whether objects can be named and command buffers labelled.
+    pub fn has_debug_utils(&self) -> bool {
+        self.debug_utils.is_some()
+    }
+
+    /// Names `handle` for graphics debuggers such as RenderDoc. Does nothing
+    /// without debug utils.
+    pub fn set_object_name<H: vk::Handle>(&self, handle: H, name: &str) {
+        let Some(debug_utils) = &self.debug_utils else {
+            return;
+        };
+        let Ok(name) = CString::new(name) else {
+            return;
+        };
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
+            .object_handle(handle)
+            .object_name(&name);
+        // A missing name is no reason to fail.
+        unsafe {
+            let _ = debug_utils.set_debug_utils_object_name(&name_info);
         }
     }
 
-    fn pick_physical_device(instance: &AshInstance) -> vk::PhysicalDevice {
-        let physical_devices = unsafe {
-            instance.instance
-                .enumerate_physical_devices()
-                .expect("Failed to enumerate physical devices!")
+    /// Opens a named region of `command_buffer`, closed by
+    /// [`Self::cmd_end_label`]. Does nothing without debug utils.
+    pub fn cmd_begin_label(&self, command_buffer: vk::CommandBuffer, name: &str) {
+        let Some(debug_utils) = &self.debug_utils else {
+            return;
+        };
+        let Ok(name) = CString::new(name) else {
+            return;
         };
+        let label = vk::DebugUtilsLabelEXT::default().label_name(&name);
+        unsafe {
+            debug_utils.cmd_begin_debug_utils_label(command_buffer, &label);
+        }
+    }
 
-        physical_devices
-            .into_iter()
-            .find(|&device| AshDevice::is_device_suitable(instance, device))
-            .expect("Failed to find a suitable GPU!")
+    pub fn cmd_end_label(&self, command_buffer: vk::CommandBuffer) {
+        if let Some(debug_utils) = &self.debug_utils {
+            unsafe {
+                debug_utils.cmd_end_debug_utils_label(command_buffer);
+            }
+        }
     }
+}
 
-    fn is_device_suitable(instance: &AshInstance, device: vk::PhysicalDevice) -> bool {
-        let indices = AshDevice::find_queue_families(instance, device);
-        indices.is_complete()
+impl Deref for LogicalDevice {
+    type Target = ash::Device;
+
+    fn deref(&self) -> &ash::Device {
+        &self.device
     }
+}
 
-    fn find_queue_families(instance: &AshInstance, device: vk::PhysicalDevice) -> QueueFamilyIndices {
-        let mut indices = QueueFamilyIndices::default();
+impl Drop for LogicalDevice {
+    fn drop(&mut self) {
+        unsafe {
+            // A lost device has nothing left to wait for.
+            let _ = self.device.device_wait_idle();
+            self.device.destroy_device(None);
+        }
+    }
+}
 
-        let queue_families = unsafe {
-            &instance.instance
-                .get_physical_device_queue_family_properties(device)
-        };
+pub struct AshDevice {
+    pub instance: Arc<AshInstance>,
+    pub physical_device: vk::PhysicalDevice,
+    pub device: Arc<LogicalDevice>,
+    /// Memory for the buffers and images created on the device.
+    pub allocator: Arc<Allocator>,
+    pub graphics_queue: vk::Queue,
+    pub present_queue: vk::Queue,
+    /// A queue of the transfer-only family if there is one, otherwise the
+    /// graphics queue.
+    pub transfer_queue: vk::Queue,
+    pub queue_family_indices: QueueFamilyIndices,
+}
 
-        for (i, queue_family) in queue_families.iter().enumerate() {
-            if queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
-                indices.graphics_family = Some(i as u32);
-            }
+impl AshDevice {
+    /// Creates a device that can present to `surface`, on the GPU chosen
+    /// with the default [`DeviceConfig`].
+    pub fn new(instance: &Arc<AshInstance>, surface: vk::SurfaceKHR) -> Result<Self> {
+        Self::with_config(instance, surface, &DeviceConfig::default())
+    }
 
-            let present_support = unsafe {
-                SurfaceInstance::new(&instance.entry, &instance.instance)
-                    .get_physical_device_surface_support(device, i as u32, vk::SurfaceKHR::null())
-                    .unwrap_or(false)
-            };
+    /// Creates a device that can present to `surface`, on the GPU chosen
+    /// with `config`.
+    pub fn with_config(
+        instance: &Arc<AshInstance>,
+        surface: vk::SurfaceKHR,
+        config: &DeviceConfig,
+    ) -> Result<Self> {
+        Self::create(instance, surface, config)
+    }
 
-            if present_support {
-                indices.present_family = Some(i as u32);
-            }
+    /// Creates a device for offscreen rendering only. No present queue or
+    /// swapchain extension is required, so this works without a display.
+    pub fn new_headless(instance: &Arc<AshInstance>) -> Result<Self> {
+        Self::create(instance, vk::SurfaceKHR::null(), &DeviceConfig::default())
+    }
 
-            if indices.is_complete() {
-                break;
-            }
-        }
+    fn create(
+        instance: &Arc<AshInstance>,
+        surface: vk::SurfaceKHR,
+        config: &DeviceConfig,
+    ) -> Result<Self> {
+        let headless = surface == vk::SurfaceKHR::null();
+        let candidate = select_physical_device(instance, surface, config)?;
+        let physical_device = candidate.physical_device;
+        let queue_family_indices = candidate.queue_family_indices;
+        let (device, enabled_extensions, graphics_queue, present_queue) =
+            AshDevice::create_logical_device(
+                instance,
+                physical_device,
+                &queue_family_indices,
+                headless,
+            )?;
+
+        let debug_utils = instance
+            .has_debug_utils()
+            .then(|| debug_utils::Device::new(&instance.instance, &device));
 
-        indices
+        let device = Arc::new(LogicalDevice {
+            device,
+            debug_utils,
+            enabled_extensions,
+            _instance: instance.clone(),
+        });
+        let allocator = Arc::new(Allocator::new(instance, physical_device, &device));
+        let transfer_queue =
+            unsafe { device.get_device_queue(queue_family_indices.transfer_or_graphics(), 0) };
+
+        Ok(AshDevice {
+            instance: instance.clone(),
+            physical_device,
+            device,
+            allocator,
+            graphics_queue,
+            present_queue,
+            transfer_queue,
+            queue_family_indices,
+        })
     }
 
     fn create_logical_device(
         instance: &AshInstance,
         physical_device: vk::PhysicalDevice,
         indices: &QueueFamilyIndices,
-    ) -> (ash::Device, vk::Queue, vk::Queue) {
+        headless: bool,
+    ) -> Result<(ash::Device, Vec<&'static CStr>, vk::Queue, vk::Queue)> {
         let queue_priorities = [1.0f32];
 
-        let queue_create_infos: Vec<vk::DeviceQueueCreateInfo> = vec![
-            vk::DeviceQueueCreateInfo::default()
-                .queue_family_index(indices.graphics_family.unwrap())
-                .queue_priorities(&queue_priorities),
-            vk::DeviceQueueCreateInfo::default()
-                .queue_family_index(indices.present_family.unwrap())
-                .queue_priorities(&queue_priorities),
+        // Each queue family may only be requested once.
+        let mut families = vec![
+            indices.graphics_family.unwrap(),
+            indices.present_family.unwrap(),
         ];
+        families.extend(indices.transfer_family);
+        families.sort_unstable();
+        families.dedup();
 
-        let device_extensions = [ash::khr::swapchain::NAME.as_ptr()];
+        let queue_create_infos: Vec<vk::DeviceQueueCreateInfo> = families
+            .iter()
+            .map(|&family| {
+                vk::DeviceQueueCreateInfo::default()
+                    .queue_family_index(family)
+                    .queue_priorities(&queue_priorities)
+            })
+            .collect();
+
+        let available = unsafe {
+            instance.instance.enumerate_device_extension_properties(physical_device)?
+        };
+        let is_available = |name: &CStr| {
+            available
+                .iter()
+                .any(|extension| extension.extension_name_as_c_str() == Ok(name))
+        };
+        let required = required_extensions(headless);
+        if let Some(missing) = required.iter().find(|&&name| !is_available(name)) {
+            return Err(Error::MissingExtension(missing.to_string_lossy().into_owned()));
+        }
+        let mut enabled_extensions = required.to_vec();
+        enabled_extensions.extend(
+            optional_extensions()
+                .iter()
+                .filter(|&&name| is_available(name)),
+        );
+        log::info!("enabled device extensions: {:?}", enabled_extensions);
+        let device_extensions: Vec<_> = enabled_extensions
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect();
 
         let device_create_info = vk::DeviceCreateInfo::default()
             .queue_create_infos(&queue_create_infos)
             .enabled_extension_names(&device_extensions);
 
-        let device = unsafe {
-            instance.instance
-                .create_device(physical_device, &device_create_info, None)
-                .expect("Failed to create logical device!")
-        };
+        let device =
+            unsafe { instance.instance.create_device(physical_device, &device_create_info, None)? };
 
         let graphics_queue = unsafe { device.get_device_queue(indices.graphics_family.unwrap(), 0) };
         let present_queue = unsafe { device.get_device_queue(indices.present_family.unwrap(), 0) };
 
-        (device, graphics_queue, present_queue)
+        Ok((device, enabled_extensions, graphics_queue, present_queue))
     }
 }
 
@@ -121,10 +243,20 @@ impl<'a> AshDevice<'a> {
 pub struct QueueFamilyIndices {
     pub graphics_family: Option<u32>,
     pub present_family: Option<u32>,
+    /// A family that can transfer but not draw, which GPUs often back with
+    /// dedicated copy engines.
+    pub transfer_family: Option<u32>,
 }
 
 impl QueueFamilyIndices {
-    fn is_complete(&self) -> bool {
+    pub fn is_complete(&self) -> bool {
         self.graphics_family.is_some() && self.present_family.is_some()
     }
+
+    /// The family uploads are submitted on.
+    pub fn transfer_or_graphics(&self) -> u32 {
+        self.transfer_family
+            .or(self.graphics_family)
+            .expect("no graphics queue family")
+    }
 }
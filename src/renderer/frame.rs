@@ -0,0 +1,172 @@
+use ash::vk;
+use std::sync::Arc;
+
+use crate::error::Result;
+
+use super::command::{create_sync_objects, CommandBuffers, CommandPool};
+use super::device::{AshDevice, LogicalDevice};
+use super::window_target::WindowTarget;
+
+/// A frame being recorded, returned by [`FrameScheduler::begin_frame`].
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// Which of the frames in flight this is, for indexing per-frame
+    /// resources.
+    pub index: usize,
+    /// The swapchain image being drawn to.
+    pub image_index: u32,
+    /// Already begun; ended by [`FrameScheduler::end_frame`].
+    pub command_buffer: vk::CommandBuffer,
+}
+
+/// Paces rendering so the CPU records at most `max_frames_in_flight` frames
+/// ahead of the GPU.
+///
+/// Each frame slot has its own command buffer, semaphores and fence. The
+/// fence of the last frame to draw to each swapchain image is remembered
+/// too, since images may be acquired out of order.
+pub struct FrameScheduler {
+    command_buffers: CommandBuffers,
+    image_available_semaphores: Vec<vk::Semaphore>,
+    render_finished_semaphores: Vec<vk::Semaphore>,
+    in_flight_fences: Vec<vk::Fence>,
+    /// Per swapchain image, the fence of the frame using it, or null.
+    images_in_flight: Vec<vk::Fence>,
+    current_frame: usize,
+}
+
+impl FrameScheduler {
+    pub fn new(
+        device: &Arc<LogicalDevice>,
+        command_pool: &CommandPool,
+        max_frames_in_flight: usize,
+    ) -> Result<Self> {
+        let max_frames_in_flight = max_frames_in_flight.max(1);
+        let command_buffers =
+            CommandBuffers::new(device, command_pool, max_frames_in_flight as u32)?;
+        let (image_available_semaphores, render_finished_semaphores, in_flight_fences) =
+            create_sync_objects(device, max_frames_in_flight)?;
+
+        Ok(FrameScheduler {
+            command_buffers,
+            image_available_semaphores,
+            render_finished_semaphores,
+            in_flight_fences,
+            images_in_flight: Vec::new(),
+            current_frame: 0,
+        })
+    }
+
+    pub fn max_frames_in_flight(&self) -> usize {
+        self.in_flight_fences.len()
+    }
+
+    /// Waits until the current frame slot is free, acquires a swapchain
+    /// image and begins the slot's command buffer.
+    ///
+    /// Returns `None` if `target` has nothing to draw to, such as while the
+    /// window is minimized or right after the swapchain went out of date.
+    pub fn begin_frame(
+        &mut self,
+        device: &AshDevice,
+        target: &mut WindowTarget,
+    ) -> Result<Option<Frame>> {
+        let index = self.current_frame;
+        let fence = self.in_flight_fences[index];
+        unsafe {
+            device.device.wait_for_fences(&[fence], true, u64::MAX)?;
+        }
+
+        let Some(image_index) =
+            target.acquire_next_image(device, self.image_available_semaphores[index])?
+        else {
+            return Ok(None);
+        };
+
+        // The swapchain may have been recreated with a different number of
+        // images. Recreation waits for the device, so nothing is in flight.
+        let image_count = target
+            .swapchain()
+            .map_or(0, |swapchain| swapchain.images.len());
+        if self.images_in_flight.len() != image_count {
+            self.images_in_flight = vec![vk::Fence::null(); image_count];
+        }
+
+        // Another frame slot may still be drawing to this image.
+        let image_fence = self.images_in_flight[image_index as usize];
+        if image_fence != vk::Fence::null() && image_fence != fence {
+            unsafe {
+                device
+                    .device
+                    .wait_for_fences(&[image_fence], true, u64::MAX)?;
+            }
+        }
+        self.images_in_flight[image_index as usize] = fence;
+
+        // Only reset once a frame will definitely be submitted, or the next
+        // wait on this slot would never return.
+        unsafe {
+            device.device.reset_fences(&[fence])?;
+        }
+        self.command_buffers.begin_command_buffer(index)?;
+
+        Ok(Some(Frame {
+            index,
+            image_index,
+            command_buffer: self.command_buffers.buffers[index],
+        }))
+    }
+
+    /// Ends and submits `frame`'s command buffer, presents its image and
+    /// moves on to the next frame slot.
+    pub fn end_frame(
+        &mut self,
+        device: &AshDevice,
+        target: &mut WindowTarget,
+        frame: Frame,
+    ) -> Result<()> {
+        self.command_buffers.end_command_buffer(frame.index)?;
+
+        let wait_semaphores = [self.image_available_semaphores[frame.index]];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let signal_semaphores = [self.render_finished_semaphores[frame.index]];
+        let command_buffers = [frame.command_buffer];
+        let submit_info = vk::SubmitInfo::default()
+            .wait_semaphores(&wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .command_buffers(&command_buffers)
+            .signal_semaphores(&signal_semaphores);
+
+        unsafe {
+            device.device.queue_submit(
+                device.graphics_queue,
+                &[submit_info],
+                self.in_flight_fences[frame.index],
+            )?;
+        }
+
+        target.present(device.present_queue, frame.image_index, &signal_semaphores)?;
+        self.current_frame = (self.current_frame + 1) % self.max_frames_in_flight();
+        Ok(())
+    }
+}
+
+/// Destroys the synchronization objects. The device must be idle; the
+/// command buffers are freed with their pool.
+impl Drop for FrameScheduler {
+    fn drop(&mut self) {
+        let device = self.command_buffers.device();
+        unsafe {
+            for &semaphore in self
+                .image_available_semaphores
+                .iter()
+                .chain(&self.render_finished_semaphores)
+            {
+                device.destroy_semaphore(semaphore, None);
+            }
+            for &fence in &self.in_flight_fences {
+                device.destroy_fence(fence, None);
+            }
+        }
+    }
+}
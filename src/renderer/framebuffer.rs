@@ -1,23 +1,77 @@
 use ash::vk;
-use ash::Device;
 use std::sync::Arc;
 
+use super::device::LogicalDevice;
+use crate::error::Result;
+
+/// A framebuffer per swapchain image. The depth attachment is left out
+/// when its view is `None`, to match a render pass without one.
 pub struct Framebuffers {
     pub framebuffers: Vec<vk::Framebuffer>,
+    device: Arc<LogicalDevice>,
 }
 
 impl Framebuffers {
     pub fn new(
-        device: &Arc<Device>,
+        device: &Arc<LogicalDevice>,
+        render_pass: vk::RenderPass,
+        image_views: &[vk::ImageView],
+        depth_image_view: Option<vk::ImageView>,
+        swapchain_extent: vk::Extent2D,
+    ) -> Result<Framebuffers> {
+        Self::create(
+            device,
+            render_pass,
+            swapchain_extent,
+            image_views,
+            |image_view| {
+                std::iter::once(image_view)
+                    .chain(depth_image_view)
+                    .collect()
+            },
+        )
+    }
+
+    /// Creates framebuffers for a render pass made with
+    /// [`RenderPass::new_multisampled`](super::render_pass::RenderPass::new_multisampled),
+    /// drawing to `color_image_view` and resolving into each of
+    /// `image_views`.
+    pub fn new_multisampled(
+        device: &Arc<LogicalDevice>,
         render_pass: vk::RenderPass,
+        color_image_view: vk::ImageView,
         image_views: &[vk::ImageView],
-        depth_image_view: vk::ImageView,
+        depth_image_view: Option<vk::ImageView>,
         swapchain_extent: vk::Extent2D,
-    ) -> Framebuffers {
-        let mut framebuffers = Vec::with_capacity(image_views.len());
+    ) -> Result<Framebuffers> {
+        Self::create(
+            device,
+            render_pass,
+            swapchain_extent,
+            image_views,
+            |image_view| {
+                std::iter::once(color_image_view)
+                    .chain(depth_image_view)
+                    .chain([image_view])
+                    .collect()
+            },
+        )
+    }
+
+    fn create(
+        device: &Arc<LogicalDevice>,
+        render_pass: vk::RenderPass,
+        swapchain_extent: vk::Extent2D,
+        image_views: &[vk::ImageView],
+        attachments: impl Fn(vk::ImageView) -> Vec<vk::ImageView>,
+    ) -> Result<Framebuffers> {
+        let mut framebuffers = Framebuffers {
+            framebuffers: Vec::with_capacity(image_views.len()),
+            device: device.clone(),
+        };
 
-        for &image_view in image_views {
-            let attachments = [image_view, depth_image_view];
+        for (i, &image_view) in image_views.iter().enumerate() {
+            let attachments = attachments(image_view);
             let framebuffer_info = vk::FramebufferCreateInfo::default()
                 .render_pass(render_pass)
                 .attachments(&attachments)
@@ -25,22 +79,22 @@ impl Framebuffers {
                 .height(swapchain_extent.height)
                 .layers(1);
 
-            let framebuffer = unsafe {
-                device
-                    .create_framebuffer(&framebuffer_info, None)
-                    .expect("Failed to create framebuffer!")
-            };
+            // Those already created are destroyed if this fails.
+            let framebuffer = unsafe { device.create_framebuffer(&framebuffer_info, None)? };
+            device.set_object_name(framebuffer, &format!("framebuffer {}", i));
 
-            framebuffers.push(framebuffer);
+            framebuffers.framebuffers.push(framebuffer);
         }
 
-        Framebuffers { framebuffers }
+        Ok(framebuffers)
     }
+}
 
-    pub fn cleanup(&self, device: &Arc<Device>) {
+impl Drop for Framebuffers {
+    fn drop(&mut self) {
         for &framebuffer in &self.framebuffers {
             unsafe {
-                device.destroy_framebuffer(framebuffer, None);
+                self.device.destroy_framebuffer(framebuffer, None);
             }
         }
     }
@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::Path;
+
+use super::primitive::Image;
+
+/// Writes `image` to `path` as an 8-bit RGBA PNG.
+pub fn save_png(image: &Image, path: impl AsRef<Path>) -> Result<(), png::EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.data)
+}
+
+/// Reads a PNG from `path`, converting any color type to 8-bit RGBA.
+pub fn load_png(path: impl AsRef<Path>) -> Result<Image, png::DecodingError> {
+    let file = File::open(path)?;
+    read_png(BufReader::new(file))
+}
+
+/// Decodes PNG data held in memory, converting any color type to 8-bit
+/// RGBA.
+pub fn decode_png(data: &[u8]) -> Result<Image, png::DecodingError> {
+    read_png(data)
+}
+
+fn read_png(reader: impl Read) -> Result<Image, png::DecodingError> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let pixels = &buffer[..info.buffer_size()];
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Rgb => pixels
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => pixels
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        // Indexed images are expanded to RGB(A) by the transformations above.
+        png::ColorType::Grayscale | png::ColorType::Indexed => {
+            pixels.iter().flat_map(|&g| [g, g, g, 255]).collect()
+        }
+    };
+
+    Ok(Image::new(info.width, info.height, data))
+}
@@ -1,41 +1,243 @@
 use ash::{vk, Entry, Instance};
-use std::error::Error;
-use std::ffi::CString;
+use std::ffi::{c_char, CStr, CString};
+use winit::raw_window_handle::RawDisplayHandle;
+
+use super::debug::{DebugConfig, DebugMessenger, MessengerState, VALIDATION_LAYER};
+use crate::error::{Error, Result};
+
+/// Instance extensions to enable.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionRequest {
+    /// Creating the instance fails with [`Error::MissingExtension`] if any
+    /// of these is unavailable.
+    pub required: Vec<CString>,
+    /// Enabled if available, and skipped otherwise.
+    pub optional: Vec<CString>,
+}
+
+impl ExtensionRequest {
+    /// The extensions `ash_window` needs to create surfaces on `display`,
+    /// and the swapchain color space extension if available.
+    pub fn for_display(display: RawDisplayHandle) -> Result<Self> {
+        let required = ash_window::enumerate_required_extensions(display)?
+            .iter()
+            .map(|&name| unsafe { CStr::from_ptr(name) }.to_owned())
+            .collect();
+        Ok(ExtensionRequest {
+            required,
+            optional: vec![ash::ext::swapchain_colorspace::NAME.to_owned()],
+        })
+    }
+
+    pub fn require(mut self, name: &CStr) -> Self {
+        self.required.push(name.to_owned());
+        self
+    }
+
+    pub fn request(mut self, name: &CStr) -> Self {
+        self.optional.push(name.to_owned());
+        self
+    }
+}
 
 pub struct AshInstance {
     pub entry: Entry,
     pub instance: Instance,
+    enabled_extensions: Vec<CString>,
+    debug_config: DebugConfig,
+    debug_messenger: Option<DebugMessenger>,
 }
 
 impl AshInstance {
-    pub fn new(app_name: &str) -> Result<Self, Box<dyn Error>> {
+    pub fn new(app_name: &str) -> Result<Self> {
+        Self::with_extensions(app_name, &[])
+    }
+
+    /// Creates an instance with the given instance extensions enabled, such
+    /// as those `ash_window` needs to create surfaces.
+    pub fn with_extensions(app_name: &str, extension_names: &[*const c_char]) -> Result<Self> {
+        let extensions = ExtensionRequest {
+            required: extension_names
+                .iter()
+                .map(|&name| unsafe { CStr::from_ptr(name) }.to_owned())
+                .collect(),
+            optional: Vec::new(),
+        };
+        Self::with_config(app_name, &extensions, DebugConfig::default())
+    }
+
+    /// Creates an instance with the extensions in `extensions` that are
+    /// available and the debugging aids in `debug`. What was enabled is
+    /// logged and reported by [`Self::enabled_extensions`]. Validation is
+    /// skipped with a warning if the layer is not installed.
+    pub fn with_config(
+        app_name: &str,
+        extensions: &ExtensionRequest,
+        debug: DebugConfig,
+    ) -> Result<Self> {
         let entry = unsafe { Entry::load()? };
-        let app_name = CString::new(app_name)?;
-        let engine_name = CString::new("AshEngine")?;
+
+        let validation = debug.validation && {
+            let layers = unsafe { entry.enumerate_instance_layer_properties()? };
+            let found = layers
+                .iter()
+                .any(|layer| layer.layer_name_as_c_str() == Ok(VALIDATION_LAYER));
+            if !found {
+                log::warn!(
+                    "{} is not installed; running without validation",
+                    VALIDATION_LAYER.to_string_lossy()
+                );
+            }
+            found
+        };
+
+        let mut available = unsafe { entry.enumerate_instance_extension_properties(None)? };
+        if validation {
+            // The layer provides debug utils even if the loader does not.
+            available.extend(unsafe {
+                entry.enumerate_instance_extension_properties(Some(VALIDATION_LAYER))?
+            });
+        }
+        let is_available = |name: &CStr| {
+            available
+                .iter()
+                .any(|extension| extension.extension_name_as_c_str() == Ok(name))
+        };
+        if let Some(missing) = extensions.required.iter().find(|name| !is_available(name)) {
+            return Err(Error::MissingExtension(
+                missing.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let mut enabled_extensions = extensions.required.clone();
+        for name in &extensions.optional {
+            if is_available(name) {
+                enabled_extensions.push(name.clone());
+            } else {
+                log::debug!(
+                    "optional instance extension {} is not available",
+                    name.to_string_lossy()
+                );
+            }
+        }
+        let debug_utils =
+            (debug.debug_utils || validation) && is_available(ash::ext::debug_utils::NAME);
+        if debug_utils {
+            enabled_extensions.push(ash::ext::debug_utils::NAME.to_owned());
+        }
+        enabled_extensions.sort();
+        enabled_extensions.dedup();
+        log::info!(
+            "enabled instance extensions: {}",
+            enabled_extensions
+                .iter()
+                .map(|name| name.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let extension_names: Vec<_> = enabled_extensions
+            .iter()
+            .map(|name| name.as_ptr())
+            .collect();
+        let layer_names = if validation {
+            vec![VALIDATION_LAYER.as_ptr()]
+        } else {
+            vec![]
+        };
+
+        let app_name = CString::new(app_name.replace('\0', "")).unwrap_or_default();
+        let engine_name = c"AshEngine";
 
         let app_info = vk::ApplicationInfo::default()
             .application_name(&app_name)
             .application_version(0)
-            .engine_name(&engine_name)
+            .engine_name(engine_name)
             .engine_version(0)
             .api_version(vk::API_VERSION_1_3);
 
-        let create_info = vk::InstanceCreateInfo::default().application_info(&app_info);
+        let messenger_state = Box::new(MessengerState::new(debug.panic_on_error));
+        let mut messenger_info = messenger_state.create_info();
+        let mut create_info = vk::InstanceCreateInfo::default()
+            .application_info(&app_info)
+            .enabled_layer_names(&layer_names)
+            .enabled_extension_names(&extension_names);
+        // The messenger only forwards validation messages.
+        let messenger = validation && debug_utils;
+        if messenger {
+            create_info = create_info.push_next(&mut messenger_info);
+        }
 
         let instance = unsafe { entry.create_instance(&create_info, None)? };
 
-        Ok(AshInstance { entry, instance })
+        let debug_messenger = if messenger {
+            match DebugMessenger::new(&entry, &instance, messenger_state) {
+                Ok(messenger) => Some(messenger),
+                Err(err) => {
+                    unsafe {
+                        instance.destroy_instance(None);
+                    }
+                    return Err(err);
+                }
+            }
+        } else {
+            None
+        };
+
+        Ok(AshInstance {
+            entry,
+            instance,
+            enabled_extensions,
+            debug_config: debug,
+            debug_messenger,
+        })
     }
 
-    pub fn destroy(&self) {
-        unsafe {
-            self.instance.destroy_instance(None);
+    /// The instance extensions that were enabled, sorted by name.
+    pub fn enabled_extensions(&self) -> &[CString] {
+        &self.enabled_extensions
+    }
+
+    pub fn is_extension_enabled(&self, name: &CStr) -> bool {
+        self.enabled_extensions
+            .iter()
+            .any(|enabled| enabled.as_c_str() == name)
+    }
+
+    /// Whether `VK_EXT_debug_utils` is enabled, for naming objects and
+    /// labelling command buffers.
+    pub fn has_debug_utils(&self) -> bool {
+        self.is_extension_enabled(ash::ext::debug_utils::NAME)
+    }
+
+    /// Validation errors reported since the last call. Only recorded with
+    /// [`DebugConfig::panic_on_error`].
+    pub fn take_validation_errors(&self) -> Vec<String> {
+        self.debug_messenger
+            .as_ref()
+            .map(|messenger| messenger.state.take_errors())
+            .unwrap_or_default()
+    }
+
+    /// Panics if [`DebugConfig::panic_on_error`] is set and the validation
+    /// layer has reported errors since the last check.
+    pub fn check_validation(&self) {
+        if !self.debug_config.panic_on_error {
+            return;
+        }
+        let errors = self.take_validation_errors();
+        if !errors.is_empty() {
+            panic!("Vulkan validation failed:\n{}", errors.join("\n"));
         }
     }
 }
 
+/// Devices hold an `Arc` of the instance, so they and everything created
+/// from them are gone by the time it is destroyed.
 impl Drop for AshInstance {
     fn drop(&mut self) {
-        self.destroy();
+        self.debug_messenger = None;
+        unsafe {
+            self.instance.destroy_instance(None);
+        }
     }
 }
@@ -0,0 +1,200 @@
+use ash::vk;
+use ash::Device;
+use std::sync::Arc;
+
+use super::allocator::{Allocation, ResourceKind};
+use super::device::{AshDevice, LogicalDevice};
+use crate::error::Result;
+
+/// A device-local 2D image with a view of all of it, destroyed on drop. Its
+/// memory comes from the device's [`Allocator`](super::allocator::Allocator).
+pub struct OwnedImage {
+    pub image: vk::Image,
+    pub view: vk::ImageView,
+    /// Returned to the allocator after the image is destroyed.
+    _allocation: Allocation,
+    device: Arc<LogicalDevice>,
+}
+
+impl OwnedImage {
+    pub fn new(
+        device: &AshDevice,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        usage: vk::ImageUsageFlags,
+        samples: vk::SampleCountFlags,
+        aspect_mask: vk::ImageAspectFlags,
+    ) -> Result<Self> {
+        let image_info = vk::ImageCreateInfo::default()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(format)
+            .extent(vk::Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(samples)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED);
+
+        let image = unsafe { device.device.create_image(&image_info, None)? };
+        let allocation = unsafe {
+            let requirements = device.device.get_image_memory_requirements(image);
+            device
+                .allocator
+                .allocate(
+                    requirements,
+                    vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                    ResourceKind::Optimal,
+                )
+                .and_then(|allocation| {
+                    device.device.bind_image_memory(
+                        image,
+                        allocation.memory(),
+                        allocation.offset(),
+                    )?;
+                    Ok(allocation)
+                })
+                .inspect_err(|_| device.device.destroy_image(image, None))?
+        };
+
+        let mut owned = OwnedImage {
+            image,
+            view: vk::ImageView::null(),
+            _allocation: allocation,
+            device: device.device.clone(),
+        };
+        owned.view = create_image_view(&device.device, image, format, aspect_mask)?;
+        Ok(owned)
+    }
+
+    /// Names the image and its view for graphics debuggers.
+    pub fn set_name(&self, name: &str) {
+        self.device.set_object_name(self.image, name);
+        self.device
+            .set_object_name(self.view, &format!("{} view", name));
+    }
+}
+
+impl Drop for OwnedImage {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_image_view(self.view, None);
+            self.device.destroy_image(self.image, None);
+        }
+    }
+}
+
+/// A buffer destroyed on drop. Its memory comes from the device's
+/// [`Allocator`](super::allocator::Allocator).
+pub struct OwnedBuffer {
+    pub buffer: vk::Buffer,
+    pub size: vk::DeviceSize,
+    /// Returned to the allocator after the buffer is destroyed.
+    allocation: Allocation,
+    device: Arc<LogicalDevice>,
+}
+
+impl OwnedBuffer {
+    pub fn new(
+        device: &AshDevice,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        properties: vk::MemoryPropertyFlags,
+    ) -> Result<Self> {
+        let buffer_info = vk::BufferCreateInfo::default()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let buffer = unsafe { device.device.create_buffer(&buffer_info, None)? };
+        let allocation = unsafe {
+            let requirements = device.device.get_buffer_memory_requirements(buffer);
+            device
+                .allocator
+                .allocate(requirements, properties, ResourceKind::Linear)
+                .and_then(|allocation| {
+                    device.device.bind_buffer_memory(
+                        buffer,
+                        allocation.memory(),
+                        allocation.offset(),
+                    )?;
+                    Ok(allocation)
+                })
+                .inspect_err(|_| device.device.destroy_buffer(buffer, None))?
+        };
+
+        Ok(OwnedBuffer {
+            buffer,
+            size,
+            allocation,
+            device: device.device.clone(),
+        })
+    }
+
+    /// Names the buffer for graphics debuggers.
+    pub fn set_name(&self, name: &str) {
+        self.device.set_object_name(self.buffer, name);
+    }
+
+    /// Copies `bytes` to the start of the buffer, which must be host
+    /// visible and coherent.
+    pub fn write(&self, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.allocation.write(bytes)
+    }
+
+    /// Copies `bytes` to `offset` in the buffer, which must be host visible
+    /// and coherent.
+    pub fn write_at(&self, offset: vk::DeviceSize, bytes: &[u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.allocation.write_at(offset, bytes)
+    }
+
+    /// Copies the start of the buffer into `bytes`. The buffer must be host
+    /// visible and coherent.
+    pub fn read(&self, bytes: &mut [u8]) -> Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.allocation.read(bytes)
+    }
+}
+
+impl Drop for OwnedBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_buffer(self.buffer, None);
+        }
+    }
+}
+
+/// Creates a single-mip 2D view covering `aspect_mask` of `image`.
+pub fn create_image_view(
+    device: &Device,
+    image: vk::Image,
+    format: vk::Format,
+    aspect_mask: vk::ImageAspectFlags,
+) -> Result<vk::ImageView> {
+    let view_info = vk::ImageViewCreateInfo::default()
+        .image(image)
+        .view_type(vk::ImageViewType::TYPE_2D)
+        .format(format)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        });
+
+    Ok(unsafe { device.create_image_view(&view_info, None)? })
+}
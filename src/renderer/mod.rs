@@ -4,4 +4,25 @@ pub mod swapchain;
 pub mod device;
 pub mod framebuffer;
 pub mod command;
-pub mod render_pass;
\ No newline at end of file
+pub mod render_pass;
+pub mod primitive;
+pub mod software;
+pub mod canvas;
+pub mod vulkan_canvas;
+pub mod recording;
+pub mod memory;
+pub mod offscreen;
+pub mod image_io;
+pub mod text_pipeline;
+pub mod texture;
+pub mod window_target;
+pub mod frame;
+pub mod window_renderer;
+pub mod debug;
+pub mod selection;
+pub mod surface;
+pub mod allocator;
+pub mod vertex;
+pub mod buffer;
+pub mod upload;
+pub mod depth;
\ No newline at end of file
@@ -0,0 +1,112 @@
+use ash::vk;
+use ash::Device;
+
+use crate::error::Result;
+
+use super::depth::create_depth_image;
+use super::device::AshDevice;
+use super::memory::OwnedImage;
+
+/// Color format used for offscreen targets. It is UNORM so read-back pixels
+/// match what was drawn without an sRGB round trip.
+pub const OFFSCREEN_COLOR_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+/// Owned color and depth images to render into when there is no swapchain.
+pub struct OffscreenTarget {
+    pub extent: vk::Extent2D,
+    pub color: OwnedImage,
+    /// `None` if rendering without depth.
+    pub depth: Option<OwnedImage>,
+}
+
+impl OffscreenTarget {
+    pub fn new(
+        device: &AshDevice,
+        extent: vk::Extent2D,
+        depth_format: Option<vk::Format>,
+    ) -> Result<Self> {
+        let color = OwnedImage::new(
+            device,
+            extent,
+            OFFSCREEN_COLOR_FORMAT,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::SampleCountFlags::TYPE_1,
+            vk::ImageAspectFlags::COLOR,
+        )?;
+        color.set_name("offscreen color attachment");
+        let depth = depth_format
+            .map(|format| {
+                create_depth_image(
+                    device,
+                    extent,
+                    format,
+                    vk::SampleCountFlags::TYPE_1,
+                    "offscreen depth attachment",
+                )
+            })
+            .transpose()?;
+
+        Ok(OffscreenTarget {
+            extent,
+            color,
+            depth,
+        })
+    }
+
+    /// Size in bytes of the tightly packed RGBA8 pixels of the color image.
+    pub fn readback_size(&self) -> vk::DeviceSize {
+        self.extent.width as vk::DeviceSize * self.extent.height as vk::DeviceSize * 4
+    }
+
+    /// Records a copy of the color image into `buffer`, followed by a
+    /// barrier making the copy visible to host reads once the submission's
+    /// fence signals. The image must be in `TRANSFER_SRC_OPTIMAL`, as left
+    /// by an offscreen render pass.
+    pub fn record_readback(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+    ) {
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .image_extent(vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            });
+
+        unsafe {
+            device.cmd_copy_image_to_buffer(
+                command_buffer,
+                self.color.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer,
+                &[region],
+            );
+
+            let barrier = vk::BufferMemoryBarrier::default()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::HOST_READ)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .buffer(buffer)
+                .offset(0)
+                .size(self.readback_size());
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[barrier],
+                &[],
+            );
+        }
+    }
+}
@@ -1,94 +1,73 @@
 use ash::vk;
-use std::ffi::CString;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+
+use super::device::LogicalDevice;
+use super::vertex::VertexInput;
+use super::vulkan_canvas::Vertex;
 
 pub struct Pipeline {
-    pub render_pass: vk::RenderPass,
     pub pipeline_layout: vk::PipelineLayout,
     pub graphics_pipeline: vk::Pipeline,
+    device: Arc<LogicalDevice>,
 }
 
 impl Pipeline {
-    pub fn new(device: &ash::Device, swapchain: &crate::renderer::swapchain::Swapchain) -> Self {
-        // Create Render Pass
-        let color_attachment = [vk::AttachmentDescription::default()
-            .format(swapchain.format)
-            .samples(vk::SampleCountFlags::TYPE_1)
-            .load_op(vk::AttachmentLoadOp::CLEAR)
-            .store_op(vk::AttachmentStoreOp::STORE)
-            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
-            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
-            .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR)];
-
-        let color_attachment_ref = [vk::AttachmentReference {
-            attachment: 0,
-            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-        }];
-
-        let subpass = [vk::SubpassDescription::default()
-            .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-            .color_attachments(&color_attachment_ref)];
-
-        let render_pass_info = vk::RenderPassCreateInfo::default()
-            .attachments(&color_attachment)
-            .subpasses(&subpass);
-
-        let render_pass = unsafe {
-            device
-                .create_render_pass(&render_pass_info, None)
-                .expect("Failed to create render pass")
-        };
-
+    pub fn new(
+        device: &Arc<LogicalDevice>,
+        render_pass: vk::RenderPass,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
         // Create Pipeline Layout
         let pipeline_layout_info = vk::PipelineLayoutCreateInfo::default();
-        let pipeline_layout = unsafe {
-            device
-                .create_pipeline_layout(&pipeline_layout_info, None)
-                .expect("Failed to create pipeline layout")
+        let pipeline_layout =
+            unsafe { device.create_pipeline_layout(&pipeline_layout_info, None)? };
+        // Destroys the layout if a later step fails.
+        let mut pipeline = Pipeline {
+            pipeline_layout,
+            graphics_pipeline: vk::Pipeline::null(),
+            device: device.clone(),
         };
 
         // Create Graphics Pipeline
         let vert_shader_code = include_bytes!("shader/shader.vert.spv");
         let frag_shader_code = include_bytes!("shader/shader.frag.spv");
 
-        let vert_shader_module = Self::create_shader_module(device, vert_shader_code);
-        let frag_shader_module = Self::create_shader_module(device, frag_shader_code);
+        let vert_shader_module = Self::create_shader_module(device, vert_shader_code)?;
+        let frag_shader_module = Self::create_shader_module(device, frag_shader_code)
+            .inspect_err(|_| unsafe { device.destroy_shader_module(vert_shader_module, None) })?;
 
-        let entry_point = CString::new("main").unwrap();
+        let entry_point = c"main";
         let vert_shader_stage_info = vk::PipelineShaderStageCreateInfo::default()
             .stage(vk::ShaderStageFlags::VERTEX)
             .module(vert_shader_module)
-            .name(&entry_point);
+            .name(entry_point);
 
         let frag_shader_stage_info = vk::PipelineShaderStageCreateInfo::default()
             .stage(vk::ShaderStageFlags::FRAGMENT)
             .module(frag_shader_module)
-            .name(&entry_point);
+            .name(entry_point);
 
         let shader_stages = [vert_shader_stage_info, frag_shader_stage_info];
 
-        let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::default();
+        let binding_descriptions = [Vertex::binding_description(0)];
+        let attribute_descriptions = Vertex::attribute_descriptions(0);
+        let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::default()
+            .vertex_binding_descriptions(&binding_descriptions)
+            .vertex_attribute_descriptions(&attribute_descriptions);
         let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
             .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
             .primitive_restart_enable(false);
 
-        let viewport = [vk::Viewport {
-            x: 0.0,
-            y: 0.0,
-            width: swapchain.extent.width as f32,
-            height: swapchain.extent.height as f32,
-            min_depth: 0.0,
-            max_depth: 1.0,
-        }];
-
-        let scissor = [vk::Rect2D {
-            offset: vk::Offset2D { x: 0, y: 0 },
-            extent: swapchain.extent,
-        }];
-
+        // Viewport and scissor are set when drawing, so the pipeline
+        // outlives swapchain resizes.
         let viewport_state = vk::PipelineViewportStateCreateInfo::default()
-            .viewports(&viewport)
-            .scissors(&scissor);
+            .viewport_count(1)
+            .scissor_count(1);
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state =
+            vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&dynamic_states);
 
         let rasterizer = vk::PipelineRasterizationStateCreateInfo::default()
             .depth_clamp_enable(false)
@@ -101,7 +80,13 @@ impl Pipeline {
 
         let multisampling = vk::PipelineMultisampleStateCreateInfo::default()
             .sample_shading_enable(false)
-            .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+            .rasterization_samples(samples);
+
+        // The render pass may have a depth attachment, but 2D UI is drawn
+        // in painter's order so depth testing stays off.
+        let depth_stencil = vk::PipelineDepthStencilStateCreateInfo::default()
+            .depth_test_enable(false)
+            .depth_write_enable(false);
 
         let color_blend_attachment = [vk::PipelineColorBlendAttachmentState::default()
             .color_write_mask(
@@ -110,7 +95,13 @@ impl Pipeline {
                     | vk::ColorComponentFlags::B
                     | vk::ColorComponentFlags::A,
             )
-            .blend_enable(false)];
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .alpha_blend_op(vk::BlendOp::ADD)];
 
         let color_blending = vk::PipelineColorBlendStateCreateInfo::default()
             .logic_op_enable(false)
@@ -121,46 +112,57 @@ impl Pipeline {
             .vertex_input_state(&vertex_input_info)
             .input_assembly_state(&input_assembly)
             .viewport_state(&viewport_state)
+            .dynamic_state(&dynamic_state)
             .rasterization_state(&rasterizer)
             .multisample_state(&multisampling)
+            .depth_stencil_state(&depth_stencil)
             .color_blend_state(&color_blending)
             .layout(pipeline_layout)
             .render_pass(render_pass)
             .subpass(0);
 
         let graphics_pipeline = unsafe {
-            device
-                .create_graphics_pipelines(vk::PipelineCache::null(), &[pipeline_info], None)
-                .expect("Failed to create graphics pipeline")[0]
+            device.create_graphics_pipelines(vk::PipelineCache::null(), &[pipeline_info], None)
         };
 
         unsafe {
             device.destroy_shader_module(vert_shader_module, None);
             device.destroy_shader_module(frag_shader_module, None);
         }
+        pipeline.graphics_pipeline = graphics_pipeline.map_err(|(_, err)| err)?[0];
 
-        Pipeline {
-            render_pass,
-            pipeline_layout,
-            graphics_pipeline,
-        }
+        device.set_object_name(pipeline.pipeline_layout, "shape pipeline layout");
+        device.set_object_name(pipeline.graphics_pipeline, "shape pipeline");
+
+        Ok(pipeline)
     }
 
-    fn create_shader_module(device: &ash::Device, code: &[u8]) -> vk::ShaderModule {
-        let create_info = vk::ShaderModuleCreateInfo::default().code(bytemuck::cast_slice(code));
+    pub(crate) fn create_shader_module(
+        device: &ash::Device,
+        code: &[u8],
+    ) -> Result<vk::ShaderModule> {
+        // `include_bytes!` data is not guaranteed to be 4-byte aligned.
+        let code = ash::util::read_spv(&mut std::io::Cursor::new(code))
+            .map_err(|err| Error::Shader(err.to_string()))?;
+        let create_info = vk::ShaderModuleCreateInfo::default().code(&code);
 
         unsafe {
             device
                 .create_shader_module(&create_info, None)
-                .expect("Failed to create shader module")
+                .map_err(|err| match err {
+                    vk::Result::ERROR_INVALID_SHADER_NV => Error::Shader(err.to_string()),
+                    err => err.into(),
+                })
         }
     }
+}
 
-    pub fn cleanup(&self, device: &ash::Device) {
+impl Drop for Pipeline {
+    fn drop(&mut self) {
         unsafe {
-            device.destroy_pipeline(self.graphics_pipeline, None);
-            device.destroy_pipeline_layout(self.pipeline_layout, None);
-            device.destroy_render_pass(self.render_pass, None);
+            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
         }
     }
 }
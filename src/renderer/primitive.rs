@@ -0,0 +1,182 @@
+/// A linear RGBA color with components in the `0.0..=1.0` range.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const TRANSPARENT: Color = Color::rgba(0.0, 0.0, 0.0, 0.0);
+    pub const BLACK: Color = Color::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Color = Color::rgb(1.0, 1.0, 1.0);
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// Converts the color to 8-bit RGBA, clamping out-of-range components.
+    pub fn to_rgba8(self) -> [u8; 4] {
+        let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+        [to_u8(self.r), to_u8(self.g), to_u8(self.b), to_u8(self.a)]
+    }
+
+    pub fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Color::rgba(
+            rgba[0] as f32 / 255.0,
+            rgba[1] as f32 / 255.0,
+            rgba[2] as f32 / 255.0,
+            rgba[3] as f32 / 255.0,
+        )
+    }
+
+    /// Linearly interpolates between `self` and `other`.
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        Color::rgba(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+}
+
+/// An axis-aligned rectangle in pixels, with the origin at the top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn from_size(size: Size) -> Self {
+        Rect::new(0.0, 0.0, size.width, size.height)
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.right() && y >= self.y && y <= self.bottom()
+    }
+
+    /// Returns the overlapping area of two rectangles, or an empty rectangle
+    /// when they do not overlap.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        Rect::new(x, y, (right - x).max(0.0), (bottom - y).max(0.0))
+    }
+}
+
+/// A width and height in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Size {
+    pub const ZERO: Size = Size::new(0.0, 0.0);
+
+    pub const fn new(width: f32, height: f32) -> Self {
+        Size { width, height }
+    }
+}
+
+/// An 8-bit RGBA image with straight (non-premultiplied) alpha.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, data: Vec<u8>) -> Self {
+        assert_eq!(
+            data.len(),
+            (width * height * 4) as usize,
+            "Image data does not match its dimensions"
+        );
+        Image {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+}
+
+/// An 8-bit coverage mask, as produced by a glyph rasterizer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlphaMask {
+    pub width: u32,
+    pub height: u32,
+    pub coverage: Vec<u8>,
+}
+
+impl AlphaMask {
+    pub fn new(width: u32, height: u32, coverage: Vec<u8>) -> Self {
+        assert_eq!(
+            coverage.len(),
+            (width * height) as usize,
+            "Mask data does not match its dimensions"
+        );
+        AlphaMask {
+            width,
+            height,
+            coverage,
+        }
+    }
+}
+
+/// A rasterized glyph positioned in pixels, with `(x, y)` at the top-left
+/// corner of its mask.
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph<'a> {
+    pub x: f32,
+    pub y: f32,
+    pub mask: &'a AlphaMask,
+}
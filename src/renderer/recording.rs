@@ -0,0 +1,285 @@
+use std::fmt::Write;
+
+use crate::text::shaping::ShapedText;
+
+use super::canvas::Canvas;
+use super::primitive::{Color, Glyph, Image, Rect};
+
+/// A single call made against a [`RecordingCanvas`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCommand {
+    FillRect {
+        rect: Rect,
+        color: Color,
+    },
+    FillQuad {
+        rect: Rect,
+        colors: [Color; 4],
+    },
+    /// Glyph masks are recorded by position and size only.
+    GlyphRun {
+        glyphs: Vec<Rect>,
+        color: Color,
+    },
+    /// Text is recorded by content and size; glyph placement is left to
+    /// the backend.
+    Text {
+        text: String,
+        size: f32,
+        x: f32,
+        y: f32,
+        color: Color,
+    },
+    /// Images are recorded by destination and source size only.
+    Image {
+        dest: Rect,
+        width: u32,
+        height: u32,
+    },
+    PushClip(Rect),
+    PopClip,
+}
+
+/// A [`Canvas`] that records every call instead of drawing, so widget
+/// painting can be asserted on without a device.
+#[derive(Debug, Default)]
+pub struct RecordingCanvas {
+    commands: Vec<DrawCommand>,
+    clip_depth: usize,
+}
+
+impl RecordingCanvas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    /// Returns the recorded commands, leaving the canvas empty.
+    pub fn take(&mut self) -> Vec<DrawCommand> {
+        self.clip_depth = 0;
+        std::mem::take(&mut self.commands)
+    }
+
+    /// Whether every `push_clip` so far has been matched by a `pop_clip`.
+    pub fn is_balanced(&self) -> bool {
+        self.clip_depth == 0
+    }
+
+    /// Panics with a readable report if the recorded commands differ from
+    /// `expected`.
+    pub fn assert_commands(&self, expected: &[DrawCommand]) {
+        if let Some(report) = diff_commands(&self.commands, expected) {
+            panic!("{}", report);
+        }
+    }
+}
+
+impl Canvas for RecordingCanvas {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.commands.push(DrawCommand::FillRect { rect, color });
+    }
+
+    fn fill_quad(&mut self, rect: Rect, colors: [Color; 4]) {
+        self.commands.push(DrawCommand::FillQuad { rect, colors });
+    }
+
+    fn draw_glyph_run(&mut self, glyphs: &[Glyph], color: Color) {
+        let glyphs = glyphs
+            .iter()
+            .map(|glyph| {
+                Rect::new(
+                    glyph.x,
+                    glyph.y,
+                    glyph.mask.width as f32,
+                    glyph.mask.height as f32,
+                )
+            })
+            .collect();
+        self.commands.push(DrawCommand::GlyphRun { glyphs, color });
+    }
+
+    fn draw_text(&mut self, text: &ShapedText, x: f32, y: f32, color: Color) {
+        self.commands.push(DrawCommand::Text {
+            text: text.text.clone(),
+            size: text.size,
+            x,
+            y,
+            color,
+        });
+    }
+
+    fn draw_image(&mut self, dest: Rect, image: &Image) {
+        self.commands.push(DrawCommand::Image {
+            dest,
+            width: image.width,
+            height: image.height,
+        });
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        self.clip_depth += 1;
+        self.commands.push(DrawCommand::PushClip(rect));
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip_depth = self.clip_depth.saturating_sub(1);
+        self.commands.push(DrawCommand::PopClip);
+    }
+}
+
+/// Compares two command lists, returning a description of every mismatch,
+/// or `None` if they are equal.
+pub fn diff_commands(actual: &[DrawCommand], expected: &[DrawCommand]) -> Option<String> {
+    if actual == expected {
+        return None;
+    }
+
+    let mut report = format!(
+        "draw commands differ (actual {}, expected {}):\n",
+        actual.len(),
+        expected.len()
+    );
+
+    for i in 0..actual.len().max(expected.len()) {
+        match (actual.get(i), expected.get(i)) {
+            (Some(a), Some(e)) if a == e => {
+                let _ = writeln!(report, "  {:>3}   {:?}", i, a);
+            }
+            (a, e) => {
+                if let Some(e) = e {
+                    let _ = writeln!(report, "  {:>3} - {:?}", i, e);
+                }
+                if let Some(a) = a {
+                    let _ = writeln!(report, "  {:>3} + {:?}", i, a);
+                }
+            }
+        }
+    }
+
+    Some(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widget::base::Widget;
+    use crate::widget::button::Button;
+
+    const BUTTON_COLORS: [Color; 4] = [
+        Color::rgb(1.0, 0.0, 0.0),
+        Color::rgb(0.0, 1.0, 0.0),
+        Color::rgb(0.0, 0.0, 1.0),
+        Color::rgb(1.0, 1.0, 0.0),
+    ];
+
+    #[test]
+    fn records_button_without_label() {
+        let button = Button::new(10.0, 20.0, 100.0, 40.0, "");
+        let mut canvas = RecordingCanvas::new();
+        button.paint(&mut canvas);
+
+        canvas.assert_commands(&[DrawCommand::FillQuad {
+            rect: Rect::new(10.0, 20.0, 100.0, 40.0),
+            colors: BUTTON_COLORS,
+        }]);
+    }
+
+    #[test]
+    fn records_button_label_inside_clip() {
+        let bounds = Rect::new(10.0, 20.0, 100.0, 40.0);
+        let button = Button::new(10.0, 20.0, 100.0, 40.0, "OK");
+        let mut canvas = RecordingCanvas::new();
+        button.paint(&mut canvas);
+
+        let commands = canvas.take();
+        assert_eq!(commands.len(), 4, "{:#?}", commands);
+        assert_eq!(
+            commands[0],
+            DrawCommand::FillQuad {
+                rect: bounds,
+                colors: BUTTON_COLORS,
+            }
+        );
+        assert_eq!(commands[1], DrawCommand::PushClip(bounds));
+        match &commands[2] {
+            DrawCommand::Text {
+                text,
+                size,
+                x,
+                y,
+                color,
+            } => {
+                assert_eq!(text, "OK");
+                assert_eq!(*size, 16.0);
+                assert_eq!(*color, Color::WHITE);
+                // Centered, so well inside the padding on both sides.
+                assert!(
+                    *x > bounds.x + 8.0 && *x < bounds.right() - 8.0,
+                    "x = {}",
+                    x
+                );
+                assert!(*y > bounds.y && *y < bounds.bottom(), "y = {}", y);
+            }
+            command => panic!("expected text, got {:?}", command),
+        }
+        assert_eq!(commands[3], DrawCommand::PopClip);
+        assert!(canvas.is_balanced());
+    }
+
+    #[test]
+    fn unbalanced_clips_are_detected() {
+        let mut canvas = RecordingCanvas::new();
+        canvas.push_clip(Rect::new(0.0, 0.0, 10.0, 10.0));
+        assert!(!canvas.is_balanced());
+        canvas.pop_clip();
+        assert!(canvas.is_balanced());
+    }
+
+    #[test]
+    fn equal_commands_have_no_diff() {
+        let commands = [
+            DrawCommand::PushClip(Rect::new(0.0, 0.0, 10.0, 10.0)),
+            DrawCommand::PopClip,
+        ];
+        assert_eq!(diff_commands(&commands, &commands), None);
+    }
+
+    #[test]
+    fn diff_marks_changed_and_missing_commands() {
+        let red = DrawCommand::FillRect {
+            rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+            color: Color::rgb(1.0, 0.0, 0.0),
+        };
+        let blue = DrawCommand::FillRect {
+            rect: Rect::new(0.0, 0.0, 10.0, 10.0),
+            color: Color::rgb(0.0, 0.0, 1.0),
+        };
+        let actual = [DrawCommand::PopClip, red.clone()];
+        let expected = [DrawCommand::PopClip, blue.clone(), DrawCommand::PopClip];
+
+        let report = diff_commands(&actual, &expected).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "draw commands differ (actual 2, expected 3):",
+                "    0   PopClip",
+                &format!("    1 - {:?}", blue),
+                &format!("    1 + {:?}", red),
+                "    2 - PopClip",
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "draw commands differ")]
+    fn assert_commands_panics_on_mismatch() {
+        let button = Button::new(0.0, 0.0, 100.0, 40.0, "");
+        let mut canvas = RecordingCanvas::new();
+        button.paint(&mut canvas);
+        canvas.assert_commands(&[]);
+    }
+}
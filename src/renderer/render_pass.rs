@@ -1,36 +1,127 @@
 use ash::vk;
-use ash::Device;
 use std::sync::Arc;
 
+use super::device::LogicalDevice;
+use crate::error::Result;
+
+/// A single-subpass render pass. Its attachments are the color attachment,
+/// then the depth attachment unless the depth format is `None`, then the
+/// resolve attachment if multisampled.
 pub struct RenderPass {
     pub render_pass: vk::RenderPass,
+    /// Samples per pixel of the color and depth attachments.
+    pub samples: vk::SampleCountFlags,
+    /// Format of the depth attachment, if there is one.
+    pub depth_format: Option<vk::Format>,
+    device: Arc<LogicalDevice>,
 }
 
 impl RenderPass {
     pub fn new(
-        device: &Arc<Device>,
+        device: &Arc<LogicalDevice>,
+        swapchain_format: vk::Format,
+        depth_format: Option<vk::Format>,
+    ) -> Result<Self> {
+        Self::create(
+            device,
+            swapchain_format,
+            depth_format,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            vk::SampleCountFlags::TYPE_1,
+        )
+    }
+
+    /// Creates a render pass that draws into multisampled color and depth
+    /// attachments and resolves the color into a third, presentable one.
+    /// With a single sample this is the same as [`Self::new`].
+    pub fn new_multisampled(
+        device: &Arc<LogicalDevice>,
         swapchain_format: vk::Format,
-        depth_format: vk::Format,
-    ) -> Self {
+        depth_format: Option<vk::Format>,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        Self::create(
+            device,
+            swapchain_format,
+            depth_format,
+            vk::ImageLayout::PRESENT_SRC_KHR,
+            samples,
+        )
+    }
+
+    /// Creates a render pass whose color attachment is left ready to be
+    /// copied out, for rendering to an offscreen image.
+    pub fn new_offscreen(
+        device: &Arc<LogicalDevice>,
+        color_format: vk::Format,
+        depth_format: Option<vk::Format>,
+    ) -> Result<Self> {
+        Self::create(
+            device,
+            color_format,
+            depth_format,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::SampleCountFlags::TYPE_1,
+        )
+    }
+
+    fn create(
+        device: &Arc<LogicalDevice>,
+        color_format: vk::Format,
+        depth_format: Option<vk::Format>,
+        color_final_layout: vk::ImageLayout,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let multisampled = samples != vk::SampleCountFlags::TYPE_1;
+
+        // A multisampled color attachment is only needed until it has been
+        // resolved.
         let color_attachment = vk::AttachmentDescription::default()
-            .format(swapchain_format)
-            .samples(vk::SampleCountFlags::TYPE_1)
+            .format(color_format)
+            .samples(samples)
             .load_op(vk::AttachmentLoadOp::CLEAR)
-            .store_op(vk::AttachmentStoreOp::STORE)
+            .store_op(if multisampled {
+                vk::AttachmentStoreOp::DONT_CARE
+            } else {
+                vk::AttachmentStoreOp::STORE
+            })
             .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
             .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+            .final_layout(if multisampled {
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+            } else {
+                color_final_layout
+            });
 
-        let depth_attachment = vk::AttachmentDescription::default()
-            .format(depth_format)
+        let resolve_attachment = vk::AttachmentDescription::default()
+            .format(color_format)
             .samples(vk::SampleCountFlags::TYPE_1)
-            .load_op(vk::AttachmentLoadOp::CLEAR)
-            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .store_op(vk::AttachmentStoreOp::STORE)
             .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
             .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+            .final_layout(color_final_layout);
+
+        let depth_attachment = depth_format.map(|depth_format| {
+            vk::AttachmentDescription::default()
+                .format(depth_format)
+                .samples(samples)
+                .load_op(vk::AttachmentLoadOp::CLEAR)
+                .store_op(vk::AttachmentStoreOp::DONT_CARE)
+                .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+                .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+                .initial_layout(vk::ImageLayout::UNDEFINED)
+                .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+        });
+
+        let mut attachments = vec![color_attachment];
+        attachments.extend(depth_attachment);
+        let resolve_index = attachments.len() as u32;
+        if multisampled {
+            attachments.push(resolve_attachment);
+        }
 
         let color_attachment_ref = [vk::AttachmentReference {
             attachment: 0,
@@ -42,12 +133,22 @@ impl RenderPass {
             layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
         };
 
-        let subpass = vk::SubpassDescription::default()
+        let resolve_attachment_ref = [vk::AttachmentReference {
+            attachment: resolve_index,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        }];
+
+        let mut subpass = vk::SubpassDescription::default()
             .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
-            .color_attachments(&color_attachment_ref)
-            .depth_stencil_attachment(&depth_attachment_ref);
+            .color_attachments(&color_attachment_ref);
+        if depth_format.is_some() {
+            subpass = subpass.depth_stencil_attachment(&depth_attachment_ref);
+        }
+        if multisampled {
+            subpass = subpass.resolve_attachments(&resolve_attachment_ref);
+        }
 
-        let dependency = vk::SubpassDependency::default()
+        let mut dependency = vk::SubpassDependency::default()
             .src_subpass(vk::SUBPASS_EXTERNAL)
             .dst_subpass(0)
             .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
@@ -56,28 +157,61 @@ impl RenderPass {
             .dst_access_mask(
                 vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
             );
+        // Frames in flight share the depth image, so clearing it waits for
+        // the previous frame's depth writes.
+        if depth_format.is_some() {
+            let depth_tests = vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS;
+            dependency.src_stage_mask |= depth_tests;
+            dependency.src_access_mask |= vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE;
+            dependency.dst_stage_mask |= depth_tests;
+            dependency.dst_access_mask |= vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE;
+        }
+
+        // Make the color writes visible to a copy recorded after the pass.
+        let readback_dependency = vk::SubpassDependency::default()
+            .src_subpass(0)
+            .dst_subpass(vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags::TRANSFER)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ);
 
-        let attachments = [color_attachment, depth_attachment];
         let subpasses = [subpass];
-        let dependencies = [dependency];
+        let dependencies = if color_final_layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+            vec![dependency, readback_dependency]
+        } else {
+            vec![dependency]
+        };
 
         let render_pass_info = vk::RenderPassCreateInfo::default()
             .attachments(&attachments)
             .subpasses(&subpasses)
             .dependencies(&dependencies);
 
-        let render_pass = unsafe {
-            device
-                .create_render_pass(&render_pass_info, None)
-                .expect("Failed to create render pass!")
-        };
+        let render_pass = unsafe { device.create_render_pass(&render_pass_info, None)? };
+        device.set_object_name(
+            render_pass,
+            if color_final_layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+                "offscreen render pass"
+            } else {
+                "window render pass"
+            },
+        );
 
-        RenderPass { render_pass }
+        Ok(RenderPass {
+            render_pass,
+            samples,
+            depth_format,
+            device: device.clone(),
+        })
     }
+}
 
-    pub fn cleanup(&self, device: &Arc<Device>) {
+impl Drop for RenderPass {
+    fn drop(&mut self) {
         unsafe {
-            device.destroy_render_pass(self.render_pass, None);
+            self.device.destroy_render_pass(self.render_pass, None);
         }
     }
 }
@@ -0,0 +1,366 @@
+use ash::khr::surface;
+use ash::vk;
+use std::cmp::Reverse;
+use std::ffi::CStr;
+
+use super::device::QueueFamilyIndices;
+use super::instance::AshInstance;
+use super::swapchain::SwapchainSupportDetails;
+use crate::error::{Error, Result};
+
+/// Set to a device index or part of a device name to use that GPU, if it is
+/// suitable.
+pub const DEVICE_ENV_VAR: &str = "UPLIFT_DEVICE";
+
+/// How the GPU to render with is chosen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceConfig {
+    /// The GPU to use if it is suitable: its index in enumeration order, or
+    /// part of its name, ignoring case. Read from [`DEVICE_ENV_VAR`] by
+    /// default. Without one, or if it matches no suitable GPU, the highest
+    /// scoring GPU is used.
+    pub preferred: Option<String>,
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig {
+            preferred: std::env::var(DEVICE_ENV_VAR)
+                .ok()
+                .filter(|value| !value.is_empty()),
+        }
+    }
+}
+
+/// A GPU that can do what the renderer needs.
+pub struct DeviceCandidate {
+    pub physical_device: vk::PhysicalDevice,
+    /// Position in enumeration order.
+    pub index: usize,
+    pub name: String,
+    pub device_type: vk::PhysicalDeviceType,
+    pub queue_family_indices: QueueFamilyIndices,
+    /// Higher is better.
+    pub score: u32,
+}
+
+impl DeviceCandidate {
+    fn matches(&self, preferred: &str) -> bool {
+        match preferred.parse::<usize>() {
+            Ok(index) => index == self.index,
+            Err(_) => self.name.to_lowercase().contains(&preferred.to_lowercase()),
+        }
+    }
+}
+
+/// Device extensions the renderer enables. Presenting needs the swapchain
+/// extension; offscreen rendering needs none.
+pub fn required_extensions(headless: bool) -> &'static [&'static CStr] {
+    if headless {
+        &[]
+    } else {
+        &[ash::khr::swapchain::NAME]
+    }
+}
+
+/// Device extensions enabled if available. The memory budget lets the
+/// [`Allocator`](super::allocator::Allocator) report usage against what the
+/// driver allows.
+pub fn optional_extensions() -> &'static [&'static CStr] {
+    &[ash::ext::memory_budget::NAME]
+}
+
+/// Chooses the GPU to render with.
+///
+/// A GPU is suitable if it has a graphics queue and, unless `surface` is
+/// null, can present to `surface` and create an adequate swapchain for it.
+/// Suitable GPUs are scored by type, discrete first, then by how many
+/// [`optional_extensions`] they have and whether they have a dedicated
+/// transfer queue. The preferred one in `config` is used over the best if
+/// there is one.
+pub fn select_physical_device(
+    instance: &AshInstance,
+    surface: vk::SurfaceKHR,
+    config: &DeviceConfig,
+) -> Result<DeviceCandidate> {
+    let surface_loader = surface::Instance::new(&instance.entry, &instance.instance);
+    let physical_devices = unsafe { instance.instance.enumerate_physical_devices()? };
+
+    let mut candidates = Vec::new();
+    for (index, physical_device) in physical_devices.into_iter().enumerate() {
+        let properties = unsafe {
+            instance
+                .instance
+                .get_physical_device_properties(physical_device)
+        };
+        let name = properties
+            .device_name_as_c_str()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        match evaluate(instance, &surface_loader, physical_device, surface) {
+            Ok((queue_family_indices, optional_count)) => {
+                let score = score(
+                    properties.device_type,
+                    optional_count,
+                    queue_family_indices.transfer_family.is_some(),
+                );
+                log::debug!(
+                    "GPU {} ({}, {:?}) is suitable, score {}",
+                    index,
+                    name,
+                    properties.device_type,
+                    score
+                );
+                candidates.push(DeviceCandidate {
+                    physical_device,
+                    index,
+                    name,
+                    device_type: properties.device_type,
+                    queue_family_indices,
+                    score,
+                });
+            }
+            Err(reason) => log::debug!("GPU {} ({}) is unsuitable: {}", index, name, reason),
+        }
+    }
+
+    let preferred = config.preferred.as_deref().and_then(|preferred| {
+        let position = candidates
+            .iter()
+            .position(|candidate| candidate.matches(preferred));
+        if position.is_none() {
+            log::warn!(
+                "no suitable GPU matches {:?}, choosing one automatically",
+                preferred
+            );
+        }
+        position
+    });
+    let chosen = match preferred {
+        Some(position) => Some(candidates.swap_remove(position)),
+        // The first of equally scored GPUs.
+        None => candidates
+            .into_iter()
+            .min_by_key(|candidate| Reverse(candidate.score)),
+    }
+    .ok_or(Error::NoSuitableDevice)?;
+
+    log::info!("using GPU {} ({})", chosen.index, chosen.name);
+    Ok(chosen)
+}
+
+/// The queue families to use on `physical_device` and how many optional
+/// extensions it has, or why it is unsuitable.
+fn evaluate(
+    instance: &AshInstance,
+    surface_loader: &surface::Instance,
+    physical_device: vk::PhysicalDevice,
+    surface: vk::SurfaceKHR,
+) -> std::result::Result<(QueueFamilyIndices, usize), String> {
+    let headless = surface == vk::SurfaceKHR::null();
+
+    let mut indices = find_queue_families(instance, surface_loader, physical_device, surface);
+    if headless {
+        indices.present_family = indices.graphics_family;
+    }
+    if indices.graphics_family.is_none() {
+        return Err("no graphics queue".to_string());
+    }
+    if indices.present_family.is_none() {
+        return Err("cannot present to the surface".to_string());
+    }
+
+    let extensions = unsafe {
+        instance
+            .instance
+            .enumerate_device_extension_properties(physical_device)
+            .unwrap_or_default()
+    };
+    let has_extension = |name: &CStr| {
+        extensions
+            .iter()
+            .any(|extension| extension.extension_name_as_c_str() == Ok(name))
+    };
+    if let Some(missing) = required_extensions(headless)
+        .iter()
+        .find(|&&required| !has_extension(required))
+    {
+        return Err(format!("missing {}", missing.to_string_lossy()));
+    }
+    let optional_count = optional_extensions()
+        .iter()
+        .filter(|&&optional| has_extension(optional))
+        .count();
+
+    if !headless {
+        let support = SwapchainSupportDetails::query(surface_loader, physical_device, surface)
+            .map_err(|err| format!("cannot query swapchain support: {}", err))?;
+        if !support.is_adequate() {
+            return Err("no surface formats or present modes".to_string());
+        }
+    }
+
+    Ok((indices, optional_count))
+}
+
+/// Finds a graphics queue family and one that can present to `surface`,
+/// preferring a single family that can do both, and a transfer-only family
+/// if there is one. No present family is found for a null surface.
+fn find_queue_families(
+    instance: &AshInstance,
+    surface_loader: &surface::Instance,
+    physical_device: vk::PhysicalDevice,
+    surface: vk::SurfaceKHR,
+) -> QueueFamilyIndices {
+    let queue_families = unsafe {
+        instance
+            .instance
+            .get_physical_device_queue_family_properties(physical_device)
+    };
+    let mut indices = QueueFamilyIndices {
+        transfer_family: find_transfer_family(&queue_families),
+        ..QueueFamilyIndices::default()
+    };
+
+    for (i, queue_family) in queue_families.iter().enumerate() {
+        let i = i as u32;
+        let graphics = queue_family.queue_flags.contains(vk::QueueFlags::GRAPHICS);
+        let present = surface != vk::SurfaceKHR::null()
+            && unsafe {
+                surface_loader
+                    .get_physical_device_surface_support(physical_device, i, surface)
+                    .unwrap_or(false)
+            };
+
+        if graphics && present {
+            indices.graphics_family = Some(i);
+            indices.present_family = Some(i);
+            return indices;
+        }
+        if graphics && indices.graphics_family.is_none() {
+            indices.graphics_family = Some(i);
+        }
+        if present && indices.present_family.is_none() {
+            indices.present_family = Some(i);
+        }
+    }
+
+    indices
+}
+
+/// Finds a family that can transfer but not draw, preferring one that cannot
+/// compute either.
+fn find_transfer_family(queue_families: &[vk::QueueFamilyProperties]) -> Option<u32> {
+    let transfer_only = |excluded: vk::QueueFlags| {
+        queue_families.iter().position(|family| {
+            family.queue_flags.contains(vk::QueueFlags::TRANSFER)
+                && !family.queue_flags.intersects(excluded)
+        })
+    };
+    transfer_only(vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE)
+        .or_else(|| transfer_only(vk::QueueFlags::GRAPHICS))
+        .map(|i| i as u32)
+}
+
+/// Scores a suitable GPU. The type always outweighs the other terms, which
+/// only break ties between GPUs of the same type.
+fn score(device_type: vk::PhysicalDeviceType, optional_count: usize, transfer: bool) -> u32 {
+    type_score(device_type) * 10 + optional_count as u32 + transfer as u32
+}
+
+fn type_score(device_type: vk::PhysicalDeviceType) -> u32 {
+    match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 4,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 3,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(index: usize, name: &str) -> DeviceCandidate {
+        DeviceCandidate {
+            physical_device: vk::PhysicalDevice::null(),
+            index,
+            name: name.to_string(),
+            device_type: vk::PhysicalDeviceType::DISCRETE_GPU,
+            queue_family_indices: QueueFamilyIndices::default(),
+            score: 0,
+        }
+    }
+
+    fn families(flags: &[vk::QueueFlags]) -> Vec<vk::QueueFamilyProperties> {
+        flags
+            .iter()
+            .map(|&queue_flags| vk::QueueFamilyProperties {
+                queue_flags,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn preferred_device_matches_index_or_name() {
+        let gpu = candidate(1, "NVIDIA GeForce RTX 3060");
+
+        assert!(gpu.matches("1"));
+        assert!(!gpu.matches("0"));
+        // Digits are always an index, even if the name contains them.
+        assert!(!gpu.matches("3060"));
+        assert!(gpu.matches("geforce"));
+        assert!(gpu.matches("RTX"));
+        assert!(gpu.matches("nVidia geForce"));
+        assert!(!gpu.matches("radeon"));
+    }
+
+    #[test]
+    fn transfer_family_prefers_transfer_only() {
+        let graphics =
+            vk::QueueFlags::GRAPHICS | vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER;
+        let compute = vk::QueueFlags::COMPUTE | vk::QueueFlags::TRANSFER;
+        let transfer = vk::QueueFlags::TRANSFER | vk::QueueFlags::SPARSE_BINDING;
+
+        assert_eq!(
+            find_transfer_family(&families(&[graphics, compute, transfer])),
+            Some(2)
+        );
+        assert_eq!(
+            find_transfer_family(&families(&[graphics, compute])),
+            Some(1)
+        );
+        assert_eq!(find_transfer_family(&families(&[graphics])), None);
+        assert_eq!(find_transfer_family(&[]), None);
+    }
+
+    #[test]
+    fn type_outweighs_other_score_terms() {
+        let types = [
+            vk::PhysicalDeviceType::DISCRETE_GPU,
+            vk::PhysicalDeviceType::INTEGRATED_GPU,
+            vk::PhysicalDeviceType::VIRTUAL_GPU,
+            vk::PhysicalDeviceType::CPU,
+            vk::PhysicalDeviceType::OTHER,
+        ];
+        let type_scores: Vec<u32> = types.iter().map(|&t| type_score(t)).collect();
+        assert_eq!(type_scores, [4, 3, 2, 1, 0]);
+
+        let optional_count = optional_extensions().len();
+        assert!(
+            score(vk::PhysicalDeviceType::DISCRETE_GPU, 0, false)
+                > score(vk::PhysicalDeviceType::INTEGRATED_GPU, optional_count, true)
+        );
+        assert!(
+            score(vk::PhysicalDeviceType::INTEGRATED_GPU, 1, false)
+                > score(vk::PhysicalDeviceType::INTEGRATED_GPU, 0, false)
+        );
+        assert!(
+            score(vk::PhysicalDeviceType::INTEGRATED_GPU, 0, true)
+                > score(vk::PhysicalDeviceType::INTEGRATED_GPU, 0, false)
+        );
+    }
+}
@@ -0,0 +1,18 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D glyphAtlas;
+layout(set = 0, binding = 1) uniform sampler glyphSampler;
+
+layout(location = 0) in vec2 fragTexCoord;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+/**
+ * Fragment shader that multiplies the atlas texel by the vertex color.
+ * Outline glyphs are stored white with coverage in alpha and take the text
+ * color; color glyphs are drawn with a white vertex color.
+ */
+void main() {
+    outColor = fragColor * texture(sampler2D(glyphAtlas, glyphSampler), fragTexCoord);
+}
@@ -0,0 +1,22 @@
+/**
+ * @file text.vert
+ * @brief Vertex shader for textured glyph quads.
+ *
+ * Positions arrive in clip space. The atlas coordinate and tint color are
+ * passed through to the fragment shader.
+ */
+
+#version 450
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec2 inTexCoord;
+layout(location = 2) in vec4 inColor;
+
+layout(location = 0) out vec2 fragTexCoord;
+layout(location = 1) out vec4 fragColor;
+
+void main() {
+    gl_Position = vec4(inPosition, 0.0, 1.0);
+    fragTexCoord = inTexCoord;
+    fragColor = inColor;
+}
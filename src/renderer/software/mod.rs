@@ -0,0 +1,3 @@
+pub mod pixmap;
+pub mod rasterizer;
+pub mod surface;
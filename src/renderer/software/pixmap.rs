@@ -0,0 +1,114 @@
+use crate::renderer::primitive::{Color, Image};
+
+/// A CPU-side RGBA8 render target with straight alpha.
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Pixmap {
+    pub fn new(width: u32, height: u32) -> Self {
+        Pixmap {
+            width,
+            height,
+            data: vec![0; (width * height * 4) as usize],
+        }
+    }
+
+    /// Resizes the pixmap, discarding its contents.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.data.clear();
+        self.data.resize((width * height * 4) as usize, 0);
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        let rgba = color.to_rgba8();
+        for pixel in self.data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&rgba);
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// Composites `color` over the pixel at `(x, y)`, scaled by `coverage`.
+    pub fn blend_pixel(&mut self, x: u32, y: u32, color: Color, coverage: f32) {
+        let src_a = color.a * coverage;
+        if src_a <= 0.0 {
+            return;
+        }
+
+        let i = self.index(x, y);
+        let dst = Color::from_rgba8([
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]);
+
+        let out_a = src_a + dst.a * (1.0 - src_a);
+        let blend = |s: f32, d: f32| (s * src_a + d * dst.a * (1.0 - src_a)) / out_a;
+        let out = Color::rgba(
+            blend(color.r, dst.r),
+            blend(color.g, dst.g),
+            blend(color.b, dst.b),
+            out_a,
+        );
+
+        self.data[i..i + 4].copy_from_slice(&out.to_rgba8());
+    }
+
+    /// Copies the contents into an owned [`Image`].
+    pub fn to_image(&self) -> Image {
+        Image::new(self.width, self.height, self.data.clone())
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        ((y * self.width + x) * 4) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red_pixmap() -> Pixmap {
+        let mut pixmap = Pixmap::new(1, 1);
+        pixmap.fill(Color::rgb(1.0, 0.0, 0.0));
+        pixmap
+    }
+
+    #[test]
+    fn blend_pixel_scales_by_coverage() {
+        let blue = Color::rgb(0.0, 0.0, 1.0);
+
+        let mut pixmap = red_pixmap();
+        pixmap.blend_pixel(0, 0, blue, 0.0);
+        assert_eq!(pixmap.pixel(0, 0), [255, 0, 0, 255]);
+
+        let mut pixmap = red_pixmap();
+        pixmap.blend_pixel(0, 0, blue, 1.0);
+        assert_eq!(pixmap.pixel(0, 0), [0, 0, 255, 255]);
+
+        let mut pixmap = red_pixmap();
+        pixmap.blend_pixel(0, 0, blue, 0.5);
+        assert_eq!(pixmap.pixel(0, 0), [128, 0, 128, 255]);
+    }
+
+    #[test]
+    fn blend_pixel_over_transparent_keeps_straight_alpha() {
+        let mut pixmap = Pixmap::new(1, 1);
+        pixmap.blend_pixel(0, 0, Color::rgb(0.0, 0.0, 1.0), 0.5);
+        assert_eq!(pixmap.pixel(0, 0), [0, 0, 255, 128]);
+    }
+}
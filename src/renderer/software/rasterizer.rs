@@ -0,0 +1,275 @@
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{AlphaMask, Color, Glyph, Image, Rect};
+use crate::text::raster::{place_glyphs, GlyphBitmap, GlyphCache};
+use crate::text::shaping::ShapedText;
+
+use super::pixmap::Pixmap;
+
+/// Draws UI primitives into a [`Pixmap`] on the CPU.
+///
+/// This mirrors what the Vulkan pipeline draws, so it can stand in on
+/// machines without a GPU.
+pub struct SoftwareRenderer {
+    pub pixmap: Pixmap,
+    clip_stack: Vec<Rect>,
+    glyph_cache: GlyphCache,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        SoftwareRenderer {
+            pixmap: Pixmap::new(width, height),
+            clip_stack: Vec::new(),
+            glyph_cache: GlyphCache::new(),
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.pixmap.resize(width, height);
+        self.clip_stack.clear();
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.pixmap.fill(color);
+    }
+
+    /// Tints a coverage mask (e.g. a rasterized glyph) with `color` and
+    /// draws it with its top-left corner at `(x, y)`.
+    pub fn draw_mask(&mut self, x: i32, y: i32, mask: &AlphaMask, color: Color) {
+        let dest = Rect::new(x as f32, y as f32, mask.width as f32, mask.height as f32);
+        let area = dest.intersect(&self.current_clip());
+        if area.is_empty() {
+            return;
+        }
+
+        let (x0, y0, x1, y1) = self.pixel_bounds(&area);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let mx = (px as i32 - x) as u32;
+                let my = (py as i32 - y) as u32;
+                let coverage = mask.coverage[(my * mask.width + mx) as usize] as f32 / 255.0;
+
+                self.pixmap.blend_pixel(px, py, color, coverage);
+            }
+        }
+    }
+
+    fn current_clip(&self) -> Rect {
+        self.clip_stack.last().copied().unwrap_or(Rect::new(
+            0.0,
+            0.0,
+            self.pixmap.width as f32,
+            self.pixmap.height as f32,
+        ))
+    }
+
+    /// Returns the range of whole pixels touched by `rect`, clamped to the
+    /// pixmap.
+    fn pixel_bounds(&self, rect: &Rect) -> (u32, u32, u32, u32) {
+        let x0 = rect.x.floor().max(0.0) as u32;
+        let y0 = rect.y.floor().max(0.0) as u32;
+        let x1 = (rect.right().ceil().max(0.0) as u32).min(self.pixmap.width);
+        let y1 = (rect.bottom().ceil().max(0.0) as u32).min(self.pixmap.height);
+
+        (x0, y0, x1, y1)
+    }
+}
+
+impl Canvas for SoftwareRenderer {
+    fn fill_quad(&mut self, rect: Rect, colors: [Color; 4]) {
+        let area = rect.intersect(&self.current_clip());
+        if area.is_empty() {
+            return;
+        }
+
+        let (x0, y0, x1, y1) = self.pixel_bounds(&area);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let pixel = Rect::new(x as f32, y as f32, 1.0, 1.0);
+                let coverage = pixel.intersect(&area);
+                let coverage = coverage.width * coverage.height;
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                let u = ((x as f32 + 0.5 - rect.x) / rect.width).clamp(0.0, 1.0);
+                let v = ((y as f32 + 0.5 - rect.y) / rect.height).clamp(0.0, 1.0);
+                let top = colors[0].lerp(colors[1], u);
+                let bottom = colors[3].lerp(colors[2], u);
+
+                self.pixmap.blend_pixel(x, y, top.lerp(bottom, v), coverage);
+            }
+        }
+    }
+
+    fn draw_glyph_run(&mut self, glyphs: &[Glyph], color: Color) {
+        for glyph in glyphs {
+            self.draw_mask(
+                glyph.x.round() as i32,
+                glyph.y.round() as i32,
+                glyph.mask,
+                color,
+            );
+        }
+    }
+
+    fn draw_text(&mut self, text: &ShapedText, x: f32, y: f32, color: Color) {
+        // Taken out of `self` so cached masks can be borrowed while drawing.
+        let mut cache = std::mem::take(&mut self.glyph_cache);
+        for placed in place_glyphs(text, x, y) {
+            let Some(glyph) = cache.get(placed.font, placed.key) else {
+                continue;
+            };
+
+            let x = placed.x + glyph.left;
+            let y = placed.baseline - glyph.top;
+            match &glyph.bitmap {
+                GlyphBitmap::Mask(mask) => self.draw_mask(x, y, mask, color),
+                GlyphBitmap::Color(image) => {
+                    let dest =
+                        Rect::new(x as f32, y as f32, image.width as f32, image.height as f32);
+                    self.draw_image(dest, image);
+                }
+            }
+        }
+        self.glyph_cache = cache;
+    }
+
+    /// Samples `image` with nearest-neighbour filtering.
+    fn draw_image(&mut self, dest: Rect, image: &Image) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+
+        let area = dest.intersect(&self.current_clip());
+        if area.is_empty() {
+            return;
+        }
+
+        let (x0, y0, x1, y1) = self.pixel_bounds(&area);
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let u = (px as f32 + 0.5 - dest.x) / dest.width;
+                let v = (py as f32 + 0.5 - dest.y) / dest.height;
+                let sx = ((u * image.width as f32) as u32).min(image.width - 1);
+                let sy = ((v * image.height as f32) as u32).min(image.height - 1);
+
+                let color = Color::from_rgba8(image.pixel(sx, sy));
+                self.pixmap.blend_pixel(px, py, color, 1.0);
+            }
+        }
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        let clip = self.current_clip().intersect(&rect);
+        self.clip_stack.push(clip);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renderer(width: u32, height: u32) -> SoftwareRenderer {
+        let mut renderer = SoftwareRenderer::new(width, height);
+        renderer.clear(Color::BLACK);
+        renderer
+    }
+
+    fn reds(renderer: &SoftwareRenderer, y: u32) -> Vec<u8> {
+        (0..renderer.pixmap.width)
+            .map(|x| renderer.pixmap.pixel(x, y)[0])
+            .collect()
+    }
+
+    #[test]
+    fn fill_quad_covers_fractional_edges_partially() {
+        let mut renderer = renderer(4, 1);
+        renderer.fill_quad(Rect::new(0.5, 0.0, 2.0, 1.0), [Color::WHITE; 4]);
+        assert_eq!(reds(&renderer, 0), [128, 255, 128, 0]);
+    }
+
+    #[test]
+    fn fill_quad_interpolates_corner_colors() {
+        let mut renderer = renderer(2, 2);
+        renderer.fill_quad(
+            Rect::new(0.0, 0.0, 2.0, 2.0),
+            [
+                Color::rgb(1.0, 0.0, 0.0),
+                Color::rgb(0.0, 1.0, 0.0),
+                Color::rgb(0.0, 0.0, 1.0),
+                Color::rgb(1.0, 1.0, 0.0),
+            ],
+        );
+
+        // Corners in order: top left, top right, bottom right, bottom left.
+        assert_eq!(renderer.pixmap.pixel(0, 0), [191, 96, 16, 255]);
+        assert_eq!(renderer.pixmap.pixel(1, 0), [64, 159, 48, 255]);
+        assert_eq!(renderer.pixmap.pixel(1, 1), [64, 96, 143, 255]);
+        assert_eq!(renderer.pixmap.pixel(0, 1), [191, 159, 48, 255]);
+    }
+
+    #[test]
+    fn nested_clips_intersect_and_pop() {
+        let mut renderer = renderer(4, 1);
+        let everything = Rect::new(0.0, 0.0, 4.0, 1.0);
+
+        renderer.push_clip(Rect::new(0.0, 0.0, 3.0, 1.0));
+        renderer.push_clip(Rect::new(1.0, 0.0, 3.0, 1.0));
+        renderer.fill_quad(everything, [Color::rgb(0.5, 0.0, 0.0); 4]);
+        assert_eq!(reds(&renderer, 0), [0, 128, 128, 0]);
+
+        renderer.pop_clip();
+        renderer.fill_quad(everything, [Color::WHITE; 4]);
+        assert_eq!(reds(&renderer, 0), [255, 255, 255, 0]);
+
+        renderer.pop_clip();
+        renderer.fill_quad(everything, [Color::WHITE; 4]);
+        assert_eq!(reds(&renderer, 0), [255; 4]);
+    }
+
+    #[test]
+    fn draw_mask_clips_to_the_pixmap() {
+        // Coverage 0, 10, ... 80, row by row.
+        let mask = AlphaMask::new(3, 3, (0..9).map(|i| i * 10).collect());
+        let mut renderer = SoftwareRenderer::new(4, 4);
+
+        renderer.draw_mask(-1, -2, &mask, Color::WHITE);
+        assert_eq!(renderer.pixmap.pixel(0, 0), [255, 255, 255, 70]);
+        assert_eq!(renderer.pixmap.pixel(1, 0), [255, 255, 255, 80]);
+        assert_eq!(renderer.pixmap.pixel(0, 1), [0; 4]);
+
+        renderer.draw_mask(3, 2, &mask, Color::WHITE);
+        assert_eq!(renderer.pixmap.pixel(3, 2), [0; 4]);
+        assert_eq!(renderer.pixmap.pixel(3, 3), [255, 255, 255, 30]);
+    }
+
+    #[test]
+    fn draw_image_clips_to_the_pixmap() {
+        let image = Image::new(
+            2,
+            2,
+            [
+                [255, 0, 0, 255],
+                [0, 255, 0, 255],
+                [0, 0, 255, 255],
+                [255, 255, 255, 255],
+            ]
+            .concat(),
+        );
+        let mut renderer = SoftwareRenderer::new(4, 4);
+
+        renderer.draw_image(Rect::new(-1.0, -1.0, 2.0, 2.0), &image);
+        assert_eq!(renderer.pixmap.pixel(0, 0), [255, 255, 255, 255]);
+        assert_eq!(renderer.pixmap.pixel(1, 0), [0; 4]);
+        assert_eq!(renderer.pixmap.pixel(0, 1), [0; 4]);
+
+        renderer.draw_image(Rect::new(3.0, 3.0, 2.0, 2.0), &image);
+        assert_eq!(renderer.pixmap.pixel(3, 3), [255, 0, 0, 255]);
+        assert_eq!(renderer.pixmap.pixel(2, 3), [0; 4]);
+    }
+}
@@ -0,0 +1,44 @@
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use winit::window::Window;
+
+use super::pixmap::Pixmap;
+
+/// Presents a [`Pixmap`] to a window through the platform's native
+/// (non-GPU) surface.
+pub struct SoftwareSurface {
+    _context: softbuffer::Context<Arc<Window>>,
+    surface: softbuffer::Surface<Arc<Window>, Arc<Window>>,
+}
+
+impl SoftwareSurface {
+    pub fn new(window: Arc<Window>) -> Result<Self, softbuffer::SoftBufferError> {
+        let context = softbuffer::Context::new(window.clone())?;
+        let surface = softbuffer::Surface::new(&context, window)?;
+
+        Ok(SoftwareSurface {
+            _context: context,
+            surface,
+        })
+    }
+
+    /// Copies `pixmap` to the window. Frames with a zero extent (e.g. while
+    /// minimized) are skipped.
+    pub fn present(&mut self, pixmap: &Pixmap) -> Result<(), softbuffer::SoftBufferError> {
+        let (Some(width), Some(height)) = (
+            NonZeroU32::new(pixmap.width),
+            NonZeroU32::new(pixmap.height),
+        ) else {
+            return Ok(());
+        };
+
+        self.surface.resize(width, height)?;
+
+        let mut buffer = self.surface.buffer_mut()?;
+        for (dst, src) in buffer.iter_mut().zip(pixmap.data.chunks_exact(4)) {
+            // softbuffer expects 0RGB packed into a u32.
+            *dst = (src[0] as u32) << 16 | (src[1] as u32) << 8 | src[2] as u32;
+        }
+        buffer.present()
+    }
+}
@@ -0,0 +1,79 @@
+use ash::khr::surface;
+use ash::vk;
+use std::sync::Arc;
+use winit::raw_window_handle::{
+    HasDisplayHandle, HasWindowHandle, RawDisplayHandle, RawWindowHandle,
+};
+
+use super::instance::AshInstance;
+use crate::error::{Error, Result};
+
+/// A surface to present to a window, destroyed on drop.
+///
+/// Holds the instance it was created from, so the instance outlives it. It
+/// must itself outlive any swapchain created for it.
+pub struct Surface {
+    handle: vk::SurfaceKHR,
+    pub loader: surface::Instance,
+    _instance: Arc<AshInstance>,
+}
+
+impl Surface {
+    /// Creates a surface for `window`. The instance must have the
+    /// extensions from [`ExtensionRequest::for_display`] enabled.
+    ///
+    /// # Safety
+    ///
+    /// `window` must outlive the surface.
+    ///
+    /// [`ExtensionRequest::for_display`]: super::instance::ExtensionRequest::for_display
+    pub unsafe fn from_window(
+        instance: &Arc<AshInstance>,
+        window: &(impl HasDisplayHandle + HasWindowHandle),
+    ) -> Result<Self> {
+        let display_handle = window
+            .display_handle()
+            .map_err(|err| Error::Window(err.to_string()))?;
+        let window_handle = window
+            .window_handle()
+            .map_err(|err| Error::Window(err.to_string()))?;
+        Self::from_raw(instance, display_handle.as_raw(), window_handle.as_raw())
+    }
+
+    /// Creates a surface from a window's raw handles.
+    ///
+    /// # Safety
+    ///
+    /// The handles must be valid and the window must outlive the surface.
+    pub unsafe fn from_raw(
+        instance: &Arc<AshInstance>,
+        display: RawDisplayHandle,
+        window: RawWindowHandle,
+    ) -> Result<Self> {
+        if !instance.is_extension_enabled(surface::NAME) {
+            return Err(Error::MissingExtension(
+                surface::NAME.to_string_lossy().into_owned(),
+            ));
+        }
+        let handle =
+            ash_window::create_surface(&instance.entry, &instance.instance, display, window, None)?;
+
+        Ok(Surface {
+            handle,
+            loader: surface::Instance::new(&instance.entry, &instance.instance),
+            _instance: instance.clone(),
+        })
+    }
+
+    pub fn handle(&self) -> vk::SurfaceKHR {
+        self.handle
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        unsafe {
+            self.loader.destroy_surface(self.handle, None);
+        }
+    }
+}
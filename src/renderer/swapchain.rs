@@ -1,7 +1,23 @@
 use ash::khr::{self, surface, swapchain};
 use ash::vk;
-use ash::{Device, Instance};
-use winit::window::Window;
+use ash::Instance;
+use std::sync::Arc;
+
+use super::device::LogicalDevice;
+use crate::error::Result;
+
+/// What to ask of a new swapchain. Preferences the surface does not support
+/// are replaced by ones it does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapchainConfig {
+    /// The window's inner size in pixels.
+    pub window_extent: vk::Extent2D,
+    /// Used if supported, and FIFO, which always is, otherwise.
+    pub present_mode: vk::PresentModeKHR,
+    /// Whether the window is blended with what is behind it, using the
+    /// alpha of the images as premultiplied alpha.
+    pub transparent: bool,
+}
 
 pub struct SwapchainSupportDetails {
     pub capabilities: vk::SurfaceCapabilitiesKHR,
@@ -9,6 +25,99 @@ pub struct SwapchainSupportDetails {
     pub present_modes: Vec<vk::PresentModeKHR>,
 }
 
+impl SwapchainSupportDetails {
+    /// Queries what `physical_device` supports for swapchains on `surface`.
+    pub fn query(
+        surface_loader: &surface::Instance,
+        physical_device: vk::PhysicalDevice,
+        surface: vk::SurfaceKHR,
+    ) -> Result<Self> {
+        unsafe {
+            Ok(SwapchainSupportDetails {
+                capabilities: surface_loader
+                    .get_physical_device_surface_capabilities(physical_device, surface)?,
+                formats: surface_loader
+                    .get_physical_device_surface_formats(physical_device, surface)?,
+                present_modes: surface_loader
+                    .get_physical_device_surface_present_modes(physical_device, surface)?,
+            })
+        }
+    }
+
+    /// Whether a swapchain can be created at all: there is at least one
+    /// format and present mode.
+    pub fn is_adequate(&self) -> bool {
+        !self.formats.is_empty() && !self.present_modes.is_empty()
+    }
+
+    /// The preferred sRGB format, or the first one offered.
+    pub fn choose_format(&self) -> vk::SurfaceFormatKHR {
+        self.formats
+            .iter()
+            .cloned()
+            .find(|format| {
+                format.format == vk::Format::B8G8R8A8_SRGB
+                    && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
+            })
+            .unwrap_or(self.formats[0])
+    }
+
+    /// `preferred` if supported, or FIFO.
+    pub fn choose_present_mode(&self, preferred: vk::PresentModeKHR) -> vk::PresentModeKHR {
+        if self.present_modes.contains(&preferred) {
+            preferred
+        } else {
+            vk::PresentModeKHR::FIFO
+        }
+    }
+
+    /// How the images are composited with what is behind the window. Opaque
+    /// unless `transparent` is requested and the surface can blend.
+    pub fn choose_composite_alpha(&self, transparent: bool) -> vk::CompositeAlphaFlagsKHR {
+        let supported = self.capabilities.supported_composite_alpha;
+        let preferred: &[vk::CompositeAlphaFlagsKHR] = if transparent {
+            &[
+                vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+                vk::CompositeAlphaFlagsKHR::INHERIT,
+                vk::CompositeAlphaFlagsKHR::OPAQUE,
+                vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+            ]
+        } else {
+            &[
+                vk::CompositeAlphaFlagsKHR::OPAQUE,
+                vk::CompositeAlphaFlagsKHR::INHERIT,
+                vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+                vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED,
+            ]
+        };
+
+        preferred
+            .iter()
+            .copied()
+            .find(|&mode| supported.contains(mode))
+            .unwrap_or(vk::CompositeAlphaFlagsKHR::OPAQUE)
+    }
+
+    /// The extent the swapchain should have for a window of `window_extent`
+    /// pixels. Zero in either dimension while the window is minimized.
+    pub fn choose_extent(&self, window_extent: vk::Extent2D) -> vk::Extent2D {
+        let capabilities = &self.capabilities;
+        match capabilities.current_extent.width {
+            u32::MAX => vk::Extent2D {
+                width: window_extent.width.clamp(
+                    capabilities.min_image_extent.width,
+                    capabilities.max_image_extent.width,
+                ),
+                height: window_extent.height.clamp(
+                    capabilities.min_image_extent.height,
+                    capabilities.max_image_extent.height,
+                ),
+            },
+            _ => capabilities.current_extent,
+        }
+    }
+}
+
 pub struct Swapchain {
     pub swapchain: khr::swapchain::Device,
     pub swapchain_khr: vk::SwapchainKHR,
@@ -16,6 +125,7 @@ pub struct Swapchain {
     pub image_views: Vec<vk::ImageView>,
     pub format: vk::Format,
     pub extent: vk::Extent2D,
+    device: Arc<LogicalDevice>,
 }
 
 /// Represents a Vulkan swapchain.
@@ -25,74 +135,37 @@ impl Swapchain {
     /// # Arguments
     ///
     /// * `instance` - The Vulkan instance.
+    /// * `surface_loader` - The surface extension functions.
     /// * `physical_device` - The physical device.
     /// * `device` - The Vulkan device.
-    /// * `surface` - The Vulkan surface.
-    /// * `window` - The window.
+    /// * `surface` - The Vulkan surface. It must outlive the swapchain.
+    /// * `config` - The requested size and presentation.
+    /// * `old_swapchain` - The swapchain being replaced, or null. It is
+    ///   retired but must still be dropped by the caller.
     ///
     /// # Returns
     ///
-    /// A new `Swapchain` instance.
+    /// A new `Swapchain` instance, or the error that prevented creating it.
     pub fn new(
         instance: &Instance,
+        surface_loader: &surface::Instance,
         physical_device: vk::PhysicalDevice,
-        device: &Device,
+        device: &Arc<LogicalDevice>,
         surface: vk::SurfaceKHR,
-        window: &Window,
-    ) -> Self {
-        // Create a surface loader
-        let surface_loader =
-            surface::Instance::new(unsafe { &ash::Entry::load().unwrap() }, instance);
-
-        // Get the surface capabilities
-        let capabilities = unsafe {
-            surface_loader
-                .get_physical_device_surface_capabilities(physical_device, surface)
-                .unwrap()
-        };
-
-        // Get the surface formats
-        let formats = unsafe {
-            surface_loader
-                .get_physical_device_surface_formats(physical_device, surface)
-                .unwrap()
-        };
-
-        // Get the present modes
-        let present_modes = unsafe {
-            surface_loader
-                .get_physical_device_surface_present_modes(physical_device, surface)
-                .unwrap()
-        };
+        config: &SwapchainConfig,
+        old_swapchain: vk::SwapchainKHR,
+    ) -> Result<Self> {
+        let support = SwapchainSupportDetails::query(surface_loader, physical_device, surface)?;
+        let capabilities = support.capabilities;
 
         // Choose the surface format
-        let surface_format = formats
-            .iter()
-            .cloned()
-            .find(|format| {
-                format.format == vk::Format::B8G8R8A8_SRGB
-                    && format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
-            })
-            .unwrap_or(formats[0]);
+        let surface_format = support.choose_format();
 
         // Choose the present mode
-        let present_mode = present_modes
-            .iter()
-            .cloned()
-            .find(|&mode| mode == vk::PresentModeKHR::MAILBOX)
-            .unwrap_or(vk::PresentModeKHR::FIFO);
+        let present_mode = support.choose_present_mode(config.present_mode);
 
         // Choose the extent
-        let extent = match capabilities.current_extent.width {
-            std::u32::MAX => {
-                let window_size = window.inner_size();
-                vk::Extent2D {
-                    width: window_size.width,
-                    height: window_size.height,
-                }
-            }
-            _ => capabilities.current_extent,
-        };
+        let extent = support.choose_extent(config.window_extent);
 
         // Choose the image count
         let image_count = if capabilities.max_image_count > 0 {
@@ -114,10 +187,10 @@ impl Swapchain {
             image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
             image_sharing_mode: vk::SharingMode::EXCLUSIVE,
             pre_transform: capabilities.current_transform,
-            composite_alpha: vk::CompositeAlphaFlagsKHR::OPAQUE,
+            composite_alpha: support.choose_composite_alpha(config.transparent),
             present_mode,
             clipped: vk::TRUE,
-            old_swapchain: vk::SwapchainKHR::null(),
+            old_swapchain,
             ..Default::default()
         };
 
@@ -125,64 +198,65 @@ impl Swapchain {
         let swapchain = swapchain::Device::new(instance, device);
 
         // Create the swapchain
-        let swapchain_khr = unsafe { swapchain.create_swapchain(&create_info, None).unwrap() };
-
-        // Get the swapchain images
-        let images = unsafe { swapchain.get_swapchain_images(swapchain_khr).unwrap() };
-
-        // Create the image views
-        let image_views = images
-            .iter()
-            .map(|&image| {
-                let create_view_info = vk::ImageViewCreateInfo {
-                    image,
-                    view_type: vk::ImageViewType::TYPE_2D,
-                    format: surface_format.format,
-                    components: vk::ComponentMapping {
-                        r: vk::ComponentSwizzle::IDENTITY,
-                        g: vk::ComponentSwizzle::IDENTITY,
-                        b: vk::ComponentSwizzle::IDENTITY,
-                        a: vk::ComponentSwizzle::IDENTITY,
-                    },
-                    subresource_range: vk::ImageSubresourceRange {
-                        aspect_mask: vk::ImageAspectFlags::COLOR,
-                        base_mip_level: 0,
-                        level_count: 1,
-                        base_array_layer: 0,
-                        layer_count: 1,
-                    },
-                    ..Default::default()
-                };
-
-                unsafe {
-                    device
-                        .create_image_view(&create_view_info, None)
-                        .expect("Failed to create image view")
-                }
-            })
-            .collect();
+        let swapchain_khr = unsafe { swapchain.create_swapchain(&create_info, None)? };
 
-        // Return the swapchain
-        Swapchain {
+        // Destroyed again if a later step fails
+        let mut swapchain = Swapchain {
             swapchain,
             swapchain_khr,
-            images,
-            image_views,
+            images: Vec::new(),
+            image_views: Vec::new(),
             format: surface_format.format,
             extent,
+            device: device.clone(),
+        };
+
+        device.set_object_name(swapchain_khr, "swapchain");
+
+        // Get the swapchain images
+        swapchain.images = unsafe { swapchain.swapchain.get_swapchain_images(swapchain_khr)? };
+
+        // Create the image views
+        for (i, &image) in swapchain.images.iter().enumerate() {
+            device.set_object_name(image, &format!("swapchain image {}", i));
+
+            let create_view_info = vk::ImageViewCreateInfo {
+                image,
+                view_type: vk::ImageViewType::TYPE_2D,
+                format: surface_format.format,
+                components: vk::ComponentMapping {
+                    r: vk::ComponentSwizzle::IDENTITY,
+                    g: vk::ComponentSwizzle::IDENTITY,
+                    b: vk::ComponentSwizzle::IDENTITY,
+                    a: vk::ComponentSwizzle::IDENTITY,
+                },
+                subresource_range: vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                },
+                ..Default::default()
+            };
+
+            let image_view = unsafe { device.create_image_view(&create_view_info, None)? };
+            device.set_object_name(image_view, &format!("swapchain image view {}", i));
+            swapchain.image_views.push(image_view);
         }
+
+        // Return the swapchain
+        Ok(swapchain)
     }
+}
 
-    /// Cleans up the swapchain.
-    ///
-    /// # Arguments
-    ///
-    /// * `device` - The Vulkan device.
-    pub fn cleanup(&self, device: &Device) {
+impl Drop for Swapchain {
+    /// Destroys the image views and the swapchain.
+    fn drop(&mut self) {
         // Destroy the image views
         for &image_view in self.image_views.iter() {
             unsafe {
-                device.destroy_image_view(image_view, None);
+                self.device.destroy_image_view(image_view, None);
             }
         }
 
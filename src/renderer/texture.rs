@@ -0,0 +1,133 @@
+use ash::vk;
+use std::sync::Arc;
+
+use crate::error::Result;
+
+use super::device::{AshDevice, LogicalDevice};
+use super::memory::OwnedImage;
+use super::upload::{ResourceUse, UploadManager};
+
+/// Format of the glyph atlas. It is UNORM so texels are sampled exactly as
+/// stored.
+pub const ATLAS_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+/// The GPU copy of a [`GlyphAtlas`](crate::text::atlas::GlyphAtlas) and the
+/// descriptor set that binds it for the text pipeline.
+pub struct AtlasTexture {
+    pub extent: vk::Extent2D,
+    pub sampler: vk::Sampler,
+    pub descriptor_set: vk::DescriptorSet,
+    descriptor_pool: vk::DescriptorPool,
+    /// Dropped after the descriptor pool whose set refers to it.
+    pub image: OwnedImage,
+    device: Arc<LogicalDevice>,
+}
+
+impl AtlasTexture {
+    pub fn new(
+        device: &AshDevice,
+        extent: vk::Extent2D,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+    ) -> Result<Self> {
+        let image = OwnedImage::new(
+            device,
+            extent,
+            ATLAS_FORMAT,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+            vk::SampleCountFlags::TYPE_1,
+            vk::ImageAspectFlags::COLOR,
+        )?;
+        image.set_name("glyph atlas");
+        let device = &device.device;
+        // Destroys what has been created if a later step fails.
+        let mut texture = AtlasTexture {
+            extent,
+            sampler: vk::Sampler::null(),
+            descriptor_set: vk::DescriptorSet::null(),
+            descriptor_pool: vk::DescriptorPool::null(),
+            image,
+            device: device.clone(),
+        };
+
+        // Glyph quads are drawn at their rasterized size on whole pixels, so
+        // texels map one to one.
+        let sampler_info = vk::SamplerCreateInfo::default()
+            .mag_filter(vk::Filter::NEAREST)
+            .min_filter(vk::Filter::NEAREST)
+            .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+            .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+            .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE);
+        texture.sampler = unsafe { device.create_sampler(&sampler_info, None)? };
+
+        let pool_sizes = [
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: 1,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLER,
+                descriptor_count: 1,
+            },
+        ];
+        let pool_info = vk::DescriptorPoolCreateInfo::default()
+            .max_sets(1)
+            .pool_sizes(&pool_sizes);
+        texture.descriptor_pool = unsafe { device.create_descriptor_pool(&pool_info, None)? };
+
+        let set_layouts = [descriptor_set_layout];
+        let alloc_info = vk::DescriptorSetAllocateInfo::default()
+            .descriptor_pool(texture.descriptor_pool)
+            .set_layouts(&set_layouts);
+        texture.descriptor_set = unsafe { device.allocate_descriptor_sets(&alloc_info)?[0] };
+
+        let image_info = [vk::DescriptorImageInfo::default()
+            .image_view(texture.image.view)
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)];
+        let sampler_info = [vk::DescriptorImageInfo::default().sampler(texture.sampler)];
+        let writes = [
+            vk::WriteDescriptorSet::default()
+                .dst_set(texture.descriptor_set)
+                .dst_binding(0)
+                .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+                .image_info(&image_info),
+            vk::WriteDescriptorSet::default()
+                .dst_set(texture.descriptor_set)
+                .dst_binding(1)
+                .descriptor_type(vk::DescriptorType::SAMPLER)
+                .image_info(&sampler_info),
+        ];
+        unsafe {
+            device.update_descriptor_sets(&writes, &[]);
+        }
+
+        Ok(texture)
+    }
+
+    /// Queues a copy of `data` (RGBA, four bytes per texel) over the whole
+    /// texture, leaving it ready for sampling once `uploads` is flushed.
+    pub fn upload(
+        &self,
+        device: &AshDevice,
+        uploads: &mut UploadManager,
+        data: &[u8],
+    ) -> Result<()> {
+        uploads.copy_to_image(
+            device,
+            data,
+            self.image.image,
+            self.extent,
+            ResourceUse::SAMPLED,
+        )
+    }
+}
+
+impl Drop for AtlasTexture {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+            self.device.destroy_sampler(self.sampler, None);
+        }
+    }
+}
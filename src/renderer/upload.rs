@@ -0,0 +1,844 @@
+use ash::vk;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::command::CommandPool;
+use super::device::{AshDevice, LogicalDevice};
+use super::memory::OwnedBuffer;
+use crate::error::Result;
+
+/// Size of the staging ring a renderer creates, which fits a full glyph
+/// atlas upload twice over.
+pub const STAGING_RING_SIZE: vk::DeviceSize = 8 * 1024 * 1024;
+
+/// Alignment of staged data, enough for buffer to image copies of any
+/// format up to 16 bytes per texel.
+const STAGING_ALIGNMENT: vk::DeviceSize = 16;
+
+/// How an uploaded resource is used once ready. Its copy is made visible to
+/// `access` at `stage`, and images are left in `layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceUse {
+    pub stage: vk::PipelineStageFlags,
+    pub access: vk::AccessFlags,
+    /// Ignored for buffers.
+    pub layout: vk::ImageLayout,
+}
+
+impl ResourceUse {
+    pub const VERTEX_BUFFER: ResourceUse = ResourceUse {
+        stage: vk::PipelineStageFlags::VERTEX_INPUT,
+        access: vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+        layout: vk::ImageLayout::UNDEFINED,
+    };
+    pub const INDEX_BUFFER: ResourceUse = ResourceUse {
+        stage: vk::PipelineStageFlags::VERTEX_INPUT,
+        access: vk::AccessFlags::INDEX_READ,
+        layout: vk::ImageLayout::UNDEFINED,
+    };
+    pub const UNIFORM_BUFFER: ResourceUse = ResourceUse {
+        stage: vk::PipelineStageFlags::from_raw(
+            vk::PipelineStageFlags::VERTEX_SHADER.as_raw()
+                | vk::PipelineStageFlags::FRAGMENT_SHADER.as_raw(),
+        ),
+        access: vk::AccessFlags::UNIFORM_READ,
+        layout: vk::ImageLayout::UNDEFINED,
+    };
+    /// An image sampled in fragment shaders.
+    pub const SAMPLED: ResourceUse = ResourceUse {
+        stage: vk::PipelineStageFlags::FRAGMENT_SHADER,
+        access: vk::AccessFlags::SHADER_READ,
+        layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    };
+}
+
+/// A flushed batch of uploads, returned by [`UploadManager::flush`].
+///
+/// Work submitted to the graphics queue after the flush already sees the
+/// uploaded data. Anything else should wait for the future first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UploadFuture {
+    serial: u64,
+}
+
+/// Copies data into buffers and images through host-visible staging memory.
+///
+/// Copies are batched until [`flush`](Self::flush) submits them. They run
+/// on the device's transfer-only queue if it has one, in which case
+/// ownership of each resource is released to the graphics queue family
+/// afterwards; otherwise they run on the graphics queue.
+///
+/// Data is staged in a ring that is reused as batches complete. Data that
+/// does not fit gets a staging buffer of its own for its batch.
+pub struct UploadManager {
+    staging: OwnedBuffer,
+    /// Which bytes of `staging` are in use.
+    ring: StagingRing,
+    pending: Vec<PendingCopy>,
+    /// Ring bytes used by the pending copies, padding included.
+    pending_consumed: vk::DeviceSize,
+    pending_buffers: Vec<OwnedBuffer>,
+    /// Oldest first.
+    in_flight: VecDeque<Submission>,
+    /// Completed submissions whose objects can be reused.
+    free: Vec<Submission>,
+    next_serial: u64,
+    completed_serial: u64,
+    transfer_family: u32,
+    graphics_family: u32,
+    transfer_queue: vk::Queue,
+    graphics_queue: vk::Queue,
+    /// Records the copies, on the transfer family.
+    transfer_pool: CommandPool,
+    /// Records ownership acquires, if the transfer family is separate.
+    graphics_pool: Option<CommandPool>,
+    device: Arc<LogicalDevice>,
+}
+
+enum PendingCopy {
+    Buffer {
+        src: vk::Buffer,
+        src_offset: vk::DeviceSize,
+        dst: vk::Buffer,
+        dst_offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+        usage: ResourceUse,
+    },
+    Image {
+        src: vk::Buffer,
+        src_offset: vk::DeviceSize,
+        dst: vk::Image,
+        extent: vk::Extent2D,
+        usage: ResourceUse,
+    },
+}
+
+/// The objects of a submitted batch.
+struct Submission {
+    serial: u64,
+    transfer_commands: vk::CommandBuffer,
+    /// Null unless the transfer family is separate.
+    acquire_commands: vk::CommandBuffer,
+    /// Signalled when the batch has completed.
+    fence: vk::Fence,
+    /// Orders the copies after graphics work submitted before them. Null
+    /// unless the transfer family is separate.
+    graphics_done: vk::Semaphore,
+    /// Orders the acquire after the copies. Null unless the transfer family
+    /// is separate.
+    copies_done: vk::Semaphore,
+    ring_consumed: vk::DeviceSize,
+    staging_buffers: Vec<OwnedBuffer>,
+}
+
+impl UploadManager {
+    pub fn new(device: &AshDevice, staging_size: vk::DeviceSize) -> Result<Self> {
+        let indices = &device.queue_family_indices;
+        let graphics_family = indices.graphics_family.unwrap();
+        let transfer_family = indices.transfer_or_graphics();
+
+        let buffer = OwnedBuffer::new(
+            device,
+            staging_size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        buffer.set_name("staging ring");
+        let transfer_pool = CommandPool::new(&device.device, transfer_family)?;
+        let graphics_pool = if transfer_family != graphics_family {
+            Some(CommandPool::new(&device.device, graphics_family)?)
+        } else {
+            None
+        };
+        log::debug!(
+            "uploading on queue family {} for graphics family {}",
+            transfer_family,
+            graphics_family
+        );
+
+        Ok(UploadManager {
+            ring: StagingRing::new(buffer.size),
+            staging: buffer,
+            pending: Vec::new(),
+            pending_consumed: 0,
+            pending_buffers: Vec::new(),
+            in_flight: VecDeque::new(),
+            free: Vec::new(),
+            next_serial: 1,
+            completed_serial: 0,
+            transfer_family,
+            graphics_family,
+            transfer_queue: device.transfer_queue,
+            graphics_queue: device.graphics_queue,
+            transfer_pool,
+            graphics_pool,
+            device: device.device.clone(),
+        })
+    }
+
+    /// Whether uploads run on a queue family of their own.
+    pub fn has_transfer_queue(&self) -> bool {
+        self.graphics_pool.is_some()
+    }
+
+    /// Whether there are copies waiting for [`flush`](Self::flush).
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Queues a copy of `data` to `offset` in `dst`, which needs
+    /// `TRANSFER_DST` usage. Nothing may use the range until the copy is
+    /// flushed.
+    pub fn copy_to_buffer(
+        &mut self,
+        device: &AshDevice,
+        data: &[u8],
+        dst: vk::Buffer,
+        offset: vk::DeviceSize,
+        usage: ResourceUse,
+    ) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let (src, src_offset) = self.stage(device, data)?;
+        self.pending.push(PendingCopy::Buffer {
+            src,
+            src_offset,
+            dst,
+            dst_offset: offset,
+            size: data.len() as vk::DeviceSize,
+            usage,
+        });
+        Ok(())
+    }
+
+    /// Queues a copy of `data`, tightly packed texels, over the whole of the
+    /// first mip level of the colour image `dst`, which needs `TRANSFER_DST`
+    /// usage. The previous contents are discarded.
+    pub fn copy_to_image(
+        &mut self,
+        device: &AshDevice,
+        data: &[u8],
+        dst: vk::Image,
+        extent: vk::Extent2D,
+        usage: ResourceUse,
+    ) -> Result<()> {
+        let (src, src_offset) = self.stage(device, data)?;
+        self.pending.push(PendingCopy::Image {
+            src,
+            src_offset,
+            dst,
+            extent,
+            usage,
+        });
+        Ok(())
+    }
+
+    /// Submits the queued copies. With none queued, returns the future of
+    /// the last batch.
+    pub fn flush(&mut self) -> Result<UploadFuture> {
+        self.retire()?;
+        if self.pending.is_empty() {
+            return Ok(UploadFuture {
+                serial: self.next_serial - 1,
+            });
+        }
+
+        let mut submission = match self.free.pop() {
+            Some(submission) => submission,
+            None => self.create_submission()?,
+        };
+        submission.serial = self.next_serial;
+        submission.ring_consumed = std::mem::take(&mut self.pending_consumed);
+        submission.staging_buffers = std::mem::take(&mut self.pending_buffers);
+        let copies = std::mem::take(&mut self.pending);
+
+        let result = unsafe { self.record(&submission, &copies) }
+            .and_then(|_| unsafe { self.submit(&submission, &copies) });
+        if let Err(err) = result {
+            let consumed = submission.ring_consumed;
+            self.discard(submission);
+            // Everything in flight has completed too, and the ring is
+            // released oldest first.
+            let _ = self.retire();
+            self.ring.release(consumed);
+            return Err(err);
+        }
+
+        self.next_serial += 1;
+        let future = UploadFuture {
+            serial: submission.serial,
+        };
+        self.in_flight.push_back(submission);
+        Ok(future)
+    }
+
+    /// Whether the uploads of `future` have completed.
+    pub fn is_ready(&mut self, future: UploadFuture) -> Result<bool> {
+        self.retire()?;
+        Ok(future.serial <= self.completed_serial)
+    }
+
+    /// Blocks until the uploads of `future` have completed.
+    pub fn wait(&mut self, future: UploadFuture) -> Result<()> {
+        while future.serial > self.completed_serial {
+            if !self.wait_oldest()? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// The fence signalled when the uploads of `future` complete, to wait on
+    /// alongside other fences. `None` once they are known to be complete, as
+    /// the fence is then reused.
+    pub fn fence(&self, future: UploadFuture) -> Option<vk::Fence> {
+        self.in_flight
+            .iter()
+            .find(|submission| submission.serial == future.serial)
+            .map(|submission| submission.fence)
+    }
+
+    /// Copies `data` into staging memory, returning the buffer and offset it
+    /// is at.
+    fn stage(&mut self, device: &AshDevice, data: &[u8]) -> Result<(vk::Buffer, vk::DeviceSize)> {
+        let size = data.len() as vk::DeviceSize;
+        self.retire()?;
+        loop {
+            if let Some((offset, consumed)) = self.ring.reserve(size) {
+                self.staging.write_at(offset, data)?;
+                self.pending_consumed += consumed;
+                return Ok((self.staging.buffer, offset));
+            }
+            if !self.wait_oldest()? {
+                break;
+            }
+        }
+
+        log::debug!("staging {} bytes outside the staging ring", size);
+        let buffer = OwnedBuffer::new(
+            device,
+            size.max(1),
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        buffer.set_name("staging buffer");
+        buffer.write(data)?;
+        let handle = buffer.buffer;
+        self.pending_buffers.push(buffer);
+        Ok((handle, 0))
+    }
+
+    /// Recycles the submissions that have completed, in order.
+    fn retire(&mut self) -> Result<()> {
+        while let Some(submission) = self.in_flight.front() {
+            if !unsafe { self.device.get_fence_status(submission.fence)? } {
+                break;
+            }
+            let submission = self.in_flight.pop_front().unwrap();
+            self.recycle(submission)?;
+        }
+        Ok(())
+    }
+
+    /// Waits for the oldest submission and recycles it. Returns false if
+    /// nothing is in flight.
+    fn wait_oldest(&mut self) -> Result<bool> {
+        let Some(submission) = self.in_flight.pop_front() else {
+            return Ok(false);
+        };
+        let waited = unsafe {
+            self.device
+                .wait_for_fences(&[submission.fence], true, u64::MAX)
+        };
+        if let Err(err) = waited {
+            self.in_flight.push_front(submission);
+            return Err(err.into());
+        }
+        self.recycle(submission)?;
+        Ok(true)
+    }
+
+    fn recycle(&mut self, mut submission: Submission) -> Result<()> {
+        self.completed_serial = submission.serial;
+        self.ring.release(submission.ring_consumed);
+        submission.ring_consumed = 0;
+        submission.staging_buffers.clear();
+        unsafe { self.device.reset_fences(&[submission.fence])? };
+        self.free.push(submission);
+        Ok(())
+    }
+
+    fn create_submission(&self) -> Result<Submission> {
+        let allocate = |pool: &CommandPool| {
+            let alloc_info = vk::CommandBufferAllocateInfo::default()
+                .command_pool(pool.pool)
+                .level(vk::CommandBufferLevel::PRIMARY)
+                .command_buffer_count(1);
+            unsafe { self.device.allocate_command_buffers(&alloc_info) }
+        };
+
+        let mut submission = Submission {
+            serial: 0,
+            transfer_commands: allocate(&self.transfer_pool)?[0],
+            acquire_commands: vk::CommandBuffer::null(),
+            fence: vk::Fence::null(),
+            graphics_done: vk::Semaphore::null(),
+            copies_done: vk::Semaphore::null(),
+            ring_consumed: 0,
+            staging_buffers: Vec::new(),
+        };
+        // Destroys what has been created if a later step fails.
+        let created = (|| unsafe {
+            submission.fence = self
+                .device
+                .create_fence(&vk::FenceCreateInfo::default(), None)?;
+            if let Some(graphics_pool) = &self.graphics_pool {
+                submission.acquire_commands = allocate(graphics_pool)?[0];
+                let semaphore_info = vk::SemaphoreCreateInfo::default();
+                submission.graphics_done = self.device.create_semaphore(&semaphore_info, None)?;
+                submission.copies_done = self.device.create_semaphore(&semaphore_info, None)?;
+            }
+            Ok::<_, vk::Result>(())
+        })();
+        if let Err(err) = created {
+            self.destroy(submission);
+            return Err(err.into());
+        }
+        Ok(submission)
+    }
+
+    /// Records the copies of `submission`, with barriers that order them
+    /// after earlier use of their destinations and make them visible to
+    /// their next use.
+    unsafe fn record(&self, submission: &Submission, copies: &[PendingCopy]) -> Result<()> {
+        let device = &self.device;
+        let separate = self.graphics_pool.is_some();
+        let begin_info = vk::CommandBufferBeginInfo::default()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        // Without a separate family, copies wait for earlier work using
+        // their destinations. With one, the graphics_done semaphore has
+        // already waited, at the transfer stage.
+        let previous_stage = |usage: &ResourceUse| {
+            if separate {
+                vk::PipelineStageFlags::TRANSFER
+            } else {
+                usage.stage
+            }
+        };
+
+        let cb = submission.transfer_commands;
+        device.begin_command_buffer(cb, &begin_info)?;
+        for copy in copies {
+            match *copy {
+                PendingCopy::Buffer {
+                    src,
+                    src_offset,
+                    dst,
+                    dst_offset,
+                    size,
+                    usage,
+                } => {
+                    if !separate {
+                        device.cmd_pipeline_barrier(
+                            cb,
+                            usage.stage,
+                            vk::PipelineStageFlags::TRANSFER,
+                            vk::DependencyFlags::empty(),
+                            &[],
+                            &[],
+                            &[],
+                        );
+                    }
+                    let region = vk::BufferCopy {
+                        src_offset,
+                        dst_offset,
+                        size,
+                    };
+                    device.cmd_copy_buffer(cb, src, dst, &[region]);
+                }
+                PendingCopy::Image {
+                    src,
+                    src_offset,
+                    dst,
+                    extent,
+                    usage,
+                } => {
+                    let to_transfer = vk::ImageMemoryBarrier::default()
+                        .src_access_mask(vk::AccessFlags::empty())
+                        .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                        .old_layout(vk::ImageLayout::UNDEFINED)
+                        .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                        .image(dst)
+                        .subresource_range(COLOR_RANGE);
+                    device.cmd_pipeline_barrier(
+                        cb,
+                        previous_stage(&usage),
+                        vk::PipelineStageFlags::TRANSFER,
+                        vk::DependencyFlags::empty(),
+                        &[],
+                        &[],
+                        &[to_transfer],
+                    );
+                    let region = vk::BufferImageCopy::default()
+                        .buffer_offset(src_offset)
+                        .image_subresource(vk::ImageSubresourceLayers {
+                            aspect_mask: vk::ImageAspectFlags::COLOR,
+                            mip_level: 0,
+                            base_array_layer: 0,
+                            layer_count: 1,
+                        })
+                        .image_extent(vk::Extent3D {
+                            width: extent.width,
+                            height: extent.height,
+                            depth: 1,
+                        });
+                    device.cmd_copy_buffer_to_image(
+                        cb,
+                        src,
+                        dst,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                        &[region],
+                    );
+                }
+            }
+        }
+
+        if separate {
+            // Released here and acquired by the graphics family, with the
+            // same barriers on both sides.
+            let (buffer_barriers, image_barriers) =
+                self.ownership_barriers(copies, vk::AccessFlags::TRANSFER_WRITE, false);
+            device.cmd_pipeline_barrier(
+                cb,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &buffer_barriers,
+                &image_barriers,
+            );
+            device.end_command_buffer(cb)?;
+
+            let cb = submission.acquire_commands;
+            device.begin_command_buffer(cb, &begin_info)?;
+            for copy in copies {
+                let (buffer_barriers, image_barriers) = self.ownership_barriers(
+                    std::slice::from_ref(copy),
+                    vk::AccessFlags::empty(),
+                    true,
+                );
+                let stage = copy.usage().stage;
+                device.cmd_pipeline_barrier(
+                    cb,
+                    stage,
+                    stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &buffer_barriers,
+                    &image_barriers,
+                );
+            }
+            device.end_command_buffer(cb)?;
+        } else {
+            for copy in copies {
+                let (buffer_barriers, image_barriers) = self.ownership_barriers(
+                    std::slice::from_ref(copy),
+                    vk::AccessFlags::TRANSFER_WRITE,
+                    true,
+                );
+                device.cmd_pipeline_barrier(
+                    cb,
+                    vk::PipelineStageFlags::TRANSFER,
+                    copy.usage().stage,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &buffer_barriers,
+                    &image_barriers,
+                );
+            }
+            device.end_command_buffer(cb)?;
+        }
+        Ok(())
+    }
+
+    /// Barriers that take the destinations of `copies` from the transfer
+    /// layout to their next use, transferring ownership to the graphics
+    /// family if it is separate. Destination accesses are only included
+    /// when `visible`, as a release makes nothing visible.
+    fn ownership_barriers(
+        &self,
+        copies: &[PendingCopy],
+        src_access: vk::AccessFlags,
+        visible: bool,
+    ) -> (
+        Vec<vk::BufferMemoryBarrier<'static>>,
+        Vec<vk::ImageMemoryBarrier<'static>>,
+    ) {
+        let (src_family, dst_family) = if self.graphics_pool.is_some() {
+            (self.transfer_family, self.graphics_family)
+        } else {
+            (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED)
+        };
+        let dst_access = |usage: &ResourceUse| {
+            if visible {
+                usage.access
+            } else {
+                vk::AccessFlags::empty()
+            }
+        };
+
+        let mut buffer_barriers = Vec::new();
+        let mut image_barriers = Vec::new();
+        for copy in copies {
+            match *copy {
+                PendingCopy::Buffer {
+                    dst,
+                    dst_offset,
+                    size,
+                    usage,
+                    ..
+                } => buffer_barriers.push(
+                    vk::BufferMemoryBarrier::default()
+                        .src_access_mask(src_access)
+                        .dst_access_mask(dst_access(&usage))
+                        .src_queue_family_index(src_family)
+                        .dst_queue_family_index(dst_family)
+                        .buffer(dst)
+                        .offset(dst_offset)
+                        .size(size),
+                ),
+                PendingCopy::Image { dst, usage, .. } => image_barriers.push(
+                    vk::ImageMemoryBarrier::default()
+                        .src_access_mask(src_access)
+                        .dst_access_mask(dst_access(&usage))
+                        .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+                        .new_layout(usage.layout)
+                        .src_queue_family_index(src_family)
+                        .dst_queue_family_index(dst_family)
+                        .image(dst)
+                        .subresource_range(COLOR_RANGE),
+                ),
+            }
+        }
+        (buffer_barriers, image_barriers)
+    }
+
+    unsafe fn submit(&self, submission: &Submission, copies: &[PendingCopy]) -> Result<()> {
+        let device = &self.device;
+        let transfer_commands = [submission.transfer_commands];
+        if self.graphics_pool.is_none() {
+            let submit_info = vk::SubmitInfo::default().command_buffers(&transfer_commands);
+            device.queue_submit(self.graphics_queue, &[submit_info], submission.fence)?;
+            return Ok(());
+        }
+
+        // Graphics work submitted before the flush may still use the
+        // destinations, so the copies wait for it.
+        let graphics_done = [submission.graphics_done];
+        let signal_info = vk::SubmitInfo::default().signal_semaphores(&graphics_done);
+        device.queue_submit(self.graphics_queue, &[signal_info], vk::Fence::null())?;
+
+        let copies_done = [submission.copies_done];
+        let transfer_stages = [vk::PipelineStageFlags::TRANSFER];
+        let transfer_info = vk::SubmitInfo::default()
+            .wait_semaphores(&graphics_done)
+            .wait_dst_stage_mask(&transfer_stages)
+            .command_buffers(&transfer_commands)
+            .signal_semaphores(&copies_done);
+        device.queue_submit(self.transfer_queue, &[transfer_info], vk::Fence::null())?;
+
+        let acquire_stages = [copies
+            .iter()
+            .fold(vk::PipelineStageFlags::empty(), |stages, copy| {
+                stages | copy.usage().stage
+            })];
+        let acquire_commands = [submission.acquire_commands];
+        let acquire_info = vk::SubmitInfo::default()
+            .wait_semaphores(&copies_done)
+            .wait_dst_stage_mask(&acquire_stages)
+            .command_buffers(&acquire_commands);
+        device.queue_submit(self.graphics_queue, &[acquire_info], submission.fence)?;
+        Ok(())
+    }
+
+    /// Destroys a submission that failed part way, once nothing can be
+    /// using its objects.
+    fn discard(&self, submission: Submission) {
+        unsafe {
+            let _ = self.device.device_wait_idle();
+        }
+        self.destroy(submission);
+    }
+
+    fn destroy(&self, submission: Submission) {
+        unsafe {
+            self.device
+                .free_command_buffers(self.transfer_pool.pool, &[submission.transfer_commands]);
+            if let Some(graphics_pool) = &self.graphics_pool {
+                if submission.acquire_commands != vk::CommandBuffer::null() {
+                    self.device
+                        .free_command_buffers(graphics_pool.pool, &[submission.acquire_commands]);
+                }
+            }
+            self.device.destroy_fence(submission.fence, None);
+            self.device
+                .destroy_semaphore(submission.graphics_done, None);
+            self.device.destroy_semaphore(submission.copies_done, None);
+        }
+    }
+}
+
+/// Waits for uploads in flight before their staging memory is freed.
+impl Drop for UploadManager {
+    fn drop(&mut self) {
+        let fences: Vec<_> = self
+            .in_flight
+            .iter()
+            .map(|submission| submission.fence)
+            .collect();
+        if !fences.is_empty() {
+            // A lost device has nothing left to wait for.
+            unsafe {
+                let _ = self.device.wait_for_fences(&fences, true, u64::MAX);
+            }
+        }
+        let submissions: Vec<_> = self
+            .in_flight
+            .drain(..)
+            .chain(self.free.drain(..))
+            .collect();
+        for submission in submissions {
+            self.destroy(submission);
+        }
+    }
+}
+
+impl PendingCopy {
+    fn usage(&self) -> ResourceUse {
+        match *self {
+            PendingCopy::Buffer { usage, .. } | PendingCopy::Image { usage, .. } => usage,
+        }
+    }
+}
+
+const COLOR_RANGE: vk::ImageSubresourceRange = vk::ImageSubresourceRange {
+    aspect_mask: vk::ImageAspectFlags::COLOR,
+    base_mip_level: 0,
+    level_count: 1,
+    base_array_layer: 0,
+    layer_count: 1,
+};
+
+/// Tracks the space in use in a staging buffer used as a ring. Space is
+/// reserved at the head and released from the tail, in the order it was
+/// reserved.
+struct StagingRing {
+    capacity: vk::DeviceSize,
+    head: vk::DeviceSize,
+    /// Bytes reserved and not yet released, padding included.
+    used: vk::DeviceSize,
+}
+
+impl StagingRing {
+    fn new(capacity: vk::DeviceSize) -> Self {
+        StagingRing {
+            capacity,
+            head: 0,
+            used: 0,
+        }
+    }
+
+    /// Reserves `size` aligned bytes, returning their offset and how many
+    /// bytes the reservation used, counting padding and any skipped space at
+    /// the end of the ring.
+    fn reserve(&mut self, size: vk::DeviceSize) -> Option<(vk::DeviceSize, vk::DeviceSize)> {
+        let capacity = self.capacity;
+        let free = capacity - self.used;
+        let offset = self.head.next_multiple_of(STAGING_ALIGNMENT);
+        let (offset, consumed) = if offset + size <= capacity {
+            (offset, offset - self.head + size)
+        } else {
+            // Wraps around to the start.
+            (0, capacity - self.head + size)
+        };
+        if consumed > free {
+            return None;
+        }
+        self.head = offset + size;
+        self.used += consumed;
+        Some((offset, consumed))
+    }
+
+    fn release(&mut self, consumed: vk::DeviceSize) {
+        self.used -= consumed;
+        if self.used == 0 {
+            self.head = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reservations_are_aligned() {
+        let mut ring = StagingRing::new(64);
+        assert_eq!(ring.reserve(10), Some((0, 10)));
+        // Padding up to the alignment counts as used.
+        assert_eq!(ring.reserve(4), Some((16, 10)));
+        assert_eq!(ring.used, 20);
+    }
+
+    #[test]
+    fn wraps_around_when_the_end_is_too_small() {
+        let mut ring = StagingRing::new(64);
+        assert_eq!(ring.reserve(32), Some((0, 32)));
+        assert_eq!(ring.reserve(16), Some((32, 16)));
+        ring.release(32);
+
+        // The 16 bytes left at the end are skipped and count as used.
+        assert_eq!(ring.reserve(20), Some((0, 36)));
+        assert_eq!(ring.used, 52);
+
+        ring.release(16);
+        ring.release(36);
+        assert_eq!(ring.used, 0);
+        assert_eq!(ring.reserve(64), Some((0, 64)));
+    }
+
+    #[test]
+    fn full_ring_refuses_until_released() {
+        let mut ring = StagingRing::new(64);
+        assert_eq!(ring.reserve(64), Some((0, 64)));
+        assert_eq!(ring.reserve(1), None);
+        ring.release(64);
+        assert_eq!(ring.reserve(1), Some((0, 1)));
+    }
+
+    #[test]
+    fn wrapping_never_overwrites_unreleased_space() {
+        let mut ring = StagingRing::new(64);
+        ring.reserve(32);
+        ring.reserve(16);
+        ring.release(32);
+
+        // Bytes 32..48 are still in use, so 40 bytes fit neither after
+        // them nor before them.
+        assert_eq!(ring.reserve(40), None);
+        assert_eq!(ring.used, 16);
+        assert_eq!(ring.reserve(32), Some((0, 48)));
+    }
+
+    #[test]
+    fn oversized_data_never_fits() {
+        // Such data gets a staging buffer of its own instead.
+        let mut ring = StagingRing::new(64);
+        assert_eq!(ring.reserve(65), None);
+        assert_eq!(ring.used, 0);
+        assert_eq!(ring.reserve(64), Some((0, 64)));
+    }
+}
@@ -0,0 +1,177 @@
+use ash::vk;
+use bytemuck::Pod;
+
+pub use uplift_derive::VertexInput;
+
+/// Where one attribute lies in a vertex and how it is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VertexAttribute {
+    pub offset: u32,
+    pub format: vk::Format,
+}
+
+/// A vertex type pipelines can read, with one attribute per field.
+///
+/// Derive it with `#[derive(VertexInput)]` on a `#[repr(C)]` struct whose
+/// field types implement [`VertexFormat`]:
+///
+/// ```
+/// use bytemuck::{Pod, Zeroable};
+/// use uplift::renderer::vertex::VertexInput;
+///
+/// #[derive(Clone, Copy, Pod, Zeroable, VertexInput)]
+/// #[repr(C)]
+/// struct Vertex {
+///     position: [f32; 2],
+///     color: [u8; 4],
+/// }
+/// ```
+///
+/// Deriving it for anything else fails to compile, such as a struct without
+/// `#[repr(C)]`:
+///
+/// ```compile_fail
+/// # use bytemuck::{Pod, Zeroable};
+/// # use uplift::renderer::vertex::VertexInput;
+/// #[derive(Clone, Copy, VertexInput)]
+/// struct Vertex {
+///     position: [f32; 2],
+/// }
+/// # unsafe impl Zeroable for Vertex {}
+/// # unsafe impl Pod for Vertex {}
+/// ```
+///
+/// a tuple struct:
+///
+/// ```compile_fail
+/// # use bytemuck::{Pod, Zeroable};
+/// # use uplift::renderer::vertex::VertexInput;
+/// #[derive(Clone, Copy, Pod, Zeroable, VertexInput)]
+/// #[repr(C)]
+/// struct Vertex([f32; 2]);
+/// ```
+///
+/// a generic struct:
+///
+/// ```compile_fail
+/// # use bytemuck::{Pod, Zeroable};
+/// # use uplift::renderer::vertex::VertexInput;
+/// #[derive(Clone, Copy, VertexInput)]
+/// #[repr(C)]
+/// struct Vertex<T> {
+///     position: T,
+/// }
+/// # unsafe impl<T: Pod> Zeroable for Vertex<T> {}
+/// # unsafe impl<T: Pod> Pod for Vertex<T> {}
+/// ```
+///
+/// or a field type without a [`VertexFormat`]:
+///
+/// ```compile_fail,E0277
+/// # use bytemuck::{Pod, Zeroable};
+/// # use uplift::renderer::vertex::VertexInput;
+/// #[derive(Clone, Copy, Pod, Zeroable, VertexInput)]
+/// #[repr(C)]
+/// struct Vertex {
+///     position: [f64; 2],
+/// }
+/// ```
+pub trait VertexInput: Pod {
+    /// The attributes at locations 0, 1, ... in order.
+    const ATTRIBUTES: &'static [VertexAttribute];
+
+    /// Describes a buffer of these vertices bound at `binding`.
+    fn binding_description(binding: u32) -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription {
+            binding,
+            stride: std::mem::size_of::<Self>() as u32,
+            input_rate: vk::VertexInputRate::VERTEX,
+        }
+    }
+
+    /// Describes the attributes read from the buffer bound at `binding`.
+    fn attribute_descriptions(binding: u32) -> Vec<vk::VertexInputAttributeDescription> {
+        Self::ATTRIBUTES
+            .iter()
+            .enumerate()
+            .map(
+                |(location, attribute)| vk::VertexInputAttributeDescription {
+                    location: location as u32,
+                    binding,
+                    format: attribute.format,
+                    offset: attribute.offset,
+                },
+            )
+            .collect()
+    }
+}
+
+/// A type a vertex attribute can have, and the format it is read as.
+pub trait VertexFormat {
+    const FORMAT: vk::Format;
+}
+
+macro_rules! impl_vertex_format {
+    ($($ty:ty => $format:ident),* $(,)?) => {
+        $(
+            impl VertexFormat for $ty {
+                const FORMAT: vk::Format = vk::Format::$format;
+            }
+        )*
+    };
+}
+
+impl_vertex_format! {
+    f32 => R32_SFLOAT,
+    [f32; 2] => R32G32_SFLOAT,
+    [f32; 3] => R32G32B32_SFLOAT,
+    [f32; 4] => R32G32B32A32_SFLOAT,
+    u32 => R32_UINT,
+    [u32; 2] => R32G32_UINT,
+    [u32; 3] => R32G32B32_UINT,
+    [u32; 4] => R32G32B32A32_UINT,
+    i32 => R32_SINT,
+    [i32; 2] => R32G32_SINT,
+    [i32; 3] => R32G32B32_SINT,
+    [i32; 4] => R32G32B32A32_SINT,
+    // Bytes are read as normalized colors.
+    [u8; 4] => R8G8B8A8_UNORM,
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::{Pod, Zeroable};
+
+    use super::*;
+
+    #[derive(Clone, Copy, Pod, Zeroable, VertexInput)]
+    #[repr(C)]
+    struct Sample {
+        position: [f32; 3],
+        color: [u8; 4],
+        uv: [f32; 2],
+        layer: u32,
+    }
+
+    #[test]
+    fn derived_attributes_follow_the_layout() {
+        let attributes: Vec<_> = Sample::attribute_descriptions(1)
+            .iter()
+            .map(|a| (a.location, a.binding, a.format, a.offset))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                (0, 1, vk::Format::R32G32B32_SFLOAT, 0),
+                (1, 1, vk::Format::R8G8B8A8_UNORM, 12),
+                (2, 1, vk::Format::R32G32_SFLOAT, 16),
+                (3, 1, vk::Format::R32_UINT, 24),
+            ]
+        );
+
+        let binding = Sample::binding_description(1);
+        assert_eq!(binding.binding, 1);
+        assert_eq!(binding.stride, 28);
+        assert_eq!(binding.input_rate, vk::VertexInputRate::VERTEX);
+    }
+}
@@ -0,0 +1,452 @@
+use ash::vk;
+use bytemuck::{Pod, Zeroable};
+
+use std::ops::Range;
+
+use crate::text::atlas::{AtlasEntry, GlyphAtlas};
+use crate::text::raster::place_glyphs;
+use crate::text::shaping::ShapedText;
+
+use super::canvas::Canvas;
+use super::device::LogicalDevice;
+use super::pipeline::Pipeline;
+use super::primitive::{Color, Glyph, Image, Rect};
+use super::text_pipeline::TextPipeline;
+use super::texture::AtlasTexture;
+use super::vertex::VertexInput;
+
+/// Vertex layout consumed by `shader.vert`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, VertexInput)]
+pub struct Vertex {
+    pub position: [f32; 2],
+    /// Straight-alpha RGBA.
+    pub color: [f32; 4],
+}
+
+/// Vertex layout consumed by `text.vert`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable, VertexInput)]
+pub struct TextVertex {
+    pub position: [f32; 2],
+    pub tex_coord: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// What the canvas needs to draw text.
+pub struct TextResources<'a> {
+    pub pipeline: &'a TextPipeline,
+    pub texture: &'a AtlasTexture,
+    pub atlas: &'a mut GlyphAtlas,
+}
+
+/// A run of consecutive vertices drawn with one pipeline, or a debug label
+/// around such runs.
+enum Batch {
+    Shapes(Range<u32>),
+    Text(Range<u32>),
+    BeginLabel(String),
+    EndLabel,
+}
+
+/// A [`Canvas`] that batches geometry for the Vulkan pipeline.
+///
+/// Shapes are converted to clip-space triangles as they are drawn and
+/// issued by [`VulkanCanvas::finish`], one draw call per run of shapes or
+/// text so painter's order is kept. Clipping is applied on the CPU, so no
+/// dynamic scissor state is needed.
+///
+/// Text, glyph runs and images are only drawn when [`TextResources`] are
+/// supplied with [`VulkanCanvas::with_text`]. They are all packed into the
+/// glyph atlas while painting and drawn with the text pipeline; upload the
+/// atlas before submitting if [`GlyphAtlas::take_dirty`] says it changed.
+/// If the atlas filled up, see [`VulkanCanvas::atlas_overflowed`].
+///
+/// Labels are only recorded when the device has debug utils, since they
+/// split batches.
+pub struct VulkanCanvas<'a> {
+    device: &'a LogicalDevice,
+    command_buffer: vk::CommandBuffer,
+    pipeline: &'a Pipeline,
+    extent: vk::Extent2D,
+    vertices: Vec<Vertex>,
+    text_vertices: Vec<TextVertex>,
+    batches: Vec<Batch>,
+    text: Option<TextResources<'a>>,
+    clip_stack: Vec<Rect>,
+}
+
+impl<'a> VulkanCanvas<'a> {
+    pub fn new(
+        device: &'a LogicalDevice,
+        command_buffer: vk::CommandBuffer,
+        pipeline: &'a Pipeline,
+        extent: vk::Extent2D,
+    ) -> Self {
+        VulkanCanvas {
+            device,
+            command_buffer,
+            pipeline,
+            extent,
+            vertices: Vec::new(),
+            text_vertices: Vec::new(),
+            batches: Vec::new(),
+            text: None,
+            clip_stack: Vec::new(),
+        }
+    }
+
+    pub fn with_text(mut self, text: TextResources<'a>) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Whether the glyph atlas ran out of space while painting, leaving
+    /// glyphs or images out. Call [`Self::reset_and_clear_atlas`] and paint
+    /// again.
+    pub fn atlas_overflowed(&self) -> bool {
+        self.text
+            .as_ref()
+            .is_some_and(|text| text.atlas.overflowed())
+    }
+
+    /// Discards everything painted so far and empties the glyph atlas, so a
+    /// frame that overflowed it can be painted again from scratch. Only
+    /// call this before the atlas is uploaded for the frame.
+    pub fn reset_and_clear_atlas(&mut self) {
+        self.vertices.clear();
+        self.text_vertices.clear();
+        self.batches.clear();
+        self.clip_stack.clear();
+        if let Some(text) = self.text.as_mut() {
+            text.atlas.clear();
+        }
+    }
+
+    /// The vertices batched so far, to be uploaded before [`Self::finish`].
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// The glyph vertices batched so far, to be uploaded before
+    /// [`Self::finish`].
+    pub fn text_vertices(&self) -> &[TextVertex] {
+        &self.text_vertices
+    }
+
+    /// Records the draws for every batch, reading shape vertices from
+    /// `vertex_buffer` and glyph vertices from `text_vertex_buffer`. The
+    /// viewport and scissor are set to cover the whole extent.
+    pub fn finish(self, vertex_buffer: vk::Buffer, text_vertex_buffer: vk::Buffer) {
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: self.extent.width as f32,
+            height: self.extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        let scissor = vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: self.extent,
+        };
+        unsafe {
+            self.device
+                .cmd_set_viewport(self.command_buffer, 0, &[viewport]);
+            self.device
+                .cmd_set_scissor(self.command_buffer, 0, &[scissor]);
+        }
+
+        for batch in &self.batches {
+            unsafe {
+                match batch {
+                    Batch::Shapes(range) => {
+                        self.device.cmd_bind_pipeline(
+                            self.command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            self.pipeline.graphics_pipeline,
+                        );
+                        self.device.cmd_bind_vertex_buffers(
+                            self.command_buffer,
+                            0,
+                            &[vertex_buffer],
+                            &[0],
+                        );
+                        self.device.cmd_draw(
+                            self.command_buffer,
+                            range.len() as u32,
+                            1,
+                            range.start,
+                            0,
+                        );
+                    }
+                    Batch::Text(range) => {
+                        // Text batches are only created with resources.
+                        let text = self.text.as_ref().unwrap();
+                        self.device.cmd_bind_pipeline(
+                            self.command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            text.pipeline.graphics_pipeline,
+                        );
+                        self.device.cmd_bind_descriptor_sets(
+                            self.command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            text.pipeline.pipeline_layout,
+                            0,
+                            &[text.texture.descriptor_set],
+                            &[],
+                        );
+                        self.device.cmd_bind_vertex_buffers(
+                            self.command_buffer,
+                            0,
+                            &[text_vertex_buffer],
+                            &[0],
+                        );
+                        self.device.cmd_draw(
+                            self.command_buffer,
+                            range.len() as u32,
+                            1,
+                            range.start,
+                            0,
+                        );
+                    }
+                    Batch::BeginLabel(name) => {
+                        self.device.cmd_begin_label(self.command_buffer, name);
+                    }
+                    Batch::EndLabel => self.device.cmd_end_label(self.command_buffer),
+                }
+            }
+        }
+    }
+
+    /// Extends the last batch if it is of the same kind, or starts a new one.
+    fn push_batch(&mut self, batch: Batch) {
+        match (self.batches.last_mut(), batch) {
+            (Some(Batch::Shapes(last)), Batch::Shapes(range))
+            | (Some(Batch::Text(last)), Batch::Text(range))
+                if last.end == range.start =>
+            {
+                last.end = range.end;
+            }
+            (_, batch) => self.batches.push(batch),
+        }
+    }
+
+    fn current_clip(&self) -> Rect {
+        self.clip_stack.last().copied().unwrap_or(Rect::new(
+            0.0,
+            0.0,
+            self.extent.width as f32,
+            self.extent.height as f32,
+        ))
+    }
+
+    fn vertex(&self, x: f32, y: f32, color: Color) -> Vertex {
+        Vertex {
+            position: to_clip_space(self.extent, x, y),
+            color: [color.r, color.g, color.b, color.a],
+        }
+    }
+}
+
+impl Canvas for VulkanCanvas<'_> {
+    fn fill_quad(&mut self, rect: Rect, colors: [Color; 4]) {
+        let area = rect.intersect(&self.current_clip());
+        if area.is_empty() {
+            return;
+        }
+
+        // Re-interpolate the corner colors so clipping does not stretch the
+        // gradient across the visible part.
+        let color_at = |x: f32, y: f32| {
+            let u = (x - rect.x) / rect.width;
+            let v = (y - rect.y) / rect.height;
+            colors[0]
+                .lerp(colors[1], u)
+                .lerp(colors[3].lerp(colors[2], u), v)
+        };
+
+        let top_left = self.vertex(area.x, area.y, color_at(area.x, area.y));
+        let top_right = self.vertex(area.right(), area.y, color_at(area.right(), area.y));
+        let bottom_right = self.vertex(
+            area.right(),
+            area.bottom(),
+            color_at(area.right(), area.bottom()),
+        );
+        let bottom_left = self.vertex(area.x, area.bottom(), color_at(area.x, area.bottom()));
+
+        let start = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&[
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_right,
+            bottom_left,
+            top_left,
+        ]);
+        self.push_batch(Batch::Shapes(start..start + 6));
+    }
+
+    fn draw_glyph_run(&mut self, glyphs: &[Glyph], color: Color) {
+        let clip = self.current_clip();
+        let extent = self.extent;
+        let Some(resources) = self.text.as_mut() else {
+            return;
+        };
+
+        let start = self.text_vertices.len() as u32;
+        for glyph in glyphs {
+            let Some(entry) = resources.atlas.get_mask(glyph.mask) else {
+                continue;
+            };
+            let dest = Rect::new(
+                glyph.x.round(),
+                glyph.y.round(),
+                entry.width as f32,
+                entry.height as f32,
+            );
+            if let Some(quad) = textured_quad(extent, clip, resources.atlas, entry, dest, color) {
+                self.text_vertices.extend_from_slice(&quad);
+            }
+        }
+
+        let end = self.text_vertices.len() as u32;
+        if end > start {
+            self.push_batch(Batch::Text(start..end));
+        }
+    }
+
+    fn draw_text(&mut self, text: &ShapedText, x: f32, y: f32, color: Color) {
+        let clip = self.current_clip();
+        let extent = self.extent;
+        let Some(resources) = self.text.as_mut() else {
+            return;
+        };
+
+        let start = self.text_vertices.len() as u32;
+        for placed in place_glyphs(text, x, y) {
+            let Some(entry) = resources.atlas.get(placed.font, placed.key) else {
+                continue;
+            };
+            let dest = Rect::new(
+                (placed.x + entry.left) as f32,
+                (placed.baseline - entry.top) as f32,
+                entry.width as f32,
+                entry.height as f32,
+            );
+            if let Some(quad) = textured_quad(extent, clip, resources.atlas, entry, dest, color) {
+                self.text_vertices.extend_from_slice(&quad);
+            }
+        }
+
+        let end = self.text_vertices.len() as u32;
+        if end > start {
+            self.push_batch(Batch::Text(start..end));
+        }
+    }
+
+    /// Samples `image` with nearest-neighbour filtering. Images larger than
+    /// the glyph atlas are not drawn.
+    fn draw_image(&mut self, dest: Rect, image: &Image) {
+        let clip = self.current_clip();
+        let extent = self.extent;
+        let Some(resources) = self.text.as_mut() else {
+            return;
+        };
+        let Some(entry) = resources.atlas.get_image(image) else {
+            return;
+        };
+
+        if let Some(quad) = textured_quad(extent, clip, resources.atlas, entry, dest, Color::WHITE)
+        {
+            let start = self.text_vertices.len() as u32;
+            self.text_vertices.extend_from_slice(&quad);
+            self.push_batch(Batch::Text(start..start + 6));
+        }
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        let clip = self.current_clip().intersect(&rect);
+        self.clip_stack.push(clip);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+
+    fn push_label(&mut self, name: &str) {
+        if self.device.has_debug_utils() {
+            self.batches.push(Batch::BeginLabel(name.to_string()));
+        }
+    }
+
+    fn pop_label(&mut self) {
+        if !self.device.has_debug_utils() {
+            return;
+        }
+        // A label around nothing is dropped.
+        if let Some(Batch::BeginLabel(_)) = self.batches.last() {
+            self.batches.pop();
+        } else {
+            self.batches.push(Batch::EndLabel);
+        }
+    }
+}
+
+/// Two triangles drawing the part of `dest` inside `clip`, textured with
+/// the atlas `entry` stretched over `dest`. Color entries are drawn as is
+/// and only take the alpha of `color`.
+fn textured_quad(
+    extent: vk::Extent2D,
+    clip: Rect,
+    atlas: &GlyphAtlas,
+    entry: AtlasEntry,
+    dest: Rect,
+    color: Color,
+) -> Option<[TextVertex; 6]> {
+    let area = dest.intersect(&clip);
+    if area.is_empty() {
+        return None;
+    }
+
+    // Texture coordinates of the visible part of the entry.
+    let (atlas_width, atlas_height) = (atlas.width as f32, atlas.height as f32);
+    let scale_x = entry.width as f32 / dest.width;
+    let scale_y = entry.height as f32 / dest.height;
+    let u0 = (entry.x as f32 + (area.x - dest.x) * scale_x) / atlas_width;
+    let v0 = (entry.y as f32 + (area.y - dest.y) * scale_y) / atlas_height;
+    let u1 = u0 + area.width * scale_x / atlas_width;
+    let v1 = v0 + area.height * scale_y / atlas_height;
+
+    let color = if entry.color {
+        [1.0, 1.0, 1.0, color.a]
+    } else {
+        [color.r, color.g, color.b, color.a]
+    };
+    let vertex = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+        position: to_clip_space(extent, x, y),
+        tex_coord: [u, v],
+        color,
+    };
+    let top_left = vertex(area.x, area.y, u0, v0);
+    let top_right = vertex(area.right(), area.y, u1, v0);
+    let bottom_right = vertex(area.right(), area.bottom(), u1, v1);
+    let bottom_left = vertex(area.x, area.bottom(), u0, v1);
+
+    Some([
+        top_left,
+        top_right,
+        bottom_right,
+        bottom_right,
+        bottom_left,
+        top_left,
+    ])
+}
+
+/// Converts a pixel position to Vulkan clip space, where y points down.
+fn to_clip_space(extent: vk::Extent2D, x: f32, y: f32) -> [f32; 2] {
+    [
+        x / extent.width as f32 * 2.0 - 1.0,
+        y / extent.height as f32 * 2.0 - 1.0,
+    ]
+}
@@ -0,0 +1,188 @@
+use ash::vk;
+
+use super::buffer::{Buffer, BufferKind};
+use super::canvas::Canvas;
+use super::command::CommandPool;
+use super::device::AshDevice;
+use super::frame::FrameScheduler;
+use super::pipeline::Pipeline;
+use super::text_pipeline::TextPipeline;
+use super::texture::AtlasTexture;
+use super::upload::{UploadManager, STAGING_RING_SIZE};
+use super::vulkan_canvas::{TextResources, TextVertex, Vertex, VulkanCanvas};
+use super::window_target::WindowTarget;
+use crate::error::Result;
+use crate::text::atlas::GlyphAtlas;
+
+/// Side length of the glyph atlas texture.
+const ATLAS_SIZE: u32 = 1024;
+
+/// Smallest vertex buffer allocated, so small scenes do not reallocate.
+const MIN_VERTEX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Draws frames to a window with a [`VulkanCanvas`].
+pub struct WindowRenderer {
+    pub target: WindowTarget,
+    pub clear_color: [f32; 4],
+    pipeline: Pipeline,
+    text_pipeline: TextPipeline,
+    atlas: GlyphAtlas,
+    atlas_texture: AtlasTexture,
+    uploads: UploadManager,
+    command_pool: CommandPool,
+    frames: FrameScheduler,
+    /// Shape and glyph vertices of each frame in flight.
+    vertex_buffers: Vec<(Buffer<Vertex>, Buffer<TextVertex>)>,
+}
+
+impl WindowRenderer {
+    pub fn new(
+        device: &AshDevice,
+        target: WindowTarget,
+        max_frames_in_flight: usize,
+    ) -> Result<Self> {
+        let pipeline = Pipeline::new(
+            &device.device,
+            target.render_pass.render_pass,
+            target.render_pass.samples,
+        )?;
+        let text_pipeline = TextPipeline::new(
+            &device.device,
+            target.render_pass.render_pass,
+            target.render_pass.samples,
+        )?;
+
+        let atlas = GlyphAtlas::new(ATLAS_SIZE, ATLAS_SIZE);
+        let atlas_texture = AtlasTexture::new(
+            device,
+            vk::Extent2D {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+            },
+            text_pipeline.descriptor_set_layout,
+        )?;
+
+        let uploads = UploadManager::new(device, STAGING_RING_SIZE)?;
+        let command_pool = CommandPool::new(
+            &device.device,
+            device.queue_family_indices.graphics_family.unwrap(),
+        )?;
+        let frames = FrameScheduler::new(&device.device, &command_pool, max_frames_in_flight)?;
+        let vertex_buffers = (0..frames.max_frames_in_flight())
+            .map(|_| {
+                let mut vertex_buffer = Buffer::new(BufferKind::Vertex);
+                vertex_buffer.set_name("vertex buffer");
+                vertex_buffer.reserve(device, MIN_VERTEX_BUFFER_SIZE / size_of::<Vertex>())?;
+                let mut text_vertex_buffer = Buffer::new(BufferKind::Vertex);
+                text_vertex_buffer.set_name("text vertex buffer");
+                text_vertex_buffer
+                    .reserve(device, MIN_VERTEX_BUFFER_SIZE / size_of::<TextVertex>())?;
+                Ok((vertex_buffer, text_vertex_buffer))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(WindowRenderer {
+            target,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            pipeline,
+            text_pipeline,
+            atlas,
+            atlas_texture,
+            uploads,
+            command_pool,
+            frames,
+            vertex_buffers,
+        })
+    }
+
+    /// Records the commands `paint` issues and presents them, returning
+    /// whether a frame was drawn. Nothing is drawn while the window is
+    /// minimized or its swapchain is being replaced.
+    ///
+    /// `paint` runs a second time if the glyph atlas fills up, after it has
+    /// been cleared.
+    pub fn draw_frame(
+        &mut self,
+        device: &AshDevice,
+        mut paint: impl FnMut(&mut dyn Canvas),
+    ) -> Result<bool> {
+        let Some(frame) = self.frames.begin_frame(device, &mut self.target)? else {
+            return Ok(false);
+        };
+        let extent = self.target.extent();
+
+        let mut canvas =
+            VulkanCanvas::new(&device.device, frame.command_buffer, &self.pipeline, extent)
+                .with_text(TextResources {
+                    pipeline: &self.text_pipeline,
+                    texture: &self.atlas_texture,
+                    atlas: &mut self.atlas,
+                });
+        paint(&mut canvas);
+        // Nothing has been uploaded yet, so evicting cannot affect a frame
+        // in flight: the previous upload has been waited on by the fence.
+        if canvas.atlas_overflowed() {
+            canvas.reset_and_clear_atlas();
+            paint(&mut canvas);
+        }
+
+        // The frame's fence has been waited on, so its buffers are free.
+        let (vertex_buffer, text_vertex_buffer) = &mut self.vertex_buffers[frame.index];
+        vertex_buffer.write(device, canvas.vertices())?;
+        text_vertex_buffer.write(device, canvas.text_vertices())?;
+
+        let clear_values = [
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: self.clear_color,
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
+        let render_pass_info = vk::RenderPassBeginInfo::default()
+            .render_pass(self.target.render_pass.render_pass)
+            .framebuffer(self.target.framebuffer(frame.image_index))
+            .render_area(vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            })
+            .clear_values(&clear_values);
+
+        unsafe {
+            device.device.cmd_begin_render_pass(
+                frame.command_buffer,
+                &render_pass_info,
+                vk::SubpassContents::INLINE,
+            );
+            canvas.finish(vertex_buffer.handle(), text_vertex_buffer.handle());
+            device.device.cmd_end_render_pass(frame.command_buffer);
+        }
+
+        // Submitted ahead of the frame, so the frame samples the new glyphs.
+        if self.atlas.take_dirty() {
+            self.atlas_texture
+                .upload(device, &mut self.uploads, &self.atlas.data)?;
+            self.uploads.flush()?;
+        }
+
+        self.frames.end_frame(device, &mut self.target, frame)?;
+        device.instance.check_validation();
+        Ok(true)
+    }
+}
+
+/// Waits for the device to finish before the fields, including the window
+/// target, are dropped.
+impl Drop for WindowRenderer {
+    fn drop(&mut self) {
+        // A lost device has nothing left to wait for.
+        unsafe {
+            let _ = self.command_pool.device().device_wait_idle();
+        }
+    }
+}
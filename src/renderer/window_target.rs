@@ -0,0 +1,350 @@
+use ash::vk;
+
+use crate::error::Result;
+
+use super::depth::{create_depth_image, find_depth_format};
+use super::device::AshDevice;
+use super::framebuffer::Framebuffers;
+use super::memory::OwnedImage;
+use super::render_pass::RenderPass;
+use super::surface::Surface;
+use super::swapchain::{Swapchain, SwapchainConfig, SwapchainSupportDetails};
+
+/// How a [`WindowTarget`] presents. Settings the device or surface cannot
+/// provide fall back to ones it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetConfig {
+    /// Preferred present mode; FIFO if unsupported.
+    pub present_mode: vk::PresentModeKHR,
+    /// Samples per pixel, lowered to the most the device supports.
+    pub samples: vk::SampleCountFlags,
+    /// Blends the window with what is behind it.
+    pub transparent: bool,
+    /// Gives the window a depth attachment. 2D UI drawn in painter's order
+    /// does not need one.
+    pub depth: bool,
+}
+
+impl Default for TargetConfig {
+    fn default() -> Self {
+        TargetConfig {
+            present_mode: vk::PresentModeKHR::MAILBOX,
+            samples: vk::SampleCountFlags::TYPE_1,
+            transparent: false,
+            depth: true,
+        }
+    }
+}
+
+/// A window surface and the swapchain presenting to it.
+///
+/// The swapchain and everything sized to it are rebuilt when the window is
+/// resized or presentation reports the swapchain as out of date. While the
+/// window is minimized there is no swapchain and nothing is drawn.
+///
+/// Fields are dropped in order, so the swapchain is destroyed before the
+/// surface it presents to.
+pub struct WindowTarget {
+    resources: Option<SwapchainResources>,
+    pub render_pass: RenderPass,
+    pub format: vk::Format,
+    config: TargetConfig,
+    /// Inner size of the window in pixels.
+    window_extent: vk::Extent2D,
+    needs_recreate: bool,
+    surface: Surface,
+}
+
+/// The swapchain and the attachments that must match its extent, dropped
+/// in that order.
+struct SwapchainResources {
+    framebuffers: Framebuffers,
+    /// The multisampled image drawn to and resolved into the swapchain
+    /// image, when multisampling.
+    _color: Option<OwnedImage>,
+    /// Sized to the swapchain, if the render pass has a depth attachment.
+    _depth: Option<OwnedImage>,
+    swapchain: Swapchain,
+}
+
+impl WindowTarget {
+    /// Creates the swapchain for `surface`, which the target takes ownership
+    /// of.
+    pub fn new(
+        device: &AshDevice,
+        surface: Surface,
+        window_extent: vk::Extent2D,
+        config: TargetConfig,
+    ) -> Result<Self> {
+        let format = SwapchainSupportDetails::query(
+            &surface.loader,
+            device.physical_device,
+            surface.handle(),
+        )?
+        .choose_format()
+        .format;
+        let config = TargetConfig {
+            samples: supported_samples(device, config.samples),
+            ..config
+        };
+        let depth_format = if config.depth {
+            Some(find_depth_format(device)?)
+        } else {
+            None
+        };
+        let render_pass =
+            RenderPass::new_multisampled(&device.device, format, depth_format, config.samples)?;
+
+        let mut target = WindowTarget {
+            resources: None,
+            render_pass,
+            format,
+            config,
+            window_extent,
+            needs_recreate: true,
+            surface,
+        };
+        target.prepare(device)?;
+        Ok(target)
+    }
+
+    /// Records a new window size. The swapchain is rebuilt before the next
+    /// frame is acquired.
+    pub fn resize(&mut self, window_extent: vk::Extent2D) {
+        if window_extent != self.window_extent {
+            self.window_extent = window_extent;
+            self.needs_recreate = true;
+        }
+    }
+
+    pub fn surface(&self) -> vk::SurfaceKHR {
+        self.surface.handle()
+    }
+
+    /// Whether the window has no area, such as while it is minimized.
+    pub fn is_paused(&self) -> bool {
+        self.window_extent.width == 0 || self.window_extent.height == 0
+    }
+
+    /// The current swapchain, or `None` while paused.
+    pub fn swapchain(&self) -> Option<&Swapchain> {
+        self.resources
+            .as_ref()
+            .map(|resources| &resources.swapchain)
+    }
+
+    /// Extent of the swapchain images, or zero while paused.
+    pub fn extent(&self) -> vk::Extent2D {
+        self.swapchain()
+            .map(|swapchain| swapchain.extent)
+            .unwrap_or_default()
+    }
+
+    /// The framebuffer drawing to swapchain image `image_index`.
+    pub fn framebuffer(&self, image_index: u32) -> vk::Framebuffer {
+        let resources = self.resources.as_ref().expect("No swapchain to draw to!");
+        resources.framebuffers.framebuffers[image_index as usize]
+    }
+
+    /// Rebuilds the swapchain if it was invalidated, returning whether there
+    /// is one to draw to.
+    pub fn prepare(&mut self, device: &AshDevice) -> Result<bool> {
+        if self.needs_recreate {
+            self.recreate(device)?;
+        }
+        Ok(self.resources.is_some())
+    }
+
+    /// Acquires the next image to draw to, signalling `semaphore` when it is
+    /// ready. Returns `None` if there is nothing to draw to this frame, in
+    /// which case `semaphore` is left unsignalled.
+    pub fn acquire_next_image(
+        &mut self,
+        device: &AshDevice,
+        semaphore: vk::Semaphore,
+    ) -> Result<Option<u32>> {
+        if !self.prepare(device)? {
+            return Ok(None);
+        }
+        let Some(resources) = &self.resources else {
+            return Ok(None);
+        };
+
+        let swapchain = &resources.swapchain;
+        let result = unsafe {
+            swapchain.swapchain.acquire_next_image(
+                swapchain.swapchain_khr,
+                u64::MAX,
+                semaphore,
+                vk::Fence::null(),
+            )
+        };
+
+        match result {
+            Ok((image_index, suboptimal)) => {
+                // A suboptimal swapchain can still be presented to; replace
+                // it after this frame.
+                self.needs_recreate |= suboptimal;
+                Ok(Some(image_index))
+            }
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                self.needs_recreate = true;
+                Ok(None)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Queues `image_index` for presentation once `wait_semaphores` are
+    /// signalled.
+    pub fn present(
+        &mut self,
+        queue: vk::Queue,
+        image_index: u32,
+        wait_semaphores: &[vk::Semaphore],
+    ) -> Result<()> {
+        let Some(resources) = &self.resources else {
+            return Ok(());
+        };
+
+        let swapchains = [resources.swapchain.swapchain_khr];
+        let image_indices = [image_index];
+        let present_info = vk::PresentInfoKHR::default()
+            .wait_semaphores(wait_semaphores)
+            .swapchains(&swapchains)
+            .image_indices(&image_indices);
+
+        let result = unsafe {
+            resources
+                .swapchain
+                .swapchain
+                .queue_present(queue, &present_info)
+        };
+
+        match result {
+            Ok(suboptimal) => self.needs_recreate |= suboptimal,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => self.needs_recreate = true,
+            Err(error) => return Err(error.into()),
+        }
+        Ok(())
+    }
+
+    /// Replaces the swapchain, depth image and framebuffers to match the
+    /// surface's current size. Waits for the device to be idle first, since
+    /// the old resources may still be in use.
+    fn recreate(&mut self, device: &AshDevice) -> Result<()> {
+        unsafe {
+            device.device.device_wait_idle()?;
+        }
+        self.needs_recreate = false;
+
+        let old = self.resources.take();
+        let support = SwapchainSupportDetails::query(
+            &self.surface.loader,
+            device.physical_device,
+            self.surface.handle(),
+        )?;
+        let extent = support.choose_extent(self.window_extent);
+        if self.is_paused() || extent.width == 0 || extent.height == 0 {
+            return Ok(());
+        }
+
+        let old_swapchain = old
+            .as_ref()
+            .map(|old| old.swapchain.swapchain_khr)
+            .unwrap_or_default();
+        let swapchain = Swapchain::new(
+            &device.instance.instance,
+            &self.surface.loader,
+            device.physical_device,
+            &device.device,
+            self.surface.handle(),
+            &SwapchainConfig {
+                window_extent: extent,
+                present_mode: self.config.present_mode,
+                transparent: self.config.transparent,
+            },
+            old_swapchain,
+        );
+        // The old swapchain is retired even if creating the new one failed.
+        drop(old);
+        let swapchain = swapchain?;
+
+        let samples = self.config.samples;
+        let depth = self
+            .render_pass
+            .depth_format
+            .map(|format| {
+                create_depth_image(
+                    device,
+                    swapchain.extent,
+                    format,
+                    samples,
+                    "depth attachment",
+                )
+            })
+            .transpose()?;
+        let depth_view = depth.as_ref().map(|depth| depth.view);
+
+        let color = if samples != vk::SampleCountFlags::TYPE_1 {
+            Some(OwnedImage::new(
+                device,
+                swapchain.extent,
+                swapchain.format,
+                vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+                samples,
+                vk::ImageAspectFlags::COLOR,
+            )?)
+        } else {
+            None
+        };
+        if let Some(color) = &color {
+            color.set_name("multisampled color attachment");
+        }
+
+        let framebuffers = match &color {
+            Some(color) => Framebuffers::new_multisampled(
+                &device.device,
+                self.render_pass.render_pass,
+                color.view,
+                &swapchain.image_views,
+                depth_view,
+                swapchain.extent,
+            ),
+            None => Framebuffers::new(
+                &device.device,
+                self.render_pass.render_pass,
+                &swapchain.image_views,
+                depth_view,
+                swapchain.extent,
+            ),
+        }?;
+
+        self.resources = Some(SwapchainResources {
+            framebuffers,
+            _color: color,
+            _depth: depth,
+            swapchain,
+        });
+        Ok(())
+    }
+}
+
+/// The largest sample count up to `requested` that the device supports for
+/// both color and depth attachments.
+fn supported_samples(device: &AshDevice, requested: vk::SampleCountFlags) -> vk::SampleCountFlags {
+    let limits = unsafe {
+        device
+            .instance
+            .instance
+            .get_physical_device_properties(device.physical_device)
+            .limits
+    };
+    let supported = limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts;
+
+    let mut samples = requested.as_raw().max(1);
+    while samples > 1 && !supported.contains(vk::SampleCountFlags::from_raw(samples)) {
+        samples >>= 1;
+    }
+    vk::SampleCountFlags::from_raw(samples)
+}
@@ -0,0 +1,483 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::application::headless::render_to_image;
+use crate::renderer::canvas::{Canvas, ScaledCanvas};
+use crate::renderer::image_io::{load_png, save_png};
+use crate::renderer::primitive::{Color, Image};
+use crate::renderer::software::rasterizer::SoftwareRenderer;
+
+/// Set to `1` to (re)write reference images instead of comparing.
+pub const UPDATE_ENV_VAR: &str = "UPLIFT_UPDATE_GOLDEN";
+
+/// Which renderer produces the image under test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// The CPU rasterizer; needs no GPU or driver.
+    Software,
+    /// The Vulkan pipeline through the headless offscreen path.
+    Vulkan,
+}
+
+/// How far an image may deviate from its reference and still pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Largest allowed difference in any single RGBA channel.
+    pub max_channel_delta: u8,
+    /// Largest allowed perceptual (YIQ) difference, from `0.0` to `1.0`.
+    pub max_perceptual_delta: f32,
+    /// How many pixels may exceed the limits above.
+    pub max_failing_pixels: usize,
+}
+
+impl Tolerance {
+    /// Every pixel must match exactly.
+    pub const EXACT: Tolerance = Tolerance {
+        max_channel_delta: 0,
+        max_perceptual_delta: 0.0,
+        max_failing_pixels: 0,
+    };
+}
+
+impl Default for Tolerance {
+    /// Absorbs rounding differences between drivers.
+    fn default() -> Self {
+        Tolerance {
+            max_channel_delta: 2,
+            max_perceptual_delta: 0.01,
+            max_failing_pixels: 0,
+        }
+    }
+}
+
+/// The result of comparing two images of equal size.
+pub struct Comparison {
+    pub failing_pixels: usize,
+    pub max_channel_delta: u8,
+    pub max_perceptual_delta: f32,
+    /// The reference dimmed to grey, with failing pixels in red.
+    pub diff: Image,
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    MissingReference {
+        reference: PathBuf,
+        actual: PathBuf,
+    },
+    SizeMismatch {
+        expected_size: (u32, u32),
+        actual_size: (u32, u32),
+        actual: PathBuf,
+    },
+    Mismatch {
+        failing_pixels: usize,
+        max_channel_delta: u8,
+        max_perceptual_delta: f32,
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+    Render(crate::Error),
+    Io(Box<dyn Error>),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::MissingReference { reference, actual } => write!(
+                f,
+                "no reference image at {}; wrote the rendered image to {} (set {}=1 to accept it)",
+                reference.display(),
+                actual.display(),
+                UPDATE_ENV_VAR
+            ),
+            GoldenError::SizeMismatch {
+                expected_size,
+                actual_size,
+                actual,
+            } => write!(
+                f,
+                "rendered {}x{} but the reference is {}x{}; see {}",
+                actual_size.0,
+                actual_size.1,
+                expected_size.0,
+                expected_size.1,
+                actual.display()
+            ),
+            GoldenError::Mismatch {
+                failing_pixels,
+                max_channel_delta,
+                max_perceptual_delta,
+                actual,
+                diff,
+            } => write!(
+                f,
+                "{} pixels differ (max channel delta {}, max perceptual delta {:.4}); see {} and {}",
+                failing_pixels,
+                max_channel_delta,
+                max_perceptual_delta,
+                actual.display(),
+                diff.display()
+            ),
+            GoldenError::Render(err) => write!(f, "failed to render: {}", err),
+            GoldenError::Io(err) => write!(f, "failed to read or write an image: {}", err),
+        }
+    }
+}
+
+impl Error for GoldenError {}
+
+/// A snapshot test that renders at a fixed size and scale factor and
+/// compares the result against a checked-in reference PNG.
+///
+/// References live in `tests/golden/<name>.png` under the crate being
+/// tested. On any failure the rendered image is written to
+/// `target/golden/`, along with a diff when the sizes match.
+pub struct GoldenTest {
+    name: String,
+    width: u32,
+    height: u32,
+    scale_factor: f32,
+    background: Color,
+    backend: Backend,
+    tolerance: Tolerance,
+    reference_dir: PathBuf,
+    output_dir: PathBuf,
+}
+
+impl GoldenTest {
+    /// Creates a test rendering `width` x `height` logical pixels.
+    pub fn new(name: &str, width: u32, height: u32) -> Self {
+        let root = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+
+        GoldenTest {
+            name: name.to_string(),
+            width,
+            height,
+            scale_factor: 1.0,
+            background: Color::BLACK,
+            backend: Backend::Software,
+            tolerance: Tolerance::default(),
+            reference_dir: root.join("tests").join("golden"),
+            output_dir: root.join("target").join("golden"),
+        }
+    }
+
+    pub fn scale_factor(mut self, scale_factor: f32) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Background for the software backend. The Vulkan path always clears
+    /// to opaque black.
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn reference_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.reference_dir = dir.into();
+        self
+    }
+
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = dir.into();
+        self
+    }
+
+    /// Renders with `paint` (in logical pixels) and panics with a report if
+    /// the result does not match the reference.
+    pub fn assert(&self, paint: impl FnMut(&mut dyn Canvas)) {
+        if let Err(err) = self.check(paint) {
+            panic!("golden image '{}' failed: {}", self.name, err);
+        }
+    }
+
+    /// Renders with `paint` (in logical pixels) and compares the result
+    /// against the reference.
+    pub fn check(&self, paint: impl FnMut(&mut dyn Canvas)) -> Result<(), GoldenError> {
+        let actual = self.render(paint)?;
+        let reference_path = self.reference_dir.join(format!("{}.png", self.name));
+
+        if std::env::var(UPDATE_ENV_VAR).is_ok_and(|v| v == "1") {
+            return write_image(&actual, &reference_path);
+        }
+
+        let actual_path = self.output_path("actual");
+        let result = self.compare_to_reference(&actual, &reference_path, &actual_path);
+        if result.is_err() {
+            write_image(&actual, &actual_path)?;
+        }
+        result
+    }
+
+    /// Compares `actual` against the reference, writing the diff on a
+    /// mismatch. The caller writes `actual` itself on any error.
+    fn compare_to_reference(
+        &self,
+        actual: &Image,
+        reference_path: &Path,
+        actual_path: &Path,
+    ) -> Result<(), GoldenError> {
+        if !reference_path.exists() {
+            return Err(GoldenError::MissingReference {
+                reference: reference_path.to_path_buf(),
+                actual: actual_path.to_path_buf(),
+            });
+        }
+
+        let expected = load_png(reference_path).map_err(|err| GoldenError::Io(err.into()))?;
+        if (expected.width, expected.height) != (actual.width, actual.height) {
+            return Err(GoldenError::SizeMismatch {
+                expected_size: (expected.width, expected.height),
+                actual_size: (actual.width, actual.height),
+                actual: actual_path.to_path_buf(),
+            });
+        }
+
+        let comparison = compare(actual, &expected, &self.tolerance);
+        if comparison.failing_pixels <= self.tolerance.max_failing_pixels {
+            return Ok(());
+        }
+
+        let diff_path = self.output_path("diff");
+        write_image(&comparison.diff, &diff_path)?;
+
+        Err(GoldenError::Mismatch {
+            failing_pixels: comparison.failing_pixels,
+            max_channel_delta: comparison.max_channel_delta,
+            max_perceptual_delta: comparison.max_perceptual_delta,
+            actual: actual_path.to_path_buf(),
+            diff: diff_path,
+        })
+    }
+
+    fn render(&self, mut paint: impl FnMut(&mut dyn Canvas)) -> Result<Image, GoldenError> {
+        let width = (self.width as f32 * self.scale_factor).round() as u32;
+        let height = (self.height as f32 * self.scale_factor).round() as u32;
+        let scale_factor = self.scale_factor;
+
+        match self.backend {
+            Backend::Software => {
+                let mut renderer = SoftwareRenderer::new(width, height);
+                renderer.clear(self.background);
+                paint(&mut ScaledCanvas::new(&mut renderer, scale_factor));
+                Ok(renderer.pixmap.to_image())
+            }
+            // 2D drawing in painter's order needs no depth attachment.
+            Backend::Vulkan => render_to_image(width, height, false, |canvas| {
+                paint(&mut ScaledCanvas::new(canvas, scale_factor))
+            })
+            .map_err(GoldenError::Render),
+        }
+    }
+
+    fn output_path(&self, kind: &str) -> PathBuf {
+        self.output_dir.join(format!("{}.{}.png", self.name, kind))
+    }
+}
+
+/// Compares two images of equal size pixel by pixel.
+///
+/// A pixel fails when any channel differs by more than
+/// `max_channel_delta` or its perceptual difference exceeds
+/// `max_perceptual_delta`.
+pub fn compare(actual: &Image, expected: &Image, tolerance: &Tolerance) -> Comparison {
+    assert_eq!(
+        (actual.width, actual.height),
+        (expected.width, expected.height),
+        "Compared images must have the same size"
+    );
+
+    let mut failing_pixels = 0;
+    let mut max_channel_delta = 0;
+    let mut max_perceptual_delta = 0.0f32;
+    let mut diff = Vec::with_capacity(expected.data.len());
+
+    for (a, e) in actual
+        .data
+        .chunks_exact(4)
+        .zip(expected.data.chunks_exact(4))
+    {
+        let channel_delta = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap();
+        let perceptual_delta = perceptual_delta(a, e);
+
+        max_channel_delta = max_channel_delta.max(channel_delta);
+        max_perceptual_delta = max_perceptual_delta.max(perceptual_delta);
+
+        if channel_delta > tolerance.max_channel_delta
+            || perceptual_delta > tolerance.max_perceptual_delta
+        {
+            failing_pixels += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let grey = (luma(e) * 0.25 + 255.0 * 0.75) as u8;
+            diff.extend_from_slice(&[grey, grey, grey, 255]);
+        }
+    }
+
+    Comparison {
+        failing_pixels,
+        max_channel_delta,
+        max_perceptual_delta,
+        diff: Image::new(expected.width, expected.height, diff),
+    }
+}
+
+/// The YIQ color difference between two pixels after compositing over
+/// white, normalized to `0.0..=1.0`.
+fn perceptual_delta(a: &[u8], b: &[u8]) -> f32 {
+    // Largest possible weighted YIQ distance, between black and white.
+    const MAX_DELTA: f32 = 35215.0;
+
+    let [ay, ai, aq] = yiq(a);
+    let [by, bi, bq] = yiq(b);
+    let (dy, di, dq) = (ay - by, ai - bi, aq - bq);
+
+    (0.5053 * dy * dy + 0.299 * di * di + 0.1957 * dq * dq) / MAX_DELTA
+}
+
+fn yiq(pixel: &[u8]) -> [f32; 3] {
+    let alpha = pixel[3] as f32 / 255.0;
+    let blend = |c: u8| 255.0 + (c as f32 - 255.0) * alpha;
+    let (r, g, b) = (blend(pixel[0]), blend(pixel[1]), blend(pixel[2]));
+
+    [
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_2,
+        r * 0.595_978 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_2 - g * 0.522_617_1 + b * 0.311_146_9,
+    ]
+}
+
+fn luma(pixel: &[u8]) -> f32 {
+    yiq(pixel)[0]
+}
+
+fn write_image(image: &Image, path: &Path) -> Result<(), GoldenError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| GoldenError::Io(err.into()))?;
+    }
+    save_png(image, path).map_err(|err| GoldenError::Io(err.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::primitive::Rect;
+    use crate::widget::base::Widget;
+    use crate::widget::button::Button;
+
+    /// A directory under the system temp dir, emptied first.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("uplift-golden").join(format!(
+            "{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Image {
+        Image::new(width, height, rgba.repeat((width * height) as usize))
+    }
+
+    #[test]
+    fn button_matches_reference() {
+        GoldenTest::new("button", 120, 40)
+            .background(Color::rgb(0.2, 0.2, 0.2))
+            .assert(|canvas| Button::new(10.0, 5.0, 100.0, 30.0, "Click me").paint(canvas));
+    }
+
+    #[test]
+    fn size_mismatch_writes_actual() {
+        let dir = scratch_dir("size-mismatch");
+        write_image(&solid(4, 4, [0, 0, 0, 255]), &dir.join("square.png")).unwrap();
+
+        let err = GoldenTest::new("square", 8, 4)
+            .reference_dir(&dir)
+            .output_dir(&dir)
+            .check(|_| {})
+            .unwrap_err();
+        match err {
+            GoldenError::SizeMismatch {
+                expected_size,
+                actual_size,
+                actual,
+            } => {
+                assert_eq!(expected_size, (4, 4));
+                assert_eq!(actual_size, (8, 4));
+                assert_eq!(load_png(&actual).unwrap().width, 8);
+            }
+            err => panic!("expected a size mismatch, got {}", err),
+        }
+    }
+
+    #[test]
+    fn mismatch_writes_actual_and_diff() {
+        let dir = scratch_dir("mismatch");
+        write_image(&solid(4, 4, [0, 0, 0, 255]), &dir.join("red.png")).unwrap();
+
+        let err = GoldenTest::new("red", 4, 4)
+            .reference_dir(&dir)
+            .output_dir(&dir)
+            .check(|canvas| {
+                canvas.fill_rect(Rect::new(0.0, 0.0, 2.0, 4.0), Color::rgb(1.0, 0.0, 0.0))
+            })
+            .unwrap_err();
+        match err {
+            GoldenError::Mismatch {
+                failing_pixels,
+                actual,
+                diff,
+                ..
+            } => {
+                assert_eq!(failing_pixels, 8);
+                assert!(actual.exists());
+                assert_eq!(load_png(&diff).unwrap().pixel(0, 0), [255, 0, 0, 255]);
+            }
+            err => panic!("expected a mismatch, got {}", err),
+        }
+    }
+
+    #[test]
+    fn missing_reference_writes_actual() {
+        let dir = scratch_dir("missing");
+        let err = GoldenTest::new("missing", 4, 4)
+            .reference_dir(&dir)
+            .output_dir(&dir)
+            .check(|_| {})
+            .unwrap_err();
+        match err {
+            GoldenError::MissingReference { actual, .. } => assert!(actual.exists()),
+            err => panic!("expected a missing reference, got {}", err),
+        }
+    }
+
+    #[test]
+    fn tolerance_absorbs_small_differences() {
+        let expected = solid(2, 2, [100, 100, 100, 255]);
+        let actual = solid(2, 2, [102, 101, 100, 255]);
+
+        let comparison = compare(&actual, &expected, &Tolerance::default());
+        assert_eq!(comparison.failing_pixels, 0);
+        assert_eq!(comparison.max_channel_delta, 2);
+
+        let comparison = compare(&actual, &expected, &Tolerance::EXACT);
+        assert_eq!(comparison.failing_pixels, 4);
+    }
+}
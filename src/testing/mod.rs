@@ -0,0 +1 @@
+pub mod golden;
\ No newline at end of file
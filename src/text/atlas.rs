@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+
+use crate::renderer::primitive::{AlphaMask, Image};
+
+use super::font::Font;
+use super::raster::{rasterize_glyph, GlyphBitmap, GlyphKey};
+
+/// Empty texels kept between glyphs so neighbours never touch.
+const PADDING: u32 = 1;
+
+/// Where a glyph lives in a [`GlyphAtlas`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasEntry {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Offset from the pen position to the left edge of the glyph.
+    pub left: i32,
+    /// Offset from the baseline up to the top edge of the glyph.
+    pub top: i32,
+    /// Whether the texels hold colors to draw as is, rather than coverage
+    /// to tint with the text color.
+    pub color: bool,
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    next_x: u32,
+}
+
+/// Packs rasterized glyphs into an RGBA texture on the CPU.
+///
+/// Coverage masks are stored as white with the coverage in alpha, so tinted
+/// outlines and color emoji can share one texture and one shader. Masks and
+/// images drawn directly are packed alongside, keyed by the address of
+/// their texels: keep them alive between frames to reuse their entries.
+///
+/// Glyphs are placed on shelves the height of their tallest glyph. Nothing
+/// is evicted while a frame is painted: once the atlas is full, lookups of
+/// new entries fail and [`Self::overflowed`] is set until the owner calls
+/// [`Self::clear`] between frames. Entries must therefore be looked up
+/// again every frame. The texture is re-uploaded whenever
+/// [`Self::take_dirty`] reports a change.
+pub struct GlyphAtlas {
+    pub width: u32,
+    pub height: u32,
+    /// Straight-alpha RGBA texels, row by row.
+    pub data: Vec<u8>,
+    shelves: Vec<Shelf>,
+    entries: HashMap<GlyphKey, Option<AtlasEntry>>,
+    bitmaps: HashMap<BitmapKey, Option<AtlasEntry>>,
+    dirty: bool,
+    overflowed: bool,
+}
+
+impl GlyphAtlas {
+    pub fn new(width: u32, height: u32) -> Self {
+        GlyphAtlas {
+            width,
+            height,
+            data: vec![0; (width * height * 4) as usize],
+            shelves: Vec::new(),
+            entries: HashMap::new(),
+            bitmaps: HashMap::new(),
+            dirty: true,
+            overflowed: false,
+        }
+    }
+
+    /// Returns the atlas location of `key`, rasterizing and packing it on
+    /// first use. Returns `None` for glyphs without ink, glyphs larger than
+    /// the atlas, and glyphs that do not fit in the space left.
+    pub fn get(&mut self, font: &Font, key: GlyphKey) -> Option<AtlasEntry> {
+        if let Some(entry) = self.entries.get(&key) {
+            return *entry;
+        }
+
+        let entry = rasterize_glyph(font, key).and_then(|glyph| {
+            let (x, y) = self.pack(Texels::from(&glyph.bitmap))?;
+            Some(AtlasEntry {
+                x,
+                y,
+                width: glyph.bitmap.width(),
+                height: glyph.bitmap.height(),
+                left: glyph.left,
+                top: glyph.top,
+                color: matches!(glyph.bitmap, GlyphBitmap::Color(_)),
+            })
+        });
+
+        // A miss while full may succeed once the atlas is cleared.
+        if entry.is_some() || !self.overflowed {
+            self.entries.insert(key, entry);
+        }
+        entry
+    }
+
+    /// Returns the atlas location of a coverage mask, packing it on first
+    /// use or if its texels changed. Returns `None` if it does not fit.
+    pub fn get_mask(&mut self, mask: &AlphaMask) -> Option<AtlasEntry> {
+        self.get_bitmap(Texels::Mask(mask))
+    }
+
+    /// Returns the atlas location of a color image, packing it on first use
+    /// or if its texels changed. Returns `None` if it does not fit.
+    pub fn get_image(&mut self, image: &Image) -> Option<AtlasEntry> {
+        self.get_bitmap(Texels::Color(image))
+    }
+
+    fn get_bitmap(&mut self, texels: Texels) -> Option<AtlasEntry> {
+        let key = texels.key();
+        match self.bitmaps.get(&key) {
+            // The address may have been reused for other texels, or the
+            // texels changed in place.
+            Some(Some(entry)) if self.holds(entry, texels) => return Some(*entry),
+            // Too large for the atlas, which depends only on the size.
+            Some(None) => return None,
+            _ => {}
+        }
+
+        let (width, height) = texels.size();
+        let entry = self.pack(texels).map(|(x, y)| AtlasEntry {
+            x,
+            y,
+            width,
+            height,
+            left: 0,
+            top: 0,
+            color: matches!(texels, Texels::Color(_)),
+        });
+        if entry.is_some() || !self.overflowed {
+            self.bitmaps.insert(key, entry);
+        }
+        entry
+    }
+
+    /// Whether `entry` holds exactly `texels`.
+    fn holds(&self, entry: &AtlasEntry, texels: Texels) -> bool {
+        let width = entry.width as usize;
+        (0..entry.height).all(|row| {
+            let start = (((entry.y + row) * self.width + entry.x) * 4) as usize;
+            let packed = &self.data[start..start + width * 4];
+            let src = row as usize * width;
+            match texels {
+                Texels::Mask(mask) => packed
+                    .chunks_exact(4)
+                    .map(|texel| texel[3])
+                    .eq(mask.coverage[src..src + width].iter().copied()),
+                Texels::Color(image) => packed == &image.data[src * 4..(src + width) * 4],
+            }
+        })
+    }
+
+    /// Whether `data` changed since the last call.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Whether a lookup failed for lack of space since the last
+    /// [`Self::clear`]. Whatever painted with the atlas meanwhile is
+    /// missing entries and should be painted again after clearing.
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Drops every entry. Must not be called while entries returned since
+    /// the texture was last uploaded are still being drawn.
+    pub fn clear(&mut self) {
+        self.data.fill(0);
+        self.shelves.clear();
+        self.entries.clear();
+        self.bitmaps.clear();
+        self.dirty = true;
+        self.overflowed = false;
+    }
+
+    /// Copies `texels` into free space and returns where they went, or
+    /// `None` if there is no room left.
+    fn pack(&mut self, texels: Texels) -> Option<(u32, u32)> {
+        let (width, height) = texels.size();
+        let fits_empty = width + PADDING <= self.width && height + PADDING <= self.height;
+        let Some((x, y)) = self.allocate(width, height) else {
+            self.overflowed |= fits_empty;
+            return None;
+        };
+
+        for row in 0..height {
+            let dst = (((y + row) * self.width + x) * 4) as usize;
+            let dst = &mut self.data[dst..dst + (width * 4) as usize];
+            match texels {
+                Texels::Mask(mask) => {
+                    let src = (row * width) as usize;
+                    let coverage = &mask.coverage[src..src + width as usize];
+                    for (texel, &alpha) in dst.chunks_exact_mut(4).zip(coverage) {
+                        texel.copy_from_slice(&[255, 255, 255, alpha]);
+                    }
+                }
+                Texels::Color(image) => {
+                    let src = (row * width * 4) as usize;
+                    dst.copy_from_slice(&image.data[src..src + (width * 4) as usize]);
+                }
+            }
+        }
+        self.dirty = true;
+
+        Some((x, y))
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let padded_width = width + PADDING;
+        let padded_height = height + PADDING;
+        if padded_width > self.width || padded_height > self.height {
+            return None;
+        }
+
+        // Use the shortest shelf the glyph fits on, to waste the least space.
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| {
+                shelf.height >= padded_height && shelf.next_x + padded_width <= self.width
+            })
+            .min_by_key(|shelf| shelf.height);
+        if let Some(shelf) = shelf {
+            let position = (shelf.next_x, shelf.y);
+            shelf.next_x += padded_width;
+            return Some(position);
+        }
+
+        let y = self
+            .shelves
+            .last()
+            .map_or(0, |shelf| shelf.y + shelf.height);
+        if y + padded_height > self.height {
+            return None;
+        }
+
+        self.shelves.push(Shelf {
+            y,
+            height: padded_height,
+            next_x: padded_width,
+        });
+        Some((0, y))
+    }
+}
+
+/// Identifies a mask or image by the address of its texels, which stays
+/// the same for as long as the caller keeps it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BitmapKey {
+    address: usize,
+    width: u32,
+    height: u32,
+    color: bool,
+}
+
+/// Borrowed texels to pack into the atlas.
+#[derive(Clone, Copy)]
+enum Texels<'a> {
+    Mask(&'a AlphaMask),
+    Color(&'a Image),
+}
+
+impl Texels<'_> {
+    fn size(self) -> (u32, u32) {
+        match self {
+            Texels::Mask(mask) => (mask.width, mask.height),
+            Texels::Color(image) => (image.width, image.height),
+        }
+    }
+
+    fn key(self) -> BitmapKey {
+        let (width, height) = self.size();
+        let (address, color) = match self {
+            Texels::Mask(mask) => (mask.coverage.as_ptr() as usize, false),
+            Texels::Color(image) => (image.data.as_ptr() as usize, true),
+        };
+        BitmapKey {
+            address,
+            width,
+            height,
+            color,
+        }
+    }
+}
+
+impl<'a> From<&'a GlyphBitmap> for Texels<'a> {
+    fn from(bitmap: &'a GlyphBitmap) -> Self {
+        match bitmap {
+            GlyphBitmap::Mask(mask) => Texels::Mask(mask),
+            GlyphBitmap::Color(image) => Texels::Color(image),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 7x7 mask, 8x8 with padding, distinguished by its coverage.
+    fn mask(coverage: u8) -> AlphaMask {
+        AlphaMask::new(7, 7, vec![coverage; 49])
+    }
+
+    #[test]
+    fn masks_keep_their_entry() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        let (first, second) = (mask(1), mask(2));
+        let entry = atlas.get_mask(&first).unwrap();
+        assert_eq!(atlas.get_mask(&first), Some(entry));
+        assert_ne!(atlas.get_mask(&second), Some(entry));
+        assert_eq!(atlas.get_mask(&first), Some(entry));
+    }
+
+    #[test]
+    fn changed_texels_are_packed_again() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        let mut changing = mask(1);
+        let entry = atlas.get_mask(&changing).unwrap();
+
+        // Same address and size, different contents.
+        changing.coverage[48] = 9;
+        let changed = atlas.get_mask(&changing).unwrap();
+        assert_ne!(changed, entry);
+        let last = (((changed.y + 6) * atlas.width + changed.x + 6) * 4) as usize;
+        assert_eq!(atlas.data[last + 3], 9);
+    }
+
+    #[test]
+    fn full_atlas_fails_lookups_without_evicting() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        let masks: Vec<AlphaMask> = (1..=5).map(mask).collect();
+        let entries: Vec<AtlasEntry> = masks[..4]
+            .iter()
+            .map(|mask| atlas.get_mask(mask).unwrap())
+            .collect();
+        let data = atlas.data.clone();
+        assert!(!atlas.overflowed());
+
+        assert_eq!(atlas.get_mask(&masks[4]), None);
+        assert!(atlas.overflowed());
+        // Entries handed out earlier in the frame are still valid.
+        assert_eq!(atlas.data, data);
+        for (mask, entry) in masks.iter().zip(&entries) {
+            assert_eq!(atlas.get_mask(mask), Some(*entry));
+        }
+
+        // The miss was not cached, so it succeeds after clearing.
+        atlas.clear();
+        assert!(!atlas.overflowed());
+        assert!(atlas.get_mask(&masks[4]).is_some());
+    }
+
+    #[test]
+    fn oversized_entries_do_not_overflow() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        let image = Image::new(16, 16, vec![255; 16 * 16 * 4]);
+        assert_eq!(atlas.get_image(&image), None);
+        assert!(!atlas.overflowed());
+    }
+
+    #[test]
+    fn masks_are_stored_as_white_with_coverage_alpha() {
+        let mut atlas = GlyphAtlas::new(16, 16);
+        let entry = atlas.get_mask(&mask(200)).unwrap();
+        assert!(!entry.color);
+        let texel = ((entry.y * atlas.width + entry.x) * 4) as usize;
+        assert_eq!(atlas.data[texel..texel + 4], [255, 255, 255, 200]);
+        assert!(atlas.take_dirty());
+        assert!(!atlas.take_dirty());
+    }
+}
@@ -0,0 +1,244 @@
+use unicode_properties::{EmojiStatus, UnicodeEmoji};
+use unicode_script::Script;
+
+use super::font::Font;
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const TEXT_PRESENTATION: char = '\u{FE0E}';
+const EMOJI_PRESENTATION: char = '\u{FE0F}';
+
+/// A primary font and the fonts to fall back to for characters it lacks.
+///
+/// Each grapheme cluster is drawn with the first font that covers all of
+/// it, trying in order: the emoji chain for emoji, the chain registered for
+/// the cluster's script, then the general chain starting with the primary
+/// font. Clusters nothing covers use the primary font's `.notdef` glyph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontCollection {
+    /// Every distinct font; chains below index into this.
+    fonts: Vec<Font>,
+    general: Vec<usize>,
+    scripts: Vec<(Script, Vec<usize>)>,
+    emoji: Vec<usize>,
+}
+
+impl FontCollection {
+    pub fn new(primary: Font) -> Self {
+        FontCollection {
+            fonts: vec![primary],
+            general: vec![0],
+            scripts: Vec::new(),
+            emoji: Vec::new(),
+        }
+    }
+
+    /// Appends `font` to the chain tried for every script.
+    pub fn with_fallback(mut self, font: Font) -> Self {
+        let index = self.insert(font);
+        self.general.push(index);
+        self
+    }
+
+    /// Appends `font` to the chain tried first for text in `script`.
+    pub fn with_script_fallback(mut self, script: Script, font: Font) -> Self {
+        let index = self.insert(font);
+        match self.scripts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, chain)) => chain.push(index),
+            None => self.scripts.push((script, vec![index])),
+        }
+        self
+    }
+
+    /// Appends `font` to the chain tried first for emoji, typically a color
+    /// emoji font.
+    pub fn with_emoji_fallback(mut self, font: Font) -> Self {
+        let index = self.insert(font);
+        self.emoji.push(index);
+        self
+    }
+
+    pub fn primary(&self) -> &Font {
+        &self.fonts[0]
+    }
+
+    /// Every distinct font in the collection, primary first.
+    pub fn fonts(&self) -> &[Font] {
+        &self.fonts
+    }
+
+    /// Picks the font for `cluster`, returning its index in
+    /// [`Self::fonts`]. `faces` holds the parsed faces of `fonts`, so they
+    /// are only parsed once per shaping call.
+    pub(crate) fn select(&self, faces: &[rustybuzz::Face], cluster: &str, script: Script) -> usize {
+        let script_chain = self
+            .scripts
+            .iter()
+            .find(|(s, _)| *s == script)
+            .map(|(_, chain)| chain.as_slice())
+            .unwrap_or_default();
+        let emoji_chain = if is_emoji(cluster) {
+            self.emoji.as_slice()
+        } else {
+            &[]
+        };
+
+        emoji_chain
+            .iter()
+            .chain(script_chain)
+            .chain(&self.general)
+            .copied()
+            .find(|&index| covers(&faces[index], cluster))
+            .unwrap_or(0)
+    }
+
+    fn insert(&mut self, font: Font) -> usize {
+        match self.fonts.iter().position(|f| *f == font) {
+            Some(index) => index,
+            None => {
+                self.fonts.push(font);
+                self.fonts.len() - 1
+            }
+        }
+    }
+}
+
+impl Default for FontCollection {
+    /// The bundled default font with no fallbacks.
+    fn default() -> Self {
+        Self::new(Font::default_font())
+    }
+}
+
+impl From<Font> for FontCollection {
+    fn from(font: Font) -> Self {
+        Self::new(font)
+    }
+}
+
+/// Whether `face` has a glyph for every visible character of `cluster`.
+fn covers(face: &rustybuzz::Face, cluster: &str) -> bool {
+    cluster
+        .chars()
+        .filter(|&c| !is_invisible_modifier(c))
+        .all(|c| face.glyph_index(c).is_some())
+}
+
+/// Joiners and selectors that shaping consumes even when the font has no
+/// glyph for them.
+fn is_invisible_modifier(c: char) -> bool {
+    matches!(c, ZERO_WIDTH_JOINER | '\u{FE00}'..='\u{FE0F}' | '\u{E0020}'..='\u{E007F}')
+}
+
+/// Whether `cluster` should be drawn with emoji presentation: it asks for
+/// it with VS16, joins emoji with a ZWJ, or contains a character whose
+/// default presentation is emoji. VS15 asks for text presentation instead.
+fn is_emoji(cluster: &str) -> bool {
+    if cluster.contains(TEXT_PRESENTATION) {
+        return false;
+    }
+
+    cluster.contains(EMOJI_PRESENTATION)
+        || (cluster.contains(ZERO_WIDTH_JOINER) && cluster.chars().any(|c| c.is_emoji_char()))
+        || cluster.chars().any(has_emoji_presentation)
+}
+
+/// Whether `c` has the Unicode `Emoji_Presentation` property.
+fn has_emoji_presentation(c: char) -> bool {
+    matches!(
+        c.emoji_status(),
+        EmojiStatus::EmojiPresentation
+            | EmojiStatus::EmojiPresentationAndModifierBase
+            | EmojiStatus::EmojiPresentationAndEmojiComponent
+            | EmojiStatus::EmojiPresentationAndModifierAndEmojiComponent
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::test_helpers::stub_font;
+
+    /// The font `fonts` picks for `cluster` in `script`.
+    fn selected(fonts: &FontCollection, cluster: &str, script: Script) -> Font {
+        let faces: Vec<_> = fonts.fonts().iter().map(|font| font.face()).collect();
+        fonts.fonts()[fonts.select(&faces, cluster, script)].clone()
+    }
+
+    #[test]
+    fn fallback_chains_in_order() {
+        let han = stub_font(&['中']);
+        let general = stub_font(&['中', '文', '한']);
+        let fonts = FontCollection::default()
+            .with_script_fallback(Script::Han, han.clone())
+            .with_fallback(general.clone());
+        let primary = fonts.primary().clone();
+
+        let cases = [
+            ("a", Script::Latin, &primary),
+            ("א", Script::Hebrew, &primary),
+            // The script chain comes before the general one.
+            ("中", Script::Han, &han),
+            ("文", Script::Han, &general),
+            ("한", Script::Hangul, &general),
+            // Nothing covers it: the primary font's `.notdef`.
+            ("ꯀ", Script::Meetei_Mayek, &primary),
+        ];
+        for (cluster, script, expected) in cases {
+            assert_eq!(
+                selected(&fonts, cluster, script),
+                *expected,
+                "{:?}",
+                cluster
+            );
+        }
+    }
+
+    #[test]
+    fn emoji_prefer_the_emoji_chain() {
+        let emoji = stub_font(&['😀', '👍', '🏽', '👨', '👩', '👧', '❤']);
+        let fonts = FontCollection::default().with_emoji_fallback(emoji.clone());
+        let primary = fonts.primary().clone();
+
+        let cases = [
+            ("😀", &emoji),
+            ("👍🏽", &emoji),
+            ("👨\u{200D}👩\u{200D}👧", &emoji),
+            // The primary font has a text glyph, but VS16 asks for emoji.
+            ("❤\u{FE0F}", &emoji),
+            ("❤", &primary),
+            ("a", &primary),
+        ];
+        for (cluster, expected) in cases {
+            assert_eq!(
+                selected(&fonts, cluster, Script::Common),
+                *expected,
+                "{:?}",
+                cluster
+            );
+        }
+    }
+
+    #[test]
+    fn emoji_presentation() {
+        let cases = [
+            ("😀", true),
+            ("⌚", true),
+            ("⭐", true),
+            ("🆎", true),
+            ("👍🏽", true),
+            ("🇯🇵", true),
+            ("👨\u{200D}👩\u{200D}👧", true),
+            ("1\u{FE0F}\u{20E3}", true),
+            ("☺\u{FE0F}", true),
+            ("☺", false),
+            ("😀\u{FE0E}", false),
+            ("A", false),
+            ("1", false),
+            ("🄰", false),
+            ("क्\u{200D}ष", false),
+        ];
+        for (cluster, expected) in cases {
+            assert_eq!(is_emoji(cluster), expected, "{:?}", cluster);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// DejaVu Sans, bundled so text renders without any system fonts.
+static DEFAULT_FONT_DATA: &[u8] = include_bytes!("fonts/DejaVuSans.ttf");
+
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug)]
+pub enum FontError {
+    Io(std::io::Error),
+    /// The data is not a TrueType/OpenType font, or `index` is out of range
+    /// for a font collection.
+    Invalid,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontError::Io(err) => write!(f, "failed to read font: {}", err),
+            FontError::Invalid => write!(f, "not a valid TrueType or OpenType font"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
+impl From<std::io::Error> for FontError {
+    fn from(err: std::io::Error) -> Self {
+        FontError::Io(err)
+    }
+}
+
+/// Vertical metrics of a font at a given size, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the line box.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the line box.
+    pub descent: f32,
+    pub line_gap: f32,
+}
+
+impl FontMetrics {
+    /// Recommended distance between consecutive baselines.
+    pub fn line_height(&self) -> f32 {
+        self.ascent + self.descent + self.line_gap
+    }
+}
+
+/// A loaded TrueType/OpenType face. Cloning is cheap and shares the data.
+#[derive(Clone)]
+pub struct Font {
+    id: u64,
+    data: Arc<[u8]>,
+    index: u32,
+}
+
+impl Font {
+    /// Loads face `index` from TTF/OTF/TTC data.
+    pub fn from_bytes(data: impl Into<Arc<[u8]>>, index: u32) -> Result<Self, FontError> {
+        let data = data.into();
+        if rustybuzz::Face::from_slice(&data, index).is_none() {
+            return Err(FontError::Invalid);
+        }
+
+        Ok(Font {
+            id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+            data,
+            index,
+        })
+    }
+
+    /// Loads the first face of the font file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FontError> {
+        Self::from_bytes(fs::read(path)?, 0)
+    }
+
+    /// The bundled default font.
+    pub fn default_font() -> Font {
+        static DEFAULT: OnceLock<Font> = OnceLock::new();
+        DEFAULT
+            .get_or_init(|| {
+                Font::from_bytes(DEFAULT_FONT_DATA, 0).expect("Failed to load bundled font!")
+            })
+            .clone()
+    }
+
+    /// Identifies the face in glyph caches; unique per load.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn metrics(&self, size: f32) -> FontMetrics {
+        let face = self.face();
+        let scale = self.scale(&face, size);
+
+        FontMetrics {
+            ascent: face.ascender() as f32 * scale,
+            descent: -face.descender() as f32 * scale,
+            line_gap: face.line_gap() as f32 * scale,
+        }
+    }
+
+    pub(crate) fn face(&self) -> rustybuzz::Face<'_> {
+        // Validated in `from_bytes`.
+        rustybuzz::Face::from_slice(&self.data, self.index).unwrap()
+    }
+
+    /// Pixels per font unit at `size`.
+    pub(crate) fn scale(&self, face: &rustybuzz::Face, size: f32) -> f32 {
+        size / face.units_per_em() as f32
+    }
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Font")
+            .field("id", &self.id)
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
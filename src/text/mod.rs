@@ -0,0 +1,9 @@
+pub mod atlas;
+pub mod fallback;
+pub mod font;
+pub mod paragraph;
+pub mod raster;
+pub mod shaping;
+
+#[cfg(test)]
+mod test_helpers;
\ No newline at end of file
@@ -0,0 +1,478 @@
+use std::ops::Range;
+
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{Color, Size};
+
+use super::fallback::FontCollection;
+use super::shaping::{shape, ShapedText};
+
+const ELLIPSIS: &str = "\u{2026}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Right,
+    Center,
+    /// Stretches the spaces of every line but the last of each paragraph to
+    /// fill the width.
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// Every paragraph stays on one line.
+    None,
+    /// Breaks at UAX #14 line break opportunities. Words wider than the
+    /// line are broken between characters.
+    #[default]
+    Word,
+    /// Breaks between any two grapheme clusters.
+    Character,
+}
+
+/// How a paragraph is broken into lines and positioned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParagraphStyle {
+    /// Font size in pixels per em.
+    pub size: f32,
+    pub align: TextAlign,
+    pub wrap: WrapMode,
+    /// Distance between consecutive lines; the font's recommended spacing
+    /// when `None`.
+    pub line_height: Option<f32>,
+    /// Lines beyond this are dropped; at least one line is always kept.
+    pub max_lines: Option<usize>,
+    /// Ends a truncated line with "…" when text is cut off by `max_lines`
+    /// or by a line too long to wrap.
+    pub ellipsis: bool,
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> Self {
+        ParagraphStyle {
+            size: 16.0,
+            align: TextAlign::Left,
+            wrap: WrapMode::Word,
+            line_height: None,
+            max_lines: None,
+            ellipsis: false,
+        }
+    }
+}
+
+/// One laid-out line, positioned relative to the paragraph's top-left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub text: ShapedText,
+    /// Byte range of the source text on this line.
+    pub range: Range<usize>,
+    pub x: f32,
+    /// Top of the text's line box.
+    pub y: f32,
+}
+
+/// Text broken into lines for a given width.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paragraph {
+    pub lines: Vec<Line>,
+    /// Width of the widest line.
+    pub width: f32,
+    pub height: f32,
+    /// Whether text was dropped or shortened to fit.
+    pub truncated: bool,
+}
+
+impl Paragraph {
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// Draws every line with the paragraph's top-left corner at `(x, y)`.
+    pub fn paint(&self, canvas: &mut dyn Canvas, x: f32, y: f32, color: Color) {
+        for line in &self.lines {
+            canvas.draw_text(&line.text, x + line.x, y + line.y, color);
+        }
+    }
+}
+
+/// Breaks `text` into lines no wider than `max_width` (which may be
+/// infinite) and aligns them within it.
+///
+/// Hard line breaks always start a new line. Trailing spaces hang past the
+/// end of a line and do not count towards its width.
+pub fn layout_paragraph(
+    text: &str,
+    fonts: &FontCollection,
+    style: &ParagraphStyle,
+    max_width: f32,
+) -> Paragraph {
+    let metrics = fonts.primary().metrics(style.size);
+    let line_height = style.line_height.unwrap_or(metrics.line_height());
+    let max_lines = style.max_lines.unwrap_or(usize::MAX).max(1);
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut truncated = false;
+
+    let paragraphs = split_paragraphs(text);
+    'paragraphs: for (index, paragraph) in paragraphs.iter().enumerate() {
+        let source = &text[paragraph.clone()];
+        let breaks = break_lines(source, fonts, style, max_width);
+        let is_last_paragraph = index + 1 == paragraphs.len();
+
+        for (i, range) in breaks.iter().enumerate() {
+            let is_last_line = i + 1 == breaks.len();
+            let range = paragraph.start + range.start..paragraph.start + range.end;
+
+            if lines.len() + 1 == max_lines && !(is_last_line && is_last_paragraph) {
+                // The last allowed line: show as much of the rest of this
+                // paragraph as fits.
+                let line_text = if style.ellipsis {
+                    let rest = &text[range.start..paragraph.end];
+                    truncate(rest, fonts, style.size, max_width, true)
+                } else {
+                    shape(text[range.clone()].trim_end(), fonts, style.size)
+                };
+                lines.push(Line {
+                    text: line_text,
+                    range,
+                    x: 0.0,
+                    y: 0.0,
+                });
+                truncated = true;
+                break 'paragraphs;
+            }
+
+            let mut line_text = shape(text[range.clone()].trim_end(), fonts, style.size);
+            if line_text.width > max_width {
+                truncated = true;
+                if style.ellipsis {
+                    line_text = truncate(&text[range.clone()], fonts, style.size, max_width, false);
+                }
+            } else if style.align == TextAlign::Justify && !is_last_line && max_width.is_finite() {
+                justify(&mut line_text, max_width);
+            }
+
+            lines.push(Line {
+                text: line_text,
+                range,
+                x: 0.0,
+                y: 0.0,
+            });
+        }
+    }
+
+    let width = lines
+        .iter()
+        .map(|line| line.text.width)
+        .fold(0.0f32, f32::max);
+    let align_width = if max_width.is_finite() {
+        max_width
+    } else {
+        width
+    };
+
+    for (i, line) in lines.iter_mut().enumerate() {
+        let free = (align_width - line.text.width).max(0.0);
+        line.x = match style.align {
+            TextAlign::Left | TextAlign::Justify => 0.0,
+            TextAlign::Right => free,
+            TextAlign::Center => free / 2.0,
+        };
+        // Split any leading between the top and bottom of the line.
+        line.y = i as f32 * line_height + (line_height - line.text.height()) / 2.0;
+    }
+
+    Paragraph {
+        height: lines.len() as f32 * line_height,
+        lines,
+        width,
+        truncated,
+    }
+}
+
+/// Byte ranges of the paragraphs of `text`, without their line terminators.
+fn split_paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut start = 0;
+
+    // The end of the text is always reported as a mandatory break.
+    for (position, opportunity) in linebreaks(text) {
+        if opportunity != BreakOpportunity::Mandatory {
+            continue;
+        }
+
+        let line = text[start..position].trim_end_matches([
+            '\n', '\r', '\u{0B}', '\u{0C}', '\u{85}', '\u{2028}', '\u{2029}',
+        ]);
+        paragraphs.push(start..start + line.len());
+        start = position;
+    }
+
+    paragraphs
+}
+
+/// Greedily fills lines, returning byte ranges into `text` (a single
+/// paragraph) that keep their trailing spaces.
+fn break_lines(
+    text: &str,
+    fonts: &FontCollection,
+    style: &ParagraphStyle,
+    max_width: f32,
+) -> Vec<Range<usize>> {
+    let whole = 0..text.len();
+    if style.wrap == WrapMode::None || text.is_empty() {
+        return vec![whole];
+    }
+
+    let widths = ClusterWidths::new(text, fonts, style.size);
+    let graphemes: Vec<usize> = text
+        .grapheme_indices(true)
+        .map(|(i, _)| i)
+        .skip(1)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let opportunities: Vec<usize> = match style.wrap {
+        WrapMode::Character => graphemes.clone(),
+        _ => linebreaks(text).map(|(position, _)| position).collect(),
+    };
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let fits = |end: usize| {
+            widths.measure(start..start + text[start..end].trim_end().len()) <= max_width
+        };
+
+        let mut end = None;
+        for &candidate in opportunities.iter().filter(|&&b| b > start) {
+            if !fits(candidate) {
+                break;
+            }
+            end = Some(candidate);
+        }
+
+        // Nothing fits: break the first word between grapheme clusters,
+        // keeping at least one so the line makes progress.
+        let end = end.unwrap_or_else(|| {
+            let limit = opportunities
+                .iter()
+                .copied()
+                .find(|&b| b > start)
+                .unwrap_or(text.len());
+            let mut candidates = graphemes
+                .iter()
+                .copied()
+                .filter(|&b| b > start && b <= limit);
+            let first = candidates.next().unwrap_or(limit);
+            candidates.take_while(|&b| fits(b)).last().unwrap_or(first)
+        });
+
+        lines.push(start..end);
+        start = end;
+    }
+
+    lines
+}
+
+/// Shapes `text` as one line, cutting it at a grapheme boundary and adding
+/// an ellipsis if it is wider than `max_width` or `force` is set.
+fn truncate(
+    text: &str,
+    fonts: &FontCollection,
+    size: f32,
+    max_width: f32,
+    force: bool,
+) -> ShapedText {
+    let text = text.trim_end();
+    let full = shape(text, fonts, size);
+    if !force && full.width <= max_width {
+        return full;
+    }
+
+    let widths = ClusterWidths::new(text, fonts, size);
+    let ellipsis_width = shape(ELLIPSIS, fonts, size).width;
+    let mut boundaries: Vec<usize> = text.grapheme_indices(true).map(|(i, _)| i).collect();
+    boundaries.push(text.len());
+
+    // Estimate from the advances of the whole line, then confirm by shaping
+    // since kerning and ligatures may change at the cut.
+    for &end in boundaries.iter().rev() {
+        let kept = text[..end].trim_end();
+        if widths.measure(0..kept.len()) + ellipsis_width > max_width && end > 0 {
+            continue;
+        }
+
+        let candidate = shape(&format!("{}{}", kept, ELLIPSIS), fonts, size);
+        if candidate.width <= max_width || end == 0 {
+            return candidate;
+        }
+    }
+
+    shape(ELLIPSIS, fonts, size)
+}
+
+/// Widens the spaces of `line` so it is exactly `width` wide.
+fn justify(line: &mut ShapedText, width: f32) {
+    let text = line.text.clone();
+    let is_space = |cluster: u32| text[cluster as usize..].starts_with([' ', '\u{3000}']);
+
+    let spaces = line
+        .glyphs()
+        .filter(|(_, glyph)| is_space(glyph.cluster))
+        .count();
+    if spaces == 0 {
+        return;
+    }
+
+    let extra = (width - line.width) / spaces as f32;
+    for run in &mut line.runs {
+        for glyph in &mut run.glyphs {
+            if is_space(glyph.cluster) {
+                glyph.x_advance += extra;
+                run.width += extra;
+            }
+        }
+    }
+    line.width = width;
+}
+
+/// Advances of a shaped paragraph summed per source byte, to measure any
+/// substring without shaping it again.
+struct ClusterWidths {
+    /// `prefix[i]` is the advance of everything before byte `i`.
+    prefix: Vec<f32>,
+}
+
+impl ClusterWidths {
+    fn new(text: &str, fonts: &FontCollection, size: f32) -> Self {
+        let mut advances = vec![0.0f32; text.len() + 1];
+        for (_, glyph) in shape(text, fonts, size).glyphs() {
+            advances[glyph.cluster as usize] += glyph.x_advance;
+        }
+
+        let mut prefix = Vec::with_capacity(advances.len());
+        let mut sum = 0.0;
+        for advance in advances {
+            prefix.push(sum);
+            sum += advance;
+        }
+
+        ClusterWidths { prefix }
+    }
+
+    fn measure(&self, range: Range<usize>) -> f32 {
+        self.prefix[range.end] - self.prefix[range.start]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn width_of(text: &str) -> f32 {
+        shape(text, &FontCollection::default(), 16.0).width
+    }
+
+    fn layout(text: &str, style: ParagraphStyle, max_width: f32) -> Paragraph {
+        layout_paragraph(text, &FontCollection::default(), &style, max_width)
+    }
+
+    fn line_texts(paragraph: &Paragraph) -> Vec<&str> {
+        paragraph
+            .lines
+            .iter()
+            .map(|line| line.text.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn breaks_at_uax14_opportunities() {
+        let style = ParagraphStyle::default();
+
+        let paragraph = layout("alpha beta-gamma", style, width_of("alpha beta-") + 0.5);
+        assert_eq!(line_texts(&paragraph), ["alpha beta-", "gamma"]);
+        assert_eq!(paragraph.lines[0].range, 0..11);
+        assert!(!paragraph.truncated);
+
+        let paragraph = layout("alpha beta", style, width_of("alpha b"));
+        assert_eq!(line_texts(&paragraph), ["alpha", "beta"]);
+        // The trailing space hangs and stays in the first line's range.
+        assert_eq!(paragraph.lines[0].range, 0..6);
+
+        let paragraph = layout("one\ntwo", style, f32::INFINITY);
+        assert_eq!(line_texts(&paragraph), ["one", "two"]);
+    }
+
+    #[test]
+    fn breaks_long_words_between_characters() {
+        let paragraph = layout(
+            "abcdefgh",
+            ParagraphStyle::default(),
+            width_of("abcd") + 0.5,
+        );
+        assert_eq!(line_texts(&paragraph), ["abcd", "efgh"]);
+    }
+
+    #[test]
+    fn max_lines_truncates() {
+        let style = ParagraphStyle {
+            max_lines: Some(2),
+            line_height: Some(20.0),
+            ..ParagraphStyle::default()
+        };
+        let paragraph = layout("one two three", style, width_of("three") + 0.5);
+        assert_eq!(line_texts(&paragraph), ["one", "two"]);
+        assert!(paragraph.truncated);
+        assert_eq!(paragraph.height, 40.0);
+
+        let fits = layout("one two", style, width_of("three") + 0.5);
+        assert_eq!(line_texts(&fits), ["one", "two"]);
+        assert!(!fits.truncated);
+    }
+
+    #[test]
+    fn ellipsis_marks_cut_text() {
+        let max_width = width_of("hello world");
+        let style = ParagraphStyle {
+            max_lines: Some(1),
+            ellipsis: true,
+            ..ParagraphStyle::default()
+        };
+        let paragraph = layout("hello world again", style, max_width);
+        assert_eq!(paragraph.lines.len(), 1);
+        assert!(paragraph.truncated);
+        let text = &paragraph.lines[0].text;
+        assert!(text.text.starts_with("hello"), "{:?}", text.text);
+        assert!(text.text.ends_with(ELLIPSIS), "{:?}", text.text);
+        assert!(text.width <= max_width);
+
+        // A single line too long to wrap is shortened the same way.
+        let style = ParagraphStyle {
+            wrap: WrapMode::None,
+            ellipsis: true,
+            ..ParagraphStyle::default()
+        };
+        let paragraph = layout("hello world again", style, max_width);
+        assert!(paragraph.truncated);
+        assert!(paragraph.lines[0].text.text.ends_with(ELLIPSIS));
+        assert!(paragraph.width <= max_width);
+    }
+
+    #[test]
+    fn justify_fills_all_but_the_last_line() {
+        let max_width = width_of("aa bb cc") + 0.5;
+        let style = ParagraphStyle {
+            align: TextAlign::Justify,
+            ..ParagraphStyle::default()
+        };
+        let paragraph = layout("aa bb cc dd ee", style, max_width);
+        assert_eq!(line_texts(&paragraph), ["aa bb cc", "dd ee"]);
+        assert_eq!(paragraph.lines[0].text.width, max_width);
+        assert_eq!(paragraph.lines[1].text.width, width_of("dd ee"));
+        assert_eq!(paragraph.lines[1].x, 0.0);
+    }
+}
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use ab_glyph_rasterizer::{point, Point, Rasterizer};
+
+use crate::renderer::image_io::decode_png;
+use crate::renderer::primitive::{AlphaMask, Image};
+
+use super::font::Font;
+use super::shaping::ShapedText;
+
+/// Horizontal sub-pixel positions a glyph is rasterized at.
+const SUBPIXEL_STEPS: f32 = 4.0;
+
+/// Identifies one rasterization of a glyph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    pub font_id: u64,
+    pub glyph_id: u16,
+    /// Size in 1/64 pixels.
+    pub size: u32,
+    /// Horizontal offset in 1/`SUBPIXEL_STEPS` pixels.
+    pub subpixel: u8,
+}
+
+impl GlyphKey {
+    pub fn size(&self) -> f32 {
+        self.size as f32 / 64.0
+    }
+
+    pub fn subpixel_offset(&self) -> f32 {
+        self.subpixel as f32 / SUBPIXEL_STEPS
+    }
+}
+
+/// The pixels of a rasterized glyph.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlyphBitmap {
+    /// Coverage of an outline, to be tinted with the text color.
+    Mask(AlphaMask),
+    /// A color bitmap, such as an emoji, drawn as is.
+    Color(Image),
+}
+
+impl GlyphBitmap {
+    pub fn width(&self) -> u32 {
+        match self {
+            GlyphBitmap::Mask(mask) => mask.width,
+            GlyphBitmap::Color(image) => image.width,
+        }
+    }
+
+    pub fn height(&self) -> u32 {
+        match self {
+            GlyphBitmap::Mask(mask) => mask.height,
+            GlyphBitmap::Color(image) => image.height,
+        }
+    }
+}
+
+/// A glyph bitmap and where it sits relative to the pen position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RasterizedGlyph {
+    pub bitmap: GlyphBitmap,
+    /// Offset from the pen position to the left edge of the bitmap.
+    pub left: i32,
+    /// Offset from the baseline up to the top edge of the bitmap.
+    pub top: i32,
+}
+
+/// A glyph of a [`ShapedText`] snapped to the pixel grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedGlyph<'a> {
+    pub font: &'a Font,
+    pub key: GlyphKey,
+    /// Whole-pixel pen position; add the glyph's `left` and subtract its
+    /// `top` to find the bitmap origin.
+    pub x: i32,
+    pub baseline: i32,
+}
+
+/// Positions every glyph of `text` for a line box whose top-left corner is
+/// at `(x, y)`.
+pub fn place_glyphs(text: &ShapedText, x: f32, y: f32) -> Vec<PlacedGlyph<'_>> {
+    let size = (text.size * 64.0).round() as u32;
+    let baseline = (y + text.ascent).round() as i32;
+
+    let mut pen = x;
+    text.glyphs()
+        .map(|(font, glyph)| {
+            let gx = pen + glyph.x_offset;
+            pen += glyph.x_advance;
+
+            let whole = gx.floor();
+            let subpixel = ((gx - whole) * SUBPIXEL_STEPS).round();
+            let (whole, subpixel) = if subpixel >= SUBPIXEL_STEPS {
+                (whole + 1.0, 0.0)
+            } else {
+                (whole, subpixel)
+            };
+
+            PlacedGlyph {
+                font,
+                key: GlyphKey {
+                    font_id: font.id(),
+                    glyph_id: glyph.glyph_id,
+                    size,
+                    subpixel: subpixel as u8,
+                },
+                x: whole as i32,
+                baseline: baseline - glyph.y_offset.round() as i32,
+            }
+        })
+        .collect()
+}
+
+/// Rasterizes `key`'s glyph, or returns `None` for glyphs without ink such
+/// as spaces.
+///
+/// PNG bitmaps (the `CBDT` and `sbix` tables used by color emoji fonts) are
+/// preferred over outlines and scaled from the nearest strike. Other color
+/// formats fall back to the glyph's outline.
+pub fn rasterize_glyph(font: &Font, key: GlyphKey) -> Option<RasterizedGlyph> {
+    let face = font.face();
+    let glyph_id = rustybuzz::ttf_parser::GlyphId(key.glyph_id);
+
+    if let Some(glyph) = rasterize_color_bitmap(&face, glyph_id, key.size()) {
+        return Some(glyph);
+    }
+
+    let bbox = face.glyph_bounding_box(glyph_id)?;
+    let scale = font.scale(&face, key.size());
+    let shift = key.subpixel_offset();
+
+    // Pixel bounds with y pointing up from the baseline.
+    let left = (bbox.x_min as f32 * scale + shift).floor();
+    let right = (bbox.x_max as f32 * scale + shift).ceil();
+    let bottom = (bbox.y_min as f32 * scale).floor();
+    let top = (bbox.y_max as f32 * scale).ceil();
+
+    let width = (right - left) as usize;
+    let height = (top - bottom) as usize;
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let mut builder = OutlineRasterizer {
+        rasterizer: Rasterizer::new(width, height),
+        transform: (scale, shift - left, top),
+        start: point(0.0, 0.0),
+        last: point(0.0, 0.0),
+    };
+    face.outline_glyph(glyph_id, &mut builder)?;
+
+    let mut coverage = vec![0u8; width * height];
+    builder.rasterizer.for_each_pixel_2d(|x, y, alpha| {
+        coverage[y as usize * width + x as usize] = (alpha.clamp(0.0, 1.0) * 255.0).round() as u8;
+    });
+
+    Some(RasterizedGlyph {
+        bitmap: GlyphBitmap::Mask(AlphaMask {
+            width: width as u32,
+            height: height as u32,
+            coverage,
+        }),
+        left: left as i32,
+        top: top as i32,
+    })
+}
+
+fn rasterize_color_bitmap(
+    face: &rustybuzz::Face,
+    glyph_id: rustybuzz::ttf_parser::GlyphId,
+    size: f32,
+) -> Option<RasterizedGlyph> {
+    let strike = face.glyph_raster_image(glyph_id, size.round().max(1.0) as u16)?;
+    if strike.format != rustybuzz::ttf_parser::RasterImageFormat::PNG {
+        return None;
+    }
+
+    let image = decode_png(strike.data).ok()?;
+    let factor = size / strike.pixels_per_em as f32;
+    let width = (image.width as f32 * factor).round().max(1.0) as u32;
+    let height = (image.height as f32 * factor).round().max(1.0) as u32;
+
+    // Strike offsets are to the bottom-left corner, with y pointing up.
+    Some(RasterizedGlyph {
+        left: (strike.x as f32 * factor).round() as i32,
+        top: ((strike.y as f32 + image.height as f32) * factor).round() as i32,
+        bitmap: GlyphBitmap::Color(resample(&image, width, height)),
+    })
+}
+
+/// Scales `image` to `width` x `height`, averaging the source pixels each
+/// destination pixel covers.
+fn resample(image: &Image, width: u32, height: u32) -> Image {
+    let sx = image.width as f32 / width as f32;
+    let sy = image.height as f32 / height as f32;
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+
+    for y in 0..height {
+        let y0 = (y as f32 * sy) as u32;
+        let y1 = (((y + 1) as f32 * sy).ceil() as u32).clamp(y0 + 1, image.height);
+        for x in 0..width {
+            let x0 = (x as f32 * sx) as u32;
+            let x1 = (((x + 1) as f32 * sx).ceil() as u32).clamp(x0 + 1, image.width);
+
+            // Weight colors by alpha so transparent pixels do not darken
+            // the edges.
+            let mut sum = [0.0f32; 4];
+            for py in y0..y1 {
+                for px in x0..x1 {
+                    let [r, g, b, a] = image.pixel(px, py);
+                    let alpha = a as f32;
+                    sum[0] += r as f32 * alpha;
+                    sum[1] += g as f32 * alpha;
+                    sum[2] += b as f32 * alpha;
+                    sum[3] += alpha;
+                }
+            }
+
+            let count = ((x1 - x0) * (y1 - y0)) as f32;
+            if sum[3] > 0.0 {
+                data.extend_from_slice(&[
+                    (sum[0] / sum[3]).round() as u8,
+                    (sum[1] / sum[3]).round() as u8,
+                    (sum[2] / sum[3]).round() as u8,
+                    (sum[3] / count).round() as u8,
+                ]);
+            } else {
+                data.extend_from_slice(&[0, 0, 0, 0]);
+            }
+        }
+    }
+
+    Image::new(width, height, data)
+}
+
+/// Rasterized glyphs kept across frames, for renderers that draw masks
+/// directly.
+#[derive(Default)]
+pub struct GlyphCache {
+    glyphs: HashMap<GlyphKey, Option<RasterizedGlyph>>,
+}
+
+impl GlyphCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&mut self, font: &Font, key: GlyphKey) -> Option<&RasterizedGlyph> {
+        self.glyphs
+            .entry(key)
+            .or_insert_with(|| rasterize_glyph(font, key))
+            .as_ref()
+    }
+
+    pub fn clear(&mut self) {
+        self.glyphs.clear();
+    }
+}
+
+/// Feeds font outlines into the rasterizer, flipping y and moving the
+/// glyph's bounding box to the origin.
+struct OutlineRasterizer {
+    rasterizer: Rasterizer,
+    transform: (f32, f32, f32),
+    start: Point,
+    last: Point,
+}
+
+impl OutlineRasterizer {
+    fn map(&self, x: f32, y: f32) -> Point {
+        let (scale, dx, top) = self.transform;
+        point(x * scale + dx, top - y * scale)
+    }
+}
+
+impl rustybuzz::ttf_parser::OutlineBuilder for OutlineRasterizer {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.map(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.map(x, y);
+        self.rasterizer.draw_line(self.last, p);
+        self.last = p;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p1, p) = (self.map(x1, y1), self.map(x, y));
+        self.rasterizer.draw_quad(self.last, p1, p);
+        self.last = p;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p1, p2, p) = (self.map(x1, y1), self.map(x2, y2), self.map(x, y));
+        self.rasterizer.draw_cubic(self.last, p1, p2, p);
+        self.last = p;
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.rasterizer.draw_line(self.last, self.start);
+        }
+        self.last = self.start;
+    }
+}
@@ -0,0 +1,304 @@
+use std::ops::Range;
+
+use unicode_bidi::{BidiInfo, Level};
+use unicode_script::{Script, UnicodeScript};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::fallback::FontCollection;
+use super::font::Font;
+
+/// A positioned glyph, in pixels relative to the pen position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub glyph_id: u16,
+    /// Byte offset in the source text of the first character this glyph
+    /// belongs to.
+    pub cluster: u32,
+    pub x_advance: f32,
+    pub x_offset: f32,
+    pub y_offset: f32,
+}
+
+/// Glyphs of one font, script and direction, in visual order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedRun {
+    pub font: Font,
+    /// Byte range of the source text.
+    pub range: Range<usize>,
+    /// Bidi embedding level; odd levels are right-to-left.
+    pub level: u8,
+    pub glyphs: Vec<ShapedGlyph>,
+    pub width: f32,
+}
+
+impl ShapedRun {
+    pub fn is_rtl(&self) -> bool {
+        self.level % 2 == 1
+    }
+}
+
+/// A single line of text converted to glyphs, ready to draw.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShapedText {
+    pub text: String,
+    pub size: f32,
+    /// Runs in visual order, left to right.
+    pub runs: Vec<ShapedRun>,
+    /// Sum of the glyph advances.
+    pub width: f32,
+    /// Largest ascent of the fonts used.
+    pub ascent: f32,
+    /// Largest descent of the fonts used.
+    pub descent: f32,
+}
+
+impl ShapedText {
+    pub fn height(&self) -> f32 {
+        self.ascent + self.descent
+    }
+
+    /// Every glyph with its font, in visual order.
+    pub fn glyphs(&self) -> impl Iterator<Item = (&Font, &ShapedGlyph)> {
+        self.runs
+            .iter()
+            .flat_map(|run| run.glyphs.iter().map(move |glyph| (&run.font, glyph)))
+    }
+
+    /// The same line at `factor` times the size, e.g. for a high-DPI target.
+    pub fn scaled(&self, factor: f32) -> ShapedText {
+        ShapedText {
+            text: self.text.clone(),
+            size: self.size * factor,
+            runs: self
+                .runs
+                .iter()
+                .map(|run| ShapedRun {
+                    glyphs: run
+                        .glyphs
+                        .iter()
+                        .map(|glyph| ShapedGlyph {
+                            x_advance: glyph.x_advance * factor,
+                            x_offset: glyph.x_offset * factor,
+                            y_offset: glyph.y_offset * factor,
+                            ..*glyph
+                        })
+                        .collect(),
+                    width: run.width * factor,
+                    ..run.clone()
+                })
+                .collect(),
+            width: self.width * factor,
+            ascent: self.ascent * factor,
+            descent: self.descent * factor,
+        }
+    }
+}
+
+/// Shapes `text` as a single line at `size` pixels per em.
+///
+/// The text is split into runs of uniform direction, script and font:
+/// directions come from the Unicode bidi algorithm, and fonts are chosen
+/// per grapheme cluster from `fonts`' fallback chains. Each run is shaped
+/// on its own and the runs are then reordered for display.
+pub fn shape(text: &str, fonts: &FontCollection, size: f32) -> ShapedText {
+    let faces: Vec<rustybuzz::Face> = fonts.fonts().iter().map(|font| font.face()).collect();
+
+    let mut runs: Vec<ShapedRun> = itemize(text, fonts, &faces)
+        .into_iter()
+        .map(|item| shape_item(text, &item, fonts, &faces, size))
+        .collect();
+
+    let levels: Vec<Level> = runs
+        .iter()
+        .map(|run| Level::new(run.level).unwrap_or_else(|_| Level::ltr()))
+        .collect();
+    let order = BidiInfo::reorder_visual(&levels);
+    let mut slots: Vec<Option<ShapedRun>> = runs.drain(..).map(Some).collect();
+    let runs: Vec<ShapedRun> = order
+        .into_iter()
+        .filter_map(|index| slots[index].take())
+        .collect();
+
+    let mut ascent = 0.0f32;
+    let mut descent = 0.0f32;
+    for font in std::iter::once(fonts.primary()).chain(runs.iter().map(|run| &run.font)) {
+        let metrics = font.metrics(size);
+        ascent = ascent.max(metrics.ascent);
+        descent = descent.max(metrics.descent);
+    }
+
+    ShapedText {
+        text: text.to_string(),
+        size,
+        width: runs.iter().map(|run| run.width).sum(),
+        runs,
+        ascent,
+        descent,
+    }
+}
+
+/// A logical run to be shaped as one unit.
+struct Item {
+    range: Range<usize>,
+    level: Level,
+    script: Script,
+    font: usize,
+}
+
+/// Splits `text` into runs of equal bidi level, script and font, in
+/// logical order.
+fn itemize(text: &str, fonts: &FontCollection, faces: &[rustybuzz::Face]) -> Vec<Item> {
+    if text.is_empty() {
+        return Vec::new();
+    }
+
+    let bidi = BidiInfo::new(text, None);
+
+    // Characters such as spaces and punctuation take the script of the text
+    // around them; leading ones take the first real script.
+    let mut script = text
+        .chars()
+        .map(|c| c.script())
+        .find(|s| !matches!(s, Script::Common | Script::Inherited | Script::Unknown))
+        .unwrap_or(Script::Common);
+
+    let mut items: Vec<Item> = Vec::new();
+    for (start, cluster) in text.grapheme_indices(true) {
+        if let Some(s) = cluster
+            .chars()
+            .map(|c| c.script())
+            .find(|s| !matches!(s, Script::Common | Script::Inherited | Script::Unknown))
+        {
+            script = s;
+        }
+
+        let level = bidi.levels[start];
+        let font = fonts.select(faces, cluster, script);
+        let end = start + cluster.len();
+
+        match items.last_mut() {
+            Some(item) if item.level == level && item.script == script && item.font == font => {
+                item.range.end = end;
+            }
+            _ => items.push(Item {
+                range: start..end,
+                level,
+                script,
+                font,
+            }),
+        }
+    }
+
+    items
+}
+
+fn shape_item(
+    text: &str,
+    item: &Item,
+    fonts: &FontCollection,
+    faces: &[rustybuzz::Face],
+    size: f32,
+) -> ShapedRun {
+    let font = &fonts.fonts()[item.font];
+    let face = &faces[item.font];
+    let scale = font.scale(face, size);
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(&text[item.range.clone()]);
+    buffer.guess_segment_properties();
+    buffer.set_direction(if item.level.is_rtl() {
+        rustybuzz::Direction::RightToLeft
+    } else {
+        rustybuzz::Direction::LeftToRight
+    });
+    let tag = rustybuzz::ttf_parser::Tag::from_bytes_lossy(item.script.short_name().as_bytes());
+    if let Some(script) = rustybuzz::Script::from_iso15924_tag(tag) {
+        buffer.set_script(script);
+    }
+
+    let output = rustybuzz::shape(face, &[], buffer);
+    let glyphs: Vec<ShapedGlyph> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            glyph_id: info.glyph_id as u16,
+            cluster: item.range.start as u32 + info.cluster,
+            x_advance: position.x_advance as f32 * scale,
+            x_offset: position.x_offset as f32 * scale,
+            y_offset: position.y_offset as f32 * scale,
+        })
+        .collect();
+
+    ShapedRun {
+        font: font.clone(),
+        range: item.range.clone(),
+        level: item.level.number(),
+        width: glyphs.iter().map(|glyph| glyph.x_advance).sum(),
+        glyphs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::test_helpers::stub_font;
+
+    fn ranges(text: &ShapedText) -> Vec<Range<usize>> {
+        text.runs.iter().map(|run| run.range.clone()).collect()
+    }
+
+    #[test]
+    fn mixed_directions_are_reordered_visually() {
+        let text = "abc אבג 123";
+        let shaped = shape(text, &FontCollection::default(), 16.0);
+
+        // Numbers after right-to-left text stay left-to-right but are
+        // placed before it.
+        assert_eq!(ranges(&shaped), [0..4, 11..14, 4..11]);
+        let rtl: Vec<bool> = shaped.runs.iter().map(ShapedRun::is_rtl).collect();
+        assert_eq!(rtl, [false, false, true]);
+
+        // Glyphs of a right-to-left run come last character first.
+        let clusters: Vec<u32> = shaped.runs[2]
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.cluster)
+            .collect();
+        assert_eq!(clusters, [10, 8, 6, 4]);
+        assert_eq!(
+            shaped.width,
+            shaped.runs.iter().map(|run| run.width).sum::<f32>()
+        );
+    }
+
+    #[test]
+    fn runs_split_at_script_and_font_changes() {
+        let han = stub_font(&['中', '文']);
+        let emoji = stub_font(&['👨', '👩', '👧']);
+        let fonts = FontCollection::default()
+            .with_script_fallback(Script::Han, han.clone())
+            .with_emoji_fallback(emoji.clone());
+
+        let text = "ab 中文 👨\u{200D}👩\u{200D}👧!";
+        let shaped = shape(text, &fonts, 16.0);
+        let fonts_used: Vec<&Font> = shaped.runs.iter().map(|run| &run.font).collect();
+        let primary = fonts.primary();
+        assert_eq!(fonts_used, [primary, &han, primary, &emoji, primary]);
+        // Spaces belong to the run before them.
+        assert_eq!(ranges(&shaped), [0..3, 3..9, 9..10, 10..28, 28..29]);
+    }
+
+    #[test]
+    fn neutral_characters_take_the_script_around_them() {
+        let fonts = FontCollection::default();
+        let faces: Vec<_> = fonts.fonts().iter().map(|font| font.face()).collect();
+        let items: Vec<(Range<usize>, Script)> = itemize("(abc) «中文».", &fonts, &faces)
+            .into_iter()
+            .map(|item| (item.range, item.script))
+            .collect();
+        // Leading punctuation takes the first real script, later
+        // punctuation the one before it.
+        assert_eq!(items, [(0..8, Script::Latin), (8..17, Script::Han)]);
+    }
+}
@@ -0,0 +1,73 @@
+//! Fixtures shared by the text tests.
+
+use super::font::Font;
+
+/// Builds a font with an empty glyph for each of `chars` and nothing else,
+/// to stand in for fallback fonts with known coverage.
+pub fn stub_font(chars: &[char]) -> Font {
+    let mut chars = chars.to_vec();
+    chars.sort_unstable();
+    let glyph_count = chars.len() as u16 + 1;
+
+    let mut cmap = Vec::new();
+    cmap.extend(0u16.to_be_bytes()); // version
+    cmap.extend(1u16.to_be_bytes()); // encoding records
+    cmap.extend(3u16.to_be_bytes()); // Windows
+    cmap.extend(10u16.to_be_bytes()); // full Unicode
+    cmap.extend(12u32.to_be_bytes()); // subtable offset
+    cmap.extend(12u16.to_be_bytes()); // format
+    cmap.extend(0u16.to_be_bytes());
+    cmap.extend((16 + 12 * chars.len() as u32).to_be_bytes());
+    cmap.extend(0u32.to_be_bytes()); // language
+    cmap.extend((chars.len() as u32).to_be_bytes());
+    for (glyph, &c) in (1u32..).zip(&chars) {
+        cmap.extend((c as u32).to_be_bytes());
+        cmap.extend((c as u32).to_be_bytes());
+        cmap.extend(glyph.to_be_bytes());
+    }
+
+    let mut head = vec![0; 54];
+    head[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    head[12..16].copy_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+    head[18..20].copy_from_slice(&1000u16.to_be_bytes()); // units per em
+
+    let mut hhea = vec![0; 36];
+    hhea[0..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    hhea[4..6].copy_from_slice(&800i16.to_be_bytes()); // ascender
+    hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes()); // descender
+    hhea[34..36].copy_from_slice(&glyph_count.to_be_bytes());
+
+    let hmtx: Vec<u8> = (0..glyph_count)
+        .flat_map(|_| [500u16.to_be_bytes(), 0u16.to_be_bytes()].concat())
+        .collect();
+
+    let mut maxp = 0x0000_5000u32.to_be_bytes().to_vec();
+    maxp.extend(glyph_count.to_be_bytes());
+
+    // Sorted by tag.
+    let tables = [
+        (b"cmap", cmap),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"hmtx", hmtx),
+        (b"maxp", maxp),
+    ];
+    let mut data = Vec::new();
+    data.extend(0x0001_0000u32.to_be_bytes());
+    data.extend((tables.len() as u16).to_be_bytes());
+    data.extend([0; 6]); // search hints, unused by the parser
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, table) in &tables {
+        data.extend(*tag);
+        data.extend(0u32.to_be_bytes()); // checksum
+        data.extend((offset as u32).to_be_bytes());
+        data.extend((table.len() as u32).to_be_bytes());
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in &tables {
+        data.extend(table);
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    Font::from_bytes(data, 0).expect("stub font is valid")
+}
@@ -0,0 +1,117 @@
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{Rect, Size};
+
+use super::event::{Event, EventResult};
+
+/// The common interface of every element in a widget tree.
+///
+/// A frame runs in three passes over the tree: `measure` and `layout` top
+/// down to assign rectangles, then [`paint_tree`] to draw. Input is routed
+/// with [`dispatch_event`], and [`mount_tree`] / [`unmount_tree`] bracket
+/// the time a widget is part of a live tree.
+pub trait Widget {
+    /// Returns the size the widget would like, given the space available.
+    fn measure(&mut self, available: Size) -> Size;
+
+    /// Assigns the widget its final rectangle. Containers position their
+    /// children here.
+    fn layout(&mut self, bounds: Rect);
+
+    /// The rectangle assigned by the last `layout`.
+    fn bounds(&self) -> Rect;
+
+    /// Draws the widget itself; children are painted afterwards by
+    /// [`paint_tree`].
+    fn paint(&self, canvas: &mut dyn Canvas);
+
+    /// Handles an event that was not consumed by any child.
+    fn event(&mut self, _event: &Event) -> EventResult {
+        EventResult::Ignored
+    }
+
+    /// Whether the widget wants pointer events even outside its bounds,
+    /// such as while a press that started on it is held.
+    fn has_pointer_capture(&self) -> bool {
+        false
+    }
+
+    fn children(&self) -> Vec<&dyn Widget> {
+        Vec::new()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+        Vec::new()
+    }
+
+    /// Called when the widget becomes part of a live tree.
+    fn mount(&mut self) {}
+
+    /// Called before the widget is removed from a live tree.
+    fn unmount(&mut self) {}
+
+    /// Labels the widget's draws in graphics debuggers. The type name by
+    /// default.
+    fn debug_name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Measures and lays out `root` to fill `size`.
+pub fn layout_root(root: &mut dyn Widget, size: Size) {
+    root.measure(size);
+    root.layout(Rect::from_size(size));
+}
+
+/// Paints `widget` and then its children, in order. Each widget's draws,
+/// including its children's, are labelled with its [`Widget::debug_name`].
+pub fn paint_tree(widget: &dyn Widget, canvas: &mut dyn Canvas) {
+    canvas.push_label(widget.debug_name());
+    widget.paint(canvas);
+    for child in widget.children() {
+        paint_tree(child, canvas);
+    }
+    canvas.pop_label();
+}
+
+/// Routes `event` through the tree, deepest and topmost widget first.
+///
+/// Pointer events only reach widgets whose bounds contain the pointer, and
+/// widgets holding pointer capture along with their ancestors.
+/// Propagation stops at the first widget that handles the event.
+pub fn dispatch_event(widget: &mut dyn Widget, event: &Event) -> EventResult {
+    if let Some((x, y)) = event.position() {
+        if !widget.bounds().contains(x, y) && !holds_pointer_capture(widget) {
+            return EventResult::Ignored;
+        }
+    }
+
+    // Later children paint on top, so they get the first chance to respond.
+    for child in widget.children_mut().into_iter().rev() {
+        if dispatch_event(child, event) == EventResult::Handled {
+            return EventResult::Handled;
+        }
+    }
+
+    widget.event(event)
+}
+
+/// Whether `widget` or any of its descendants has pointer capture.
+fn holds_pointer_capture(widget: &dyn Widget) -> bool {
+    widget.has_pointer_capture() || widget.children().into_iter().any(holds_pointer_capture)
+}
+
+/// Mounts `widget` and then its descendants.
+pub fn mount_tree(widget: &mut dyn Widget) {
+    widget.mount();
+    for child in widget.children_mut() {
+        mount_tree(child);
+    }
+}
+
+/// Unmounts the descendants of `widget` and then `widget` itself.
+pub fn unmount_tree(widget: &mut dyn Widget) {
+    for child in widget.children_mut() {
+        unmount_tree(child);
+    }
+    widget.unmount();
+}
@@ -1,50 +1,295 @@
-use ash::vk;
-use crate::renderer::pipeline::Pipeline;
-use crate::renderer::swapchain::Swapchain;
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{Color, Rect, Size};
+use crate::text::fallback::FontCollection;
+use crate::text::paragraph::{layout_paragraph, Paragraph, ParagraphStyle, TextAlign, WrapMode};
+
+use super::base::Widget;
+use super::event::{Event, EventResult, PointerButton};
+
+/// Default label size, in pixels per em.
+const LABEL_SIZE: f32 = 16.0;
+
+/// Space kept between the label and the left and right edges.
+const LABEL_PADDING: f32 = 8.0;
 
 pub struct Button {
-    pub x: f32,
-    pub y: f32,
-    pub width: f32,
-    pub height: f32,
-    pub label: String,
+    /// The size reported by `measure`.
+    pub preferred_size: Size,
+    label: String,
+    fonts: FontCollection,
+    label_style: ParagraphStyle,
+    /// The label laid out for the current bounds, or `None` if it is empty.
+    label_layout: Option<Paragraph>,
+    on_click: Option<Box<dyn FnMut()>>,
+    /// Whether a press started on the button and has not been released.
+    /// The button holds pointer capture meanwhile.
+    pressed: bool,
+    bounds: Rect,
 }
 
 impl Button {
+    /// Creates a button at `(x, y)` that prefers to be `width` x `height`.
     pub fn new(x: f32, y: f32, width: f32, height: f32, label: &str) -> Self {
-        Button {
-            x,
-            y,
-            width,
-            height,
+        let mut button = Button {
+            preferred_size: Size::new(width, height),
             label: label.to_string(),
+            fonts: FontCollection::default(),
+            label_style: ParagraphStyle {
+                size: LABEL_SIZE,
+                align: TextAlign::Center,
+                wrap: WrapMode::None,
+                max_lines: Some(1),
+                ellipsis: true,
+                ..ParagraphStyle::default()
+            },
+            on_click: None,
+            pressed: false,
+            bounds: Rect::new(x, y, width, height),
+            label_layout: None,
+        };
+        button.layout_label();
+        button
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.label = label.to_string();
+        self.layout_label();
+    }
+
+    /// Sets the font, or fallback chain, used for the label.
+    pub fn fonts(mut self, fonts: impl Into<FontCollection>) -> Self {
+        self.fonts = fonts.into();
+        self.layout_label();
+        self
+    }
+
+    pub fn font_size(mut self, font_size: f32) -> Self {
+        self.label_style.size = font_size;
+        self.layout_label();
+        self
+    }
+
+    /// Wraps a label too wide for the button onto as many lines as fit in
+    /// its height, instead of truncating it to one line.
+    pub fn wrap_label(mut self) -> Self {
+        self.label_style.wrap = WrapMode::Word;
+        self.label_style.max_lines = None;
+        self.layout_label();
+        self
+    }
+
+    pub fn label_style(mut self, label_style: ParagraphStyle) -> Self {
+        self.label_style = label_style;
+        self.layout_label();
+        self
+    }
+
+    /// Sets the callback run when the button is clicked.
+    pub fn on_click(mut self, on_click: impl FnMut() + 'static) -> Self {
+        self.on_click = Some(Box::new(on_click));
+        self
+    }
+
+    /// Lays the label out for the current bounds and style.
+    fn layout_label(&mut self) {
+        if self.label.is_empty() {
+            self.label_layout = None;
+            return;
+        }
+
+        // Only as many lines as fit in the button are laid out; the last
+        // one is cut short with an ellipsis if text remains.
+        let line_height = self.label_style.line_height.unwrap_or_else(|| {
+            self.fonts
+                .primary()
+                .metrics(self.label_style.size)
+                .line_height()
+        });
+        let fitting_lines = ((self.bounds.height / line_height).floor() as usize).max(1);
+        let style = ParagraphStyle {
+            max_lines: Some(
+                self.label_style
+                    .max_lines
+                    .map_or(fitting_lines, |max| max.min(fitting_lines)),
+            ),
+            ..self.label_style
+        };
+
+        let width = (self.bounds.width - 2.0 * LABEL_PADDING).max(0.0);
+        self.label_layout = Some(layout_paragraph(&self.label, &self.fonts, &style, width));
+    }
+}
+
+impl Widget for Button {
+    fn measure(&mut self, _available: Size) -> Size {
+        self.preferred_size
+    }
+
+    fn layout(&mut self, bounds: Rect) {
+        if bounds != self.bounds {
+            self.bounds = bounds;
+            self.layout_label();
         }
     }
 
-    pub fn draw(&self, device: &ash::Device, command_buffer: vk::CommandBuffer, pipeline: &Pipeline) {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
         // For simplicity, we will draw the button as a colored rectangle.
         // In a real application, you would have a more complex method here.
-        let vertices: [f32; 12] = [
-            self.x, self.y, 0.0, 1.0, 0.0, 0.0, 1.0,
-            self.x + self.width, self.y, 0.0, 0.0, 1.0, 0.0, 1.0,
-            self.x + self.width, self.y + self.height, 0.0, 0.0, 0.0, 1.0, 1.0,
-            self.x, self.y + self.height, 0.0, 1.0, 1.0, 0.0, 1.0,
-        ];
-
-        // Bind the pipeline
-        unsafe {
-            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline.graphics_pipeline);
-            // In a real application, you would bind the vertex buffer here
-            device.cmd_draw(command_buffer, 6, 1, 0, 0);
+        canvas.fill_quad(
+            self.bounds(),
+            [
+                Color::rgb(1.0, 0.0, 0.0),
+                Color::rgb(0.0, 1.0, 0.0),
+                Color::rgb(0.0, 0.0, 1.0),
+                Color::rgb(1.0, 1.0, 0.0),
+            ],
+        );
+
+        let Some(label) = &self.label_layout else {
+            return;
+        };
+        let y = self.bounds.y + (self.bounds.height - label.height) / 2.0;
+
+        canvas.push_clip(self.bounds);
+        label.paint(canvas, self.bounds.x + LABEL_PADDING, y, Color::WHITE);
+        canvas.pop_clip();
+    }
+
+    fn event(&mut self, event: &Event) -> EventResult {
+        match event {
+            Event::PointerDown {
+                button: PointerButton::Primary,
+                ..
+            } => {
+                self.pressed = true;
+                EventResult::Handled
+            }
+            // Delivered through pointer capture even when released outside
+            // the button, which then only ends the press.
+            Event::PointerUp {
+                x,
+                y,
+                button: PointerButton::Primary,
+            } if self.pressed => {
+                self.pressed = false;
+                if self.bounds.contains(*x, *y) {
+                    if let Some(on_click) = self.on_click.as_mut() {
+                        on_click();
+                    }
+                }
+                EventResult::Handled
+            }
+            Event::PointerLeave => {
+                self.pressed = false;
+                EventResult::Ignored
+            }
+            _ => EventResult::Ignored,
         }
     }
 
-    pub fn handle_click(&self, x: f32, y: f32) -> bool {
-        if x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height {
-            println!("Button '{}' clicked!", self.label);
-            true
-        } else {
-            false
+    fn has_pointer_capture(&self) -> bool {
+        self.pressed
+    }
+
+    fn unmount(&mut self) {
+        self.pressed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::widget::base::dispatch_event;
+
+    fn counting_button() -> (Button, Rc<Cell<u32>>) {
+        let clicks = Rc::new(Cell::new(0));
+        let counter = clicks.clone();
+        let button = Button::new(0.0, 0.0, 100.0, 40.0, "OK")
+            .on_click(move || counter.set(counter.get() + 1));
+        (button, clicks)
+    }
+
+    fn down(x: f32, y: f32) -> Event {
+        Event::PointerDown {
+            x,
+            y,
+            button: PointerButton::Primary,
         }
     }
+
+    fn up(x: f32, y: f32) -> Event {
+        Event::PointerUp {
+            x,
+            y,
+            button: PointerButton::Primary,
+        }
+    }
+
+    #[test]
+    fn measure_reports_preferred_size_after_layout() {
+        let mut button = Button::new(0.0, 0.0, 100.0, 40.0, "OK");
+        button.layout(Rect::new(5.0, 5.0, 300.0, 10.0));
+
+        assert_eq!(
+            button.measure(Size::new(500.0, 500.0)),
+            Size::new(100.0, 40.0)
+        );
+        assert_eq!(button.bounds(), Rect::new(5.0, 5.0, 300.0, 10.0));
+    }
+
+    #[test]
+    fn click_inside_fires() {
+        let (mut button, clicks) = counting_button();
+        dispatch_event(&mut button, &down(50.0, 20.0));
+        dispatch_event(&mut button, &up(60.0, 20.0));
+
+        assert_eq!(clicks.get(), 1);
+        assert!(!button.has_pointer_capture());
+    }
+
+    #[test]
+    fn release_outside_ends_press_without_click() {
+        let (mut button, clicks) = counting_button();
+        dispatch_event(&mut button, &down(50.0, 20.0));
+        assert!(button.has_pointer_capture());
+
+        assert_eq!(
+            dispatch_event(&mut button, &up(500.0, 20.0)),
+            EventResult::Handled
+        );
+        assert_eq!(clicks.get(), 0);
+        assert!(!button.has_pointer_capture());
+
+        // A later release inside is not a click without a new press.
+        dispatch_event(&mut button, &up(50.0, 20.0));
+        assert_eq!(clicks.get(), 0);
+    }
+
+    #[test]
+    fn label_is_laid_out_again_on_resize_and_change() {
+        let mut button = Button::new(0.0, 0.0, 200.0, 40.0, "A long label");
+        let wide = button.label_layout.clone().unwrap();
+        assert!(!wide.truncated);
+
+        button.layout(Rect::new(0.0, 0.0, 40.0, 40.0));
+        assert!(button.label_layout.as_ref().unwrap().truncated);
+
+        button.layout(Rect::new(0.0, 0.0, 200.0, 40.0));
+        assert_eq!(button.label_layout, Some(wide));
+
+        button.set_label("");
+        assert_eq!(button.label(), "");
+        assert_eq!(button.label_layout, None);
+    }
 }
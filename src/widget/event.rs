@@ -0,0 +1,38 @@
+/// A pointer button, independent of the windowing backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerButton {
+    Primary,
+    Secondary,
+    Middle,
+}
+
+/// An input event delivered to widgets, in logical pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    PointerMove { x: f32, y: f32 },
+    PointerDown { x: f32, y: f32, button: PointerButton },
+    PointerUp { x: f32, y: f32, button: PointerButton },
+    /// The pointer left the window.
+    PointerLeave,
+    /// Text typed by the user.
+    Text(String),
+}
+
+impl Event {
+    /// The pointer position, for events that have one.
+    pub fn position(&self) -> Option<(f32, f32)> {
+        match *self {
+            Event::PointerMove { x, y }
+            | Event::PointerDown { x, y, .. }
+            | Event::PointerUp { x, y, .. } => Some((x, y)),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a widget consumed an event. Handled events stop propagating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+    Handled,
+    Ignored,
+}
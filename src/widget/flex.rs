@@ -0,0 +1,183 @@
+use crate::layout::flex::{
+    compute_flex, measure_flex, AlignContent, AlignItems, FlexDirection, FlexItem, FlexStyle,
+    FlexWrap, JustifyContent,
+};
+use crate::layout::style::Edges;
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{Rect, Size};
+
+use super::base::Widget;
+
+/// A container that arranges its children with flexbox.
+pub struct Flex {
+    pub style: FlexStyle,
+    children: Vec<(Box<dyn Widget>, FlexItem)>,
+    bounds: Rect,
+}
+
+impl Flex {
+    pub fn new(style: FlexStyle) -> Self {
+        Flex {
+            style,
+            children: Vec::new(),
+            bounds: Rect::default(),
+        }
+    }
+
+    pub fn row() -> Self {
+        Self::new(FlexStyle::default())
+    }
+
+    pub fn column() -> Self {
+        Self::new(FlexStyle {
+            direction: FlexDirection::Column,
+            ..FlexStyle::default()
+        })
+    }
+
+    pub fn direction(mut self, direction: FlexDirection) -> Self {
+        self.style.direction = direction;
+        self
+    }
+
+    pub fn wrap(mut self, wrap: FlexWrap) -> Self {
+        self.style.wrap = wrap;
+        self
+    }
+
+    pub fn justify_content(mut self, justify_content: JustifyContent) -> Self {
+        self.style.justify_content = justify_content;
+        self
+    }
+
+    pub fn align_items(mut self, align_items: AlignItems) -> Self {
+        self.style.align_items = align_items;
+        self
+    }
+
+    pub fn align_content(mut self, align_content: AlignContent) -> Self {
+        self.style.align_content = align_content;
+        self
+    }
+
+    /// Sets both the row and column gap.
+    pub fn gap(mut self, gap: f32) -> Self {
+        self.style.row_gap = gap;
+        self.style.column_gap = gap;
+        self
+    }
+
+    pub fn padding(mut self, padding: Edges) -> Self {
+        self.style.padding = padding;
+        self
+    }
+
+    /// Adds a child that keeps its measured size.
+    pub fn with_child(self, child: impl Widget + 'static) -> Self {
+        self.with_flex_child(child, FlexItem::default())
+    }
+
+    /// Adds a child with explicit flex item properties.
+    pub fn with_flex_child(mut self, child: impl Widget + 'static, item: FlexItem) -> Self {
+        self.children.push((Box::new(child), item));
+        self
+    }
+
+    fn items(&self) -> Vec<FlexItem> {
+        self.children.iter().map(|(_, item)| *item).collect()
+    }
+
+    fn measure_children(&mut self, available: Size) -> Vec<Size> {
+        let inner = Size::new(
+            (available.width - self.style.padding.horizontal()).max(0.0),
+            (available.height - self.style.padding.vertical()).max(0.0),
+        );
+        self.children
+            .iter_mut()
+            .map(|(child, _)| child.measure(inner))
+            .collect()
+    }
+}
+
+impl Widget for Flex {
+    fn measure(&mut self, available: Size) -> Size {
+        let content = self.measure_children(available);
+        measure_flex(&self.style, &self.items(), &content, available)
+    }
+
+    fn layout(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+
+        let content = self.measure_children(bounds.size());
+        let rects = compute_flex(&self.style, &self.items(), &content, bounds);
+        for ((child, _), rect) in self.children.iter_mut().zip(rects) {
+            child.layout(rect);
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {}
+
+    fn children(&self) -> Vec<&dyn Widget> {
+        self.children
+            .iter()
+            .map(|(child, _)| child.as_ref() as &dyn Widget)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+        self.children
+            .iter_mut()
+            .map(|(child, _)| child.as_mut() as &mut dyn Widget)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widget::button::Button;
+
+    fn child_bounds(flex: &Flex) -> Vec<Rect> {
+        flex.children().iter().map(|child| child.bounds()).collect()
+    }
+
+    #[test]
+    fn relayout_after_resize_restores_sizes() {
+        let mut flex = Flex::row()
+            .with_child(Button::new(0.0, 0.0, 40.0, 20.0, "A"))
+            .with_child(Button::new(0.0, 0.0, 40.0, 20.0, "B"));
+
+        let wide = Rect::new(0.0, 0.0, 200.0, 20.0);
+        flex.measure(wide.size());
+        flex.layout(wide);
+        let before = child_bounds(&flex);
+        assert_eq!(
+            before,
+            [
+                Rect::new(0.0, 0.0, 40.0, 20.0),
+                Rect::new(40.0, 0.0, 40.0, 20.0)
+            ]
+        );
+
+        // Too narrow: both children shrink.
+        let narrow = Rect::new(0.0, 0.0, 60.0, 20.0);
+        flex.measure(narrow.size());
+        flex.layout(narrow);
+        assert_eq!(
+            child_bounds(&flex),
+            [
+                Rect::new(0.0, 0.0, 30.0, 20.0),
+                Rect::new(30.0, 0.0, 30.0, 20.0)
+            ]
+        );
+
+        flex.measure(wide.size());
+        flex.layout(wide);
+        assert_eq!(child_bounds(&flex), before);
+        assert_eq!(flex.measure(wide.size()), Size::new(80.0, 20.0));
+    }
+}
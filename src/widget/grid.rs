@@ -0,0 +1,122 @@
+use crate::layout::grid::{
+    compute_grid, measure_grid, GridAutoFlow, GridItem, GridStyle, TrackSize,
+};
+use crate::layout::style::Edges;
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{Rect, Size};
+
+use super::base::Widget;
+
+/// A container that arranges its children on a CSS-style grid.
+pub struct Grid {
+    pub style: GridStyle,
+    children: Vec<(Box<dyn Widget>, GridItem)>,
+    bounds: Rect,
+}
+
+impl Grid {
+    pub fn new(columns: Vec<TrackSize>, rows: Vec<TrackSize>) -> Self {
+        Grid {
+            style: GridStyle {
+                columns,
+                rows,
+                ..GridStyle::default()
+            },
+            children: Vec::new(),
+            bounds: Rect::default(),
+        }
+    }
+
+    /// Names areas of the grid, one string per row, e.g.
+    /// `["header header", "sidebar main"]`.
+    pub fn areas(mut self, areas: &[&str]) -> Self {
+        self.style.areas = areas.iter().map(|row| row.to_string()).collect();
+        self
+    }
+
+    pub fn auto_flow(mut self, auto_flow: GridAutoFlow) -> Self {
+        self.style.auto_flow = auto_flow;
+        self
+    }
+
+    pub fn auto_rows(mut self, size: TrackSize) -> Self {
+        self.style.auto_rows = size;
+        self
+    }
+
+    pub fn auto_columns(mut self, size: TrackSize) -> Self {
+        self.style.auto_columns = size;
+        self
+    }
+
+    /// Sets both the row and column gap.
+    pub fn gap(mut self, gap: f32) -> Self {
+        self.style.row_gap = gap;
+        self.style.column_gap = gap;
+        self
+    }
+
+    pub fn padding(mut self, padding: Edges) -> Self {
+        self.style.padding = padding;
+        self
+    }
+
+    /// Adds an auto-placed child occupying a single cell.
+    pub fn with_child(self, child: impl Widget + 'static) -> Self {
+        self.with_grid_child(child, GridItem::default())
+    }
+
+    /// Adds a child with explicit placement.
+    pub fn with_grid_child(mut self, child: impl Widget + 'static, item: GridItem) -> Self {
+        self.children.push((Box::new(child), item));
+        self
+    }
+
+    fn items(&self) -> Vec<GridItem> {
+        self.children.iter().map(|(_, item)| item.clone()).collect()
+    }
+
+    fn measure_children(&mut self, available: Size) -> Vec<Size> {
+        self.children
+            .iter_mut()
+            .map(|(child, _)| child.measure(available))
+            .collect()
+    }
+}
+
+impl Widget for Grid {
+    fn measure(&mut self, available: Size) -> Size {
+        let content = self.measure_children(available);
+        measure_grid(&self.style, &self.items(), &content)
+    }
+
+    fn layout(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+
+        let content = self.measure_children(bounds.size());
+        let rects = compute_grid(&self.style, &self.items(), &content, bounds);
+        for ((child, _), rect) in self.children.iter_mut().zip(rects) {
+            child.layout(rect);
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {}
+
+    fn children(&self) -> Vec<&dyn Widget> {
+        self.children
+            .iter()
+            .map(|(child, _)| child.as_ref() as &dyn Widget)
+            .collect()
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut dyn Widget> {
+        self.children
+            .iter_mut()
+            .map(|(child, _)| child.as_mut() as &mut dyn Widget)
+            .collect()
+    }
+}
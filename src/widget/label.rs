@@ -0,0 +1,130 @@
+use crate::renderer::canvas::Canvas;
+use crate::renderer::primitive::{Color, Rect, Size};
+use crate::text::fallback::FontCollection;
+use crate::text::paragraph::{layout_paragraph, Paragraph, ParagraphStyle};
+
+use super::base::Widget;
+
+/// A block of read-only text that wraps to the width it is given.
+pub struct Label {
+    text: String,
+    pub color: Color,
+    fonts: FontCollection,
+    style: ParagraphStyle,
+    bounds: Rect,
+    /// The text laid out, and the width it was laid out for.
+    paragraph: Option<(f32, Paragraph)>,
+}
+
+impl Label {
+    pub fn new(text: &str) -> Self {
+        Label {
+            text: text.to_string(),
+            color: Color::WHITE,
+            fonts: FontCollection::default(),
+            style: ParagraphStyle::default(),
+            bounds: Rect::default(),
+            paragraph: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, which is shown from the next layout on.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.paragraph = None;
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn fonts(mut self, fonts: impl Into<FontCollection>) -> Self {
+        self.fonts = fonts.into();
+        self.paragraph = None;
+        self
+    }
+
+    pub fn style(mut self, style: ParagraphStyle) -> Self {
+        self.style = style;
+        self.paragraph = None;
+        self
+    }
+
+    /// The text laid out `width` wide, reusing the last layout if it was
+    /// for the same width.
+    fn layout_text(&mut self, width: f32) -> &Paragraph {
+        if !matches!(&self.paragraph, Some((laid_out, _)) if *laid_out == width) {
+            let paragraph = layout_paragraph(&self.text, &self.fonts, &self.style, width);
+            self.paragraph = Some((width, paragraph));
+        }
+        &self.paragraph.as_ref().unwrap().1
+    }
+}
+
+impl Widget for Label {
+    /// Wraps to the available width and reports the size of the result.
+    fn measure(&mut self, available: Size) -> Size {
+        self.layout_text(available.width).size()
+    }
+
+    fn layout(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        self.layout_text(bounds.width);
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        let Some((_, paragraph)) = &self.paragraph else {
+            return;
+        };
+
+        canvas.push_clip(self.bounds);
+        paragraph.paint(canvas, self.bounds.x, self.bounds.y, self.color);
+        canvas.pop_clip();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn laid_out_width(label: &Label) -> Option<f32> {
+        label.paragraph.as_ref().map(|(width, _)| *width)
+    }
+
+    #[test]
+    fn layout_reuses_the_measured_paragraph() {
+        let mut label = Label::new("Some words to wrap");
+        let size = label.measure(Size::new(60.0, 100.0));
+        let measured = label.paragraph.clone();
+
+        label.layout(Rect::new(0.0, 0.0, 60.0, size.height));
+        assert_eq!(label.paragraph, measured);
+
+        label.layout(Rect::new(0.0, 0.0, 500.0, size.height));
+        assert_eq!(laid_out_width(&label), Some(500.0));
+        assert_eq!(label.paragraph.as_ref().unwrap().1.lines.len(), 1);
+    }
+
+    #[test]
+    fn changing_the_text_lays_it_out_again() {
+        let mut label = Label::new("One");
+        label.layout(Rect::new(0.0, 0.0, 100.0, 20.0));
+
+        label.set_text("Two");
+        assert_eq!(laid_out_width(&label), None);
+        label.layout(Rect::new(0.0, 0.0, 100.0, 20.0));
+        assert_eq!(
+            label.paragraph.as_ref().unwrap().1.lines[0].text.text,
+            "Two"
+        );
+    }
+}
@@ -1 +1,6 @@
 pub mod button;
+pub mod base;
+pub mod event;
+pub mod flex;
+pub mod grid;
+pub mod label;
\ No newline at end of file
@@ -0,0 +1,94 @@
+//! Derive macros for `uplift`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Derives `uplift::renderer::vertex::VertexInput` for a `#[repr(C)]`
+/// struct with named fields, so its binding and attribute descriptions
+/// follow its layout.
+///
+/// Each field becomes one attribute, at locations 0, 1, ... in declaration
+/// order. Its format comes from the field type's `VertexFormat`
+/// implementation.
+#[proc_macro_derive(VertexInput)]
+pub fn derive_vertex_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_vertex_input(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_vertex_input(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "VertexInput cannot be derived for generic types",
+        ));
+    }
+    if !is_repr_c(input)? {
+        return Err(Error::new_spanned(
+            name,
+            "VertexInput requires #[repr(C)], so field offsets are stable",
+        ));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    name,
+                    "VertexInput requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                name,
+                "VertexInput can only be derived for structs",
+            ))
+        }
+    };
+
+    let attributes = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        quote! {
+            ::uplift::renderer::vertex::VertexAttribute {
+                offset: ::core::mem::offset_of!(#name, #ident) as u32,
+                format: <#ty as ::uplift::renderer::vertex::VertexFormat>::FORMAT,
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl ::uplift::renderer::vertex::VertexInput for #name {
+            const ATTRIBUTES: &'static [::uplift::renderer::vertex::VertexAttribute] =
+                &[#(#attributes),*];
+        }
+    })
+}
+
+fn is_repr_c(input: &DeriveInput) -> syn::Result<bool> {
+    let mut repr_c = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.input.peek(syn::token::Paren) {
+                // Skip arguments, as in `align(16)`.
+                let _content;
+                syn::parenthesized!(_content in meta.input);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(repr_c)
+}